@@ -1,7 +1,7 @@
 import { useEffect, useState } from "preact/hooks";
-import { WasmFft } from "wasm-audio";
 import { getAudioContext, getAudioSourceNode } from "./util/audiocontext";
 import { NumericRingBuf } from "./util/ringbuf";
+import { getWasm } from "./util/wasm";
 
 const baseOffset = 0;
 const baseScale = 4;
@@ -164,8 +164,8 @@ export const useCanvasFftVis = (
 
     let stopFlag = false;
 
-    const wasmFft = WasmFft.new();
-    console.log("tests:", wasmFft.test());
+    const wasmFft = getWasm().WasmFft.new();
+    console.info("fft kernel:", wasmFft.fft_kernel());
 
     const analyzer = audioContext.createAnalyser();
     analyzer.fftSize = 1024;
@@ -235,6 +235,15 @@ export const useCanvasFftVis = (
         plotScale: myfftScale,
         enabled: false,
       }),
+
+      new FftBenchmark({
+        callback: (analyzer, i, o) => wasmFft.fft(i, o),
+        name: "auto ",
+        color: "violet",
+        plotOffset: myfftOffset,
+        plotScale: myfftScale,
+        enabled: false,
+      }),
     ];
 
     const refreshUiState = () => {
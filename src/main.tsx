@@ -2,8 +2,8 @@ import { render } from "preact";
 import { App } from "./app";
 import "./index.css";
 
-import init from "wasm-audio";
+import { initWasm } from "./util/wasm";
 
-init().then(() => {
+initWasm().then(() => {
   render(<App />, document.getElementById("app") as HTMLElement);
 });
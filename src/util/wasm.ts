@@ -0,0 +1,38 @@
+/**
+ * Loads the SIMD build of wasm-audio where the browser supports it, and the scalar build
+ * otherwise.
+ */
+
+import type * as WasmAudio from "wasm-audio";
+
+// Smallest module using a SIMD instruction (`i8x16.popcnt`), as in wasm-feature-detect
+const simdProbe = new Uint8Array([
+  0, 97, 115, 109, 1, 0, 0, 0, 1, 5, 1, 96, 0, 1, 123, 3, 2, 1, 0, 10, 10, 1, 8,
+  0, 65, 0, 253, 15, 253, 98, 11,
+]);
+
+export const simdSupported = (): boolean => WebAssembly.validate(simdProbe);
+
+let wasmAudio: typeof WasmAudio | undefined;
+
+export const initWasm = async (): Promise<typeof WasmAudio> => {
+  if (!wasmAudio) {
+    // Both builds are generated from the same crate, so they export the same API
+    const module = simdSupported()
+      ? await import("wasm-audio")
+      : ((await import("wasm-audio-nosimd")) as unknown as typeof WasmAudio);
+
+    await module.default();
+    wasmAudio = module;
+  }
+
+  return wasmAudio;
+};
+
+export const getWasm = (): typeof WasmAudio => {
+  if (!wasmAudio) {
+    throw new Error("wasm-audio used before initWasm() resolved");
+  }
+
+  return wasmAudio;
+};
@@ -0,0 +1,264 @@
+//! Native benchmarks of every FFT implementation in the crate.
+//!
+//! ```text
+//! cargo bench --bench fft -- [FILTER] [--quick] [--save PATH] [--baseline PATH]
+//! ```
+//!
+//! Results are saved as JSON (by default to `target/bench/fft.json`). Passing a previous results
+//! file with `--baseline` adds a column with the change in time per transform.
+
+use std::{
+    hint::black_box,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use rustfft::{num_complex::Complex, FftPlanner};
+use serde::{Deserialize, Serialize};
+use wasm_audio::{
+    cooley_tukey::cooley_tukey_fft,
+    dft::dft,
+    dispatch,
+    siggen::{self, NoiseColor},
+    simd_cooley_tukey::simd_cooley_tukey_fft,
+    simd_cooley_tukey2::simd_cooley_tukey_fft2,
+    simd_cooley_tukey3::simd_cooley_tukey_fft3_complex,
+};
+
+const SIZES: [usize; 11] = [
+    64, 128, 256, 512, 1024, 2048, 4096, 8192, 16384, 32768, 65536,
+];
+
+/// O(n²) kernels are skipped above this size.
+const MAX_SLOW_SIZE: usize = 4096;
+
+type Kernel = fn(&[Complex<f32>], &mut [Complex<f32>]);
+
+struct Bench {
+    name: &'static str,
+    slow: bool,
+    run: Kernel,
+}
+
+fn rustfft(input: &[Complex<f32>], output: &mut [Complex<f32>]) {
+    // Planning is cached per thread, so only the first call at each size pays for it
+    thread_local! {
+        static PLANNER: std::cell::RefCell<FftPlanner<f32>> = std::cell::RefCell::new(FftPlanner::new());
+    }
+
+    let fft = PLANNER.with(|p| p.borrow_mut().plan_fft_forward(input.len()));
+    output.copy_from_slice(input);
+    fft.process(output);
+}
+
+fn benches() -> Vec<Bench> {
+    #[allow(unused_mut)]
+    let mut benches = vec![
+        Bench {
+            name: "dft",
+            slow: true,
+            run: dft,
+        },
+        Bench {
+            name: "cooley_tukey",
+            slow: false,
+            run: cooley_tukey_fft,
+        },
+        Bench {
+            name: "rustfft",
+            slow: false,
+            run: rustfft,
+        },
+        Bench {
+            name: "simd_cooley_tukey",
+            slow: false,
+            run: simd_cooley_tukey_fft,
+        },
+        Bench {
+            name: "simd_cooley_tukey2",
+            slow: false,
+            run: simd_cooley_tukey_fft2,
+        },
+        // Includes converting to and from `Complex4`, as the accuracy report does
+        Bench {
+            name: "simd_cooley_tukey3",
+            slow: false,
+            run: simd_cooley_tukey_fft3_complex,
+        },
+        Bench {
+            name: "dispatch",
+            slow: false,
+            run: dispatch::fft,
+        },
+    ];
+
+    // Only splits transforms above `parallel::CHUNK`
+    #[cfg(feature = "parallel")]
+    benches.push(Bench {
+        name: "parallel",
+        slow: false,
+        run: wasm_audio::parallel::parallel_fft,
+    });
+
+    benches
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Results {
+    results: Vec<Measurement>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Measurement {
+    kernel: String,
+    size: usize,
+    /// Median time per transform
+    ns: f64,
+    ns_per_point: f64,
+    /// Nominal radix-2 flop count, `5 n log2(n)`, per microsecond
+    mflops: f64,
+}
+
+struct Options {
+    filter: Option<String>,
+    quick: bool,
+    save: PathBuf,
+    baseline: Option<PathBuf>,
+}
+
+impl Options {
+    fn parse() -> Self {
+        let mut options = Self {
+            filter: None,
+            quick: false,
+            save: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/bench/fft.json"),
+            baseline: None,
+        };
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                // Passed by `cargo bench`
+                "--bench" => {}
+                "--quick" => options.quick = true,
+                "--save" => options.save = args.next().expect("--save needs a path").into(),
+                "--baseline" => {
+                    options.baseline = Some(args.next().expect("--baseline needs a path").into())
+                }
+                _ if arg.starts_with("--") => panic!("unknown option {arg}"),
+                _ => options.filter = Some(arg),
+            }
+        }
+
+        options
+    }
+}
+
+/// Median time of one call to `f`, in nanoseconds.
+///
+/// Like Criterion, runs a warm-up first to size the batches, then times a number of batches and
+/// takes the median to reject outliers.
+fn measure(mut f: impl FnMut(), quick: bool) -> f64 {
+    let (warm_up, samples, sample_time) = if quick {
+        (Duration::from_millis(10), 5, Duration::from_millis(2))
+    } else {
+        (Duration::from_millis(200), 30, Duration::from_millis(20))
+    };
+
+    let start = Instant::now();
+    let mut calls = 0_u32;
+    while start.elapsed() < warm_up {
+        f();
+        calls += 1;
+    }
+    let per_call = start.elapsed() / calls;
+    let batch = (sample_time.as_nanos() / per_call.as_nanos().max(1)).max(1) as u32;
+
+    let mut times: Vec<f64> = (0..samples)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..batch {
+                f();
+            }
+            start.elapsed().as_nanos() as f64 / batch as f64
+        })
+        .collect();
+
+    times.sort_by(f64::total_cmp);
+    times[times.len() / 2]
+}
+
+fn main() {
+    let options = Options::parse();
+
+    let baseline: Option<Results> = options.baseline.as_ref().map(|path| {
+        let json = std::fs::read_to_string(path).expect("could not read baseline");
+        serde_json::from_str(&json).expect("could not parse baseline")
+    });
+
+    println!("dispatch: {}", dispatch::kernel());
+    println!(
+        "{:<20} {:>6} {:>14} {:>10} {:>10} {:>9}",
+        "kernel", "size", "ns", "ns/point", "MFLOPS", "change"
+    );
+
+    let mut results = Results {
+        results: Vec::new(),
+    };
+
+    for bench in benches() {
+        if let Some(filter) = &options.filter {
+            if !bench.name.contains(filter.as_str()) {
+                continue;
+            }
+        }
+
+        for size in SIZES {
+            if bench.slow && size > MAX_SLOW_SIZE {
+                continue;
+            }
+
+            let mut signal = vec![0.; size];
+            siggen::noise(NoiseColor::White, 1, &mut signal);
+            let input: Vec<_> = signal.iter().map(|&x| Complex::new(x, 0.)).collect();
+            let mut output = vec![Complex::new(0., 0.); size];
+
+            let ns = measure(
+                || (bench.run)(black_box(&input), black_box(&mut output)),
+                options.quick,
+            );
+            let m = Measurement {
+                kernel: bench.name.to_string(),
+                size,
+                ns,
+                ns_per_point: ns / size as f64,
+                mflops: 5. * size as f64 * (size as f64).log2() / (ns / 1000.),
+            };
+
+            let change = baseline
+                .as_ref()
+                .and_then(|b| {
+                    b.results
+                        .iter()
+                        .find(|r| r.kernel == m.kernel && r.size == m.size)
+                })
+                .map_or(String::new(), |b| {
+                    format!("{:+.1}%", (m.ns / b.ns - 1.) * 100.)
+                });
+
+            println!(
+                "{:<20} {:>6} {:>14.0} {:>10.2} {:>10.1} {:>9}",
+                m.kernel, m.size, m.ns, m.ns_per_point, m.mflops, change
+            );
+
+            results.results.push(m);
+        }
+    }
+
+    if let Some(dir) = options.save.parent() {
+        std::fs::create_dir_all(dir).expect("could not create results directory");
+    }
+    let json = serde_json::to_string_pretty(&results).unwrap();
+    std::fs::write(&options.save, json).expect("could not save results");
+    println!("saved {}", options.save.display());
+}
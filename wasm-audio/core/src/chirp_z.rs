@@ -0,0 +1,205 @@
+//! The chirp-Z transform, and the zoom FFT built on it: `M` points of the z-transform anywhere on
+//! a spiral, such as a narrow band of the spectrum at a much finer spacing than the DFT's.
+//!
+//! Points are `z_k = A W^(-k)`, so `X_k = sum x_n A^(-n) W^(nk)`. With Bluestein's
+//! `nk = (n^2 + k^2 - (k - n)^2) / 2` this is a convolution with the chirp `W^(-m^2 / 2)`, done
+//! with [`simd_cooley_tukey_fft`] at the next power of two from `N + M - 1`, for any `N` and `M`.
+
+use core::f64::consts::TAU;
+
+use alloc::{vec, vec::Vec};
+use num_complex::Complex;
+use num_traits::Zero;
+
+use crate::{simd::Float, simd_cooley_tukey::simd_cooley_tukey_fft};
+
+/// A chirp-Z transform of `input_len` samples to `points` outputs, with the chirps and the
+/// transformed filter computed ahead of time in `f64`.
+#[derive(Debug, Clone)]
+pub struct ChirpZ<T> {
+    /// `A^(-n) W^(n^2 / 2)`, applied to the input
+    pre: Vec<Complex<T>>,
+    /// `W^(k^2 / 2)`, applied to the output
+    post: Vec<Complex<T>>,
+    /// Transform of the chirp filter, divided by its length for the inverse transform
+    filter: Vec<Complex<T>>,
+}
+
+impl<T: Float> ChirpZ<T> {
+    /// Points `a w^(-k)` for `k` in `0..points`.
+    pub fn new(input_len: usize, points: usize, a: Complex<f64>, w: Complex<f64>) -> Self {
+        Self::from_logs(input_len, points, a.ln(), w.ln())
+    }
+
+    /// `points` evenly spaced frequencies from `f1` to `f2` Hz inclusive, on the unit circle: the
+    /// zoom FFT. `f2` may be below `f1`, and both may go past the Nyquist frequency.
+    pub fn zoom(input_len: usize, points: usize, f1: f64, f2: f64, sample_rate: f64) -> Self {
+        let step = if points > 1 {
+            (f2 - f1) / (points - 1) as f64
+        } else {
+            0.
+        };
+
+        Self::from_logs(
+            input_len,
+            points,
+            Complex::new(0., TAU * f1 / sample_rate),
+            Complex::new(0., -TAU * step / sample_rate),
+        )
+    }
+
+    /// Logarithms rather than `A` and `W` keep the phase of the chirps exact, and fix the branch
+    /// of the half powers.
+    fn from_logs(input_len: usize, points: usize, ln_a: Complex<f64>, ln_w: Complex<f64>) -> Self {
+        assert!(input_len > 0 && points > 0);
+
+        let to_t =
+            |x: Complex<f64>| Complex::new(T::from_f64(x.re).unwrap(), T::from_f64(x.im).unwrap());
+        let half_square = |n: usize| (n as f64) * (n as f64) / 2.;
+
+        let pre = (0..input_len)
+            .map(|n| to_t((-(n as f64) * ln_a + half_square(n) * ln_w).exp()))
+            .collect();
+        let post = (0..points)
+            .map(|k| to_t((half_square(k) * ln_w).exp()))
+            .collect();
+
+        // `W^(-m^2 / 2)` for `m` in `-(N - 1)..M`, negative `m` wrapping round to the end
+        let fft_len = (input_len + points - 1).next_power_of_two().max(2);
+        let mut chirp = vec![Complex::zero(); fft_len];
+        for (m, y) in chirp[..points].iter_mut().enumerate() {
+            *y = (-half_square(m) * ln_w).exp();
+        }
+        for m in 1..input_len {
+            chirp[fft_len - m] = (-half_square(m) * ln_w).exp();
+        }
+
+        let mut filter = vec![Complex::zero(); fft_len];
+        simd_cooley_tukey_fft(&chirp, &mut filter);
+        let filter = filter.iter().map(|&x| to_t(x / fft_len as f64)).collect();
+
+        Self { pre, post, filter }
+    }
+
+    pub fn input_len(&self) -> usize {
+        self.pre.len()
+    }
+
+    pub fn points(&self) -> usize {
+        self.post.len()
+    }
+
+    /// Scratch [`ChirpZ::process_with_scratch`] needs: two transforms' worth.
+    pub fn scratch_len(&self) -> usize {
+        2 * self.filter.len()
+    }
+
+    pub fn process(&self, input: &[Complex<T>], output: &mut [Complex<T>]) {
+        let mut scratch = vec![Complex::zero(); self.scratch_len()];
+        self.process_with_scratch(input, output, &mut scratch);
+    }
+
+    /// [`ChirpZ::process`] with caller-provided `scratch` of at least [`ChirpZ::scratch_len`], so
+    /// it never allocates.
+    pub fn process_with_scratch(
+        &self,
+        input: &[Complex<T>],
+        output: &mut [Complex<T>],
+        scratch: &mut [Complex<T>],
+    ) {
+        assert_eq!(input.len(), self.pre.len());
+        assert_eq!(output.len(), self.post.len());
+
+        let fft_len = self.filter.len();
+        let (signal, spectrum) = scratch[..2 * fft_len].split_at_mut(fft_len);
+
+        for (i, y) in signal.iter_mut().enumerate() {
+            *y = match (input.get(i), self.pre.get(i)) {
+                (Some(&x), Some(&c)) => x * c,
+                _ => Complex::zero(),
+            };
+        }
+        simd_cooley_tukey_fft(signal, spectrum);
+
+        // Inverse transform of the product, as the conjugate of the forward transform of its
+        // conjugate
+        for (y, &h) in spectrum.iter_mut().zip(&self.filter) {
+            *y = (*y * h).conj();
+        }
+        simd_cooley_tukey_fft(spectrum, signal);
+
+        for ((y, x), &c) in output.iter_mut().zip(signal.iter()).zip(&self.post) {
+            *y = x.conj() * c;
+        }
+    }
+}
+
+/// `points` evenly spaced frequencies of `input` from `f1` to `f2` Hz inclusive, with
+/// [`ChirpZ::zoom`].
+pub fn zoom_fft<T: Float>(
+    input: &[Complex<T>],
+    f1: f64,
+    f2: f64,
+    sample_rate: f64,
+    output: &mut [Complex<T>],
+) {
+    ChirpZ::zoom(input.len(), output.len(), f1, f2, sample_rate).process(input, output);
+}
+
+#[test]
+fn test_chirp_z_dft() {
+    // Not a power of two, and `W` going the whole way round the circle
+    let n = 100;
+    let mut re = vec![0.; n];
+    let mut im = vec![0.; n];
+    crate::test_noise(5, &mut re);
+    crate::test_noise(6, &mut im);
+    let input: Vec<_> = re
+        .iter()
+        .zip(&im)
+        .map(|(&re, &im)| Complex::new(re as f64, im as f64))
+        .collect();
+
+    let mut expected = vec![Complex::zero(); n];
+    crate::dft::dft(&input, &mut expected);
+
+    let w = Complex::from_polar(1., -TAU / n as f64);
+    let mut output = vec![Complex::zero(); n];
+    ChirpZ::new(n, n, Complex::new(1., 0.), w).process(&input, &mut output);
+
+    for (y, x) in output.iter().zip(&expected) {
+        approx::assert_abs_diff_eq!(y.re, x.re, epsilon = 1e-9);
+        approx::assert_abs_diff_eq!(y.im, x.im, epsilon = 1e-9);
+    }
+}
+
+#[test]
+fn test_zoom_fft() {
+    let sample_rate = 8000.;
+    let mut input = vec![0.; 1000];
+    crate::test_noise(7, &mut input);
+    let input: Vec<_> = input.iter().map(|&x| Complex::new(x, 0.)).collect();
+
+    // Downwards, from 65 to 45 Hz, more points than samples
+    let (f1, f2, points) = (65., 45., 1201);
+    let mut output = vec![Complex::zero(); points];
+    zoom_fft(&input, f1, f2, sample_rate, &mut output);
+
+    for (k, y) in output.iter().enumerate().step_by(97) {
+        let f = f1 + k as f64 * (f2 - f1) / (points - 1) as f64;
+        let x: Complex<f64> = input
+            .iter()
+            .enumerate()
+            .map(|(n, x)| x.re as f64 * Complex::from_polar(1., -TAU * f * n as f64 / sample_rate))
+            .sum();
+
+        approx::assert_abs_diff_eq!(y.re as f64, x.re, epsilon = 1e-3);
+        approx::assert_abs_diff_eq!(y.im as f64, x.im, epsilon = 1e-3);
+    }
+
+    // A single point is the DFT at `f1`
+    let mut single = [Complex::zero()];
+    zoom_fft(&input, f1, f2, sample_rate, &mut single);
+    approx::assert_abs_diff_eq!(single[0].re, output[0].re, epsilon = 1e-3);
+    approx::assert_abs_diff_eq!(single[0].im, output[0].im, epsilon = 1e-3);
+}
@@ -1,15 +1,13 @@
-use core::f32::consts::PI;
-use rustfft::num_complex::Complex;
+use num_complex::Complex;
 
-const NTWO_PI_I: Complex<f32> = Complex {
-    re: 0.,
-    im: -2.0 * PI,
-};
+use crate::simd::Float;
 
-pub fn cooley_tukey_fft(input: &[Complex<f32>], output: &mut [Complex<f32>]) {
+pub fn cooley_tukey_fft<T: Float>(input: &[Complex<T>], output: &mut [Complex<T>]) {
     assert_eq!(input.len(), output.len());
     assert!(crate::is_power_of_2(input.len()));
 
+    let ntwo_pi_i = Complex::new(T::zero(), -(T::PI() + T::PI()));
+
     let n = input.len();
 
     bit_reverse_copy(input, output);
@@ -20,22 +18,48 @@ pub fn cooley_tukey_fft(input: &[Complex<f32>], output: &mut [Complex<f32>]) {
     (1..=n_log2).for_each(|s| {
         let m: usize = 1 << s;
         let mdiv2 = m >> 1;
-        let wm = Complex::exp(NTWO_PI_I / m as f32);
+        let wm = Complex::exp(ntwo_pi_i / T::from_usize(m).unwrap());
 
         (0..n).step_by(m).for_each(|k| {
-            let mut w = Complex::new(1., 0.);
+            let mut w = Complex::new(T::one(), T::zero());
 
             (0..mdiv2).for_each(|j| {
                 let t = w * output[k + j + mdiv2];
                 let u = output[k + j];
                 output[k + j] = u + t;
                 output[k + j + mdiv2] = u - t;
-                w *= wm;
+                w = w * wm;
             })
         })
     })
 }
 
+#[test]
+fn test_cooley_tukey_fft() {
+    use alloc::{vec, vec::Vec};
+    use approx::assert_abs_diff_eq;
+    use core::f32::consts::TAU;
+
+    // Tones on bins 3 and 10, half amplitude each
+    let input: Vec<_> = (0..64)
+        .map(|i| {
+            let t = i as f32 / 64.;
+            Complex::new(0.5 * (TAU * 3. * t).sin() + 0.5 * (TAU * 10. * t).sin(), 0.)
+        })
+        .collect();
+    let mut output = vec![Complex::new(0., 0.); input.len()];
+    cooley_tukey_fft(&input, &mut output);
+
+    for (k, y) in output.iter().enumerate() {
+        let expected = if [3, 10, 54, 61].contains(&k) {
+            16.
+        } else {
+            0.
+        };
+        assert_abs_diff_eq!(y.norm(), expected, epsilon = 1e-4);
+    }
+}
+
 pub fn bit_reverse_copy<T: Copy>(input: &[T], output: &mut [T]) {
     assert_eq!(input.len(), output.len());
     assert!(crate::is_power_of_2(input.len()));
@@ -49,6 +73,8 @@ pub fn bit_reverse_copy<T: Copy>(input: &[T], output: &mut [T]) {
 
 #[test]
 fn test_bit_reverse_copy() {
+    use alloc::vec;
+
     let a = vec![0, 1, 2, 3, 4, 5, 6, 7];
     let mut b = vec![0; a.len()];
     bit_reverse_copy(&a, &mut b);
@@ -0,0 +1,254 @@
+//! Constant-Q transform by Brown and Puckette's kernel method, and chromagrams from it.
+//!
+//! Bins are geometrically spaced, `bins_per_octave` to the octave from `min_freq`, each with a
+//! window `Q` periods long, `Q = 1 / (2^(1 / bins_per_octave) - 1)`. Each bin is the inner product
+//! of the signal with a Hamming-windowed complex exponential: by Parseval's theorem, the same as
+//! the inner product of their spectra. The kernels' spectra are computed once and are mostly
+//! near zero, so only the rest are kept, and each frame costs one FFT and a few sparse sums.
+
+use core::f64::consts::TAU;
+
+use alloc::{vec, vec::Vec};
+use num_complex::Complex;
+// `Real` for the `f64` maths, which `no_std` has no inherent methods for
+use num_traits::{Float as Real, Zero};
+
+use crate::{simd::Float, simd_cooley_tukey::simd_cooley_tukey_fft};
+
+/// Parts of a kernel's spectrum below this fraction of its peak are dropped, under the Hamming
+/// window's highest sidelobe.
+const SPARSITY: f64 = 5e-3;
+
+/// Sparse spectral kernels of a constant-Q transform, for frames of [`Cqt::fft_len`] samples.
+#[derive(Debug, Clone)]
+pub struct Cqt<T> {
+    sample_rate: f64,
+    min_freq: f64,
+    bins_per_octave: usize,
+    fft_len: usize,
+
+    /// Kernel `k` is `values[starts[k]..starts[k + 1]]` at `indices[starts[k]..starts[k + 1]]`
+    starts: Vec<usize>,
+    indices: Vec<usize>,
+    values: Vec<Complex<T>>,
+
+    /// Pitch class of each bin, C being 0, for [`Cqt::chroma`]
+    pitch_classes: Vec<usize>,
+}
+
+impl<T: Float> Cqt<T> {
+    /// `bins` bins from `min_freq`, `bins_per_octave` to the octave. The highest must be below the
+    /// Nyquist frequency.
+    ///
+    /// Frames are as long as the lowest bin's window, rounded up to a power of two: low
+    /// `min_freq` and many bins per octave make long frames, and kernels slow to compute.
+    pub fn new(sample_rate: f64, min_freq: f64, bins: usize, bins_per_octave: usize) -> Self {
+        assert!(bins > 0 && bins_per_octave > 0);
+        assert!(min_freq > 0.);
+
+        let frequency = |k: usize| min_freq * Real::exp2(k as f64 / bins_per_octave as f64);
+        assert!(frequency(bins - 1) < sample_rate / 2.);
+
+        let q = 1. / (Real::exp2(1. / bins_per_octave as f64) - 1.);
+        let window_len = |k: usize| Real::ceil(q * sample_rate / frequency(k)) as usize;
+        let fft_len = window_len(0).next_power_of_two().max(2);
+
+        let mut starts = vec![0];
+        let mut indices = Vec::new();
+        let mut values = Vec::new();
+        let mut temporal = vec![Complex::zero(); fft_len];
+        let mut spectral = vec![Complex::zero(); fft_len];
+
+        for k in 0..bins {
+            // Centred in the frame, and normalised so a sine of amplitude `a` gives `a / 2`
+            let len = window_len(k);
+            let offset = (fft_len - len) / 2;
+            let hamming =
+                |n: usize| 0.54 - 0.46 * Real::cos(TAU * n as f64 / (len.max(2) - 1) as f64);
+            let sum: f64 = (0..len).map(hamming).sum();
+
+            temporal.iter_mut().for_each(|x| *x = Complex::zero());
+            for n in 0..len {
+                let phase = TAU * frequency(k) * n as f64 / sample_rate;
+                temporal[offset + n] = hamming(n) / sum * Complex::from_polar(1., phase);
+            }
+            simd_cooley_tukey_fft(&temporal, &mut spectral);
+
+            let peak = spectral.iter().map(|x| x.norm()).fold(0., f64::max);
+            for (j, x) in spectral.iter().enumerate() {
+                if x.norm() >= SPARSITY * peak {
+                    let x = x.conj() / fft_len as f64;
+                    indices.push(j);
+                    values.push(Complex::new(
+                        T::from_f64(x.re).unwrap(),
+                        T::from_f64(x.im).unwrap(),
+                    ));
+                }
+            }
+            starts.push(indices.len());
+        }
+
+        let pitch_classes = (0..bins)
+            .map(|k| {
+                // Semitones from A4, with A being 9
+                let semitones = Real::round(12. * Real::log2(frequency(k) / 440.)) as i64;
+                (semitones + 9).rem_euclid(12) as usize
+            })
+            .collect();
+
+        Self {
+            sample_rate,
+            min_freq,
+            bins_per_octave,
+            fft_len,
+            starts,
+            indices,
+            values,
+            pitch_classes,
+        }
+    }
+
+    /// Samples per frame.
+    pub fn fft_len(&self) -> usize {
+        self.fft_len
+    }
+
+    pub fn bins(&self) -> usize {
+        self.pitch_classes.len()
+    }
+
+    pub fn bins_per_octave(&self) -> usize {
+        self.bins_per_octave
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Centre frequency of bin `k`, in Hz.
+    pub fn frequency(&self, k: usize) -> f64 {
+        self.min_freq * Real::exp2(k as f64 / self.bins_per_octave as f64)
+    }
+
+    /// Length of the `scratch` that [`Cqt::process_with_scratch`] takes: room for a frame and
+    /// its spectrum side by side.
+    pub fn scratch_len(&self) -> usize {
+        2 * self.fft_len
+    }
+
+    pub fn process(&self, input: &[T], output: &mut [Complex<T>]) {
+        let mut scratch = vec![Complex::zero(); self.scratch_len()];
+        self.process_with_scratch(input, output, &mut scratch);
+    }
+
+    /// Transform a frame of [`Cqt::fft_len`] samples into [`Cqt::bins`] bins. The frame's FFT is
+    /// taken in `scratch`, at least [`Cqt::scratch_len`] long, which [`Cqt::process`] allocates
+    /// on every call instead.
+    pub fn process_with_scratch(
+        &self,
+        input: &[T],
+        output: &mut [Complex<T>],
+        scratch: &mut [Complex<T>],
+    ) {
+        assert_eq!(input.len(), self.fft_len);
+        assert_eq!(output.len(), self.bins());
+
+        let (signal, spectrum) = scratch[..2 * self.fft_len].split_at_mut(self.fft_len);
+        for (y, &x) in signal.iter_mut().zip(input) {
+            *y = Complex::new(x, T::zero());
+        }
+        simd_cooley_tukey_fft(signal, spectrum);
+
+        for (k, y) in output.iter_mut().enumerate() {
+            let kernel = self.starts[k]..self.starts[k + 1];
+            *y = self.indices[kernel.clone()]
+                .iter()
+                .zip(&self.values[kernel])
+                .fold(Complex::zero(), |y, (&j, &h)| y + spectrum[j] * h);
+        }
+    }
+
+    /// Fold `bins` from [`Cqt::process`] into 12 pitch classes from C, each bin going to its
+    /// nearest semitone with A4 at 440 Hz. The energy of each class is scaled so that the
+    /// strongest is 1.
+    pub fn chroma(&self, bins: &[Complex<T>], output: &mut [T]) {
+        assert_eq!(bins.len(), self.bins());
+        assert_eq!(output.len(), 12);
+
+        output.iter_mut().for_each(|y| *y = T::zero());
+        for (x, &class) in bins.iter().zip(&self.pitch_classes) {
+            output[class] = output[class] + x.norm_sqr();
+        }
+
+        let max = output.iter().fold(T::zero(), |a, &b| a.max(b));
+        if max > T::zero() {
+            output.iter_mut().for_each(|y| *y = *y / max);
+        }
+    }
+}
+
+#[cfg(test)]
+fn sine(freq: f64, sample_rate: f64, output: &mut [f32]) {
+    for (n, y) in output.iter_mut().enumerate() {
+        *y = (TAU * freq * n as f64 / sample_rate).sin() as f32;
+    }
+}
+
+#[test]
+fn test_cqt_bins() {
+    let sample_rate = 8000.;
+    let cqt = Cqt::<f32>::new(sample_rate, 110., 48, 12);
+    approx::assert_relative_eq!(cqt.frequency(12), 220., max_relative = 1e-12);
+    approx::assert_relative_eq!(cqt.frequency(47), 110. * (47. / 12f64).exp2());
+
+    let mut input = vec![0.; cqt.fft_len()];
+    let mut output = vec![Complex::zero(); cqt.bins()];
+
+    // A sine on each of a few bins peaks there, at half its amplitude
+    for k in [0, 7, 24, 40, 47] {
+        sine(cqt.frequency(k), sample_rate, &mut input);
+        cqt.process(&input, &mut output);
+
+        let peak = (0..cqt.bins())
+            .max_by(|&a, &b| output[a].norm().total_cmp(&output[b].norm()))
+            .unwrap();
+        assert_eq!(peak, k);
+        approx::assert_abs_diff_eq!(output[k].norm(), 0.5, epsilon = 5e-3);
+
+        // Neighbouring bins overlap, as a bin's spacing is one bin of its own window, but from
+        // two bins away the sine is outside the Hamming window's main lobe
+        for (j, x) in output.iter().enumerate() {
+            if j.abs_diff(k) >= 2 {
+                assert!(x.norm() < 0.01, "bin {j} at {}", x.norm());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_cqt_chroma() {
+    let sample_rate = 8000.;
+    let cqt = Cqt::<f32>::new(sample_rate, 65.41, 108, 36);
+
+    // A3 and E4, with E a little weaker
+    let mut input = vec![0.; cqt.fft_len()];
+    let mut e = vec![0.; cqt.fft_len()];
+    sine(220., sample_rate, &mut input);
+    sine(329.63, sample_rate, &mut e);
+    for (x, e) in input.iter_mut().zip(&e) {
+        *x += 0.7 * e;
+    }
+
+    let mut bins = vec![Complex::zero(); cqt.bins()];
+    let mut chroma = [0.; 12];
+    cqt.process(&input, &mut bins);
+    cqt.chroma(&bins, &mut chroma);
+
+    assert_eq!(chroma[9], 1.);
+    approx::assert_abs_diff_eq!(chroma[4], 0.49, epsilon = 0.05);
+    for (class, &x) in chroma.iter().enumerate() {
+        if class != 9 && class != 4 {
+            assert!(x < 0.05, "class {class} at {x}");
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use num_complex::Complex;
+
+use crate::simd::Float;
+
+pub fn dft<T: Float>(input: &[Complex<T>], output: &mut [Complex<T>]) {
+    dft_helper(input, output, false);
+}
+
+pub fn idft<T: Float>(input: &[Complex<T>], output: &mut [Complex<T>]) {
+    dft_helper(input, output, true);
+}
+
+fn dft_helper<T: Float>(input: &[Complex<T>], output: &mut [Complex<T>], inverse: bool) {
+    assert_eq!(input.len(), output.len());
+
+    let n = input.len();
+    let to_t = |x: usize| T::from_usize(x).unwrap();
+
+    let wn = (T::PI() + T::PI()) / to_t(n) * if inverse { -T::one() } else { T::one() };
+
+    (0..n).for_each(|i| {
+        let y = &mut output[i];
+        y.re = T::zero();
+        y.im = T::zero();
+
+        let wk = to_t(i) * wn;
+
+        (0..n).for_each(|j| {
+            let x = &input[j];
+            let c = (to_t(j) * wk).cos();
+            let s = (to_t(j) * wk).sin();
+
+            y.re = y.re + (x.re * c + x.im * s);
+            y.im = y.im + (-x.re * s + x.im * c);
+        });
+
+        if inverse {
+            y.re = y.re / to_t(n);
+            y.im = y.im / to_t(n);
+        }
+    })
+}
@@ -0,0 +1,576 @@
+//! Fixed-point FFTs on `i16` (Q15) and `i32` (Q31) samples, for integer PCM that never goes
+//! through float.
+//!
+//! Values are kept in range with block floating point: before each stage the whole block is
+//! shifted right just enough that no butterfly can overflow, and the shifts are counted in an
+//! exponent returned with the spectrum. Products are rounded and sums saturate, as on DSP
+//! fixed-point units. On wasm with `simd128`, Q15 stages of 8 or more butterflies run on `i16x8`
+//! vectors with `i16x8.q15mulr_sat_s`, which rounds the same way, so both paths give identical
+//! results.
+
+use num_complex::Complex;
+
+use crate::cooley_tukey::{bit_reverse_copy, numbits};
+
+#[cfg(test)]
+use alloc::{vec, vec::Vec};
+
+/// Forward FFT of Q15 samples. The DFT of `input` is `output` times `2^exponent`, where the
+/// exponent is the return value.
+#[cfg(feature = "alloc")]
+pub fn fft_q15(input: &[Complex<i16>], output: &mut [Complex<i16>]) -> u32 {
+    let mut scratch = alloc::vec![0; scratch_len(input.len())];
+    fft_q15_with_scratch(input, output, &mut scratch)
+}
+
+/// [`fft_q15`] working in caller-provided `scratch` of at least [`scratch_len`] values.
+#[cfg_attr(
+    all(target_arch = "wasm32", feature = "simd128"),
+    target_feature(enable = "simd128")
+)]
+pub fn fft_q15_with_scratch(
+    input: &[Complex<i16>],
+    output: &mut [Complex<i16>],
+    scratch: &mut [i16],
+) -> u32 {
+    fixed_fft(input, output, scratch, true)
+}
+
+/// Forward FFT of Q31 samples, scaled as in [`fft_q15`].
+#[cfg(feature = "alloc")]
+pub fn fft_q31(input: &[Complex<i32>], output: &mut [Complex<i32>]) -> u32 {
+    let mut scratch = alloc::vec![0; scratch_len(input.len())];
+    fft_q31_with_scratch(input, output, &mut scratch)
+}
+
+/// [`fft_q31`] working in caller-provided `scratch` of at least [`scratch_len`] values.
+pub fn fft_q31_with_scratch(
+    input: &[Complex<i32>],
+    output: &mut [Complex<i32>],
+    scratch: &mut [i32],
+) -> u32 {
+    fixed_fft(input, output, scratch, false)
+}
+
+/// Scratch space the `_with_scratch` transforms need for `n` points: split real and imaginary
+/// parts, and their twiddles.
+pub const fn scratch_len(n: usize) -> usize {
+    4 * n
+}
+
+/// Sample type of a fixed-point transform.
+trait Fixed: Copy + Default {
+    /// Largest magnitude a stage with arbitrary twiddles can take without an output
+    /// overflowing: `(1 + √2) · HEADROOM`, plus rounding, stays below `MAX`.
+    const HEADROOM: i64;
+    /// Same, for the first two stages, whose twiddles are `1` and `-i` and exact.
+    const HEADROOM_EXACT: i64;
+
+    fn from_f64(x: f64) -> Self;
+    fn from_i64(x: i64) -> Self;
+    fn to_i64(self) -> i64;
+    /// Rounded product of two fixed-point fractions.
+    fn mul_q(self, rhs: Self) -> Self;
+    fn add_sat(self, rhs: Self) -> Self;
+    fn sub_sat(self, rhs: Self) -> Self;
+
+    /// [`butterflies`] on SIMD vectors, if this type and target have them. Returns `None` when
+    /// the scalar loop should be used instead.
+    #[inline(always)]
+    fn simd_butterflies(
+        _re: &mut [Self],
+        _im: &mut [Self],
+        _tw_re: &[Self],
+        _tw_im: &[Self],
+        _mdiv2: usize,
+    ) -> Option<i64> {
+        None
+    }
+}
+
+macro_rules! impl_fixed {
+    ($t:ty, $frac:expr $(, $item:item)*) => {
+        impl Fixed for $t {
+            const HEADROOM: i64 = ((<$t>::MAX - 2) as f64 / (1. + core::f64::consts::SQRT_2)) as i64;
+            const HEADROOM_EXACT: i64 = <$t>::MAX as i64 / 2;
+
+            #[inline(always)]
+            fn from_f64(x: f64) -> Self {
+                Self::from_i64(num_traits::Float::round(x * (1_i64 << $frac) as f64) as i64)
+            }
+
+            #[inline(always)]
+            fn from_i64(x: i64) -> Self {
+                x.clamp(<$t>::MIN as i64, <$t>::MAX as i64) as $t
+            }
+
+            #[inline(always)]
+            fn to_i64(self) -> i64 {
+                self as i64
+            }
+
+            #[inline(always)]
+            fn mul_q(self, rhs: Self) -> Self {
+                Self::from_i64((self as i64 * rhs as i64 + (1 << ($frac - 1))) >> $frac)
+            }
+
+            #[inline(always)]
+            fn add_sat(self, rhs: Self) -> Self {
+                self.saturating_add(rhs)
+            }
+
+            #[inline(always)]
+            fn sub_sat(self, rhs: Self) -> Self {
+                self.saturating_sub(rhs)
+            }
+
+            $($item)*
+        }
+    };
+}
+
+impl_fixed!(
+    i16,
+    15,
+    #[cfg(all(
+        target_arch = "wasm32",
+        any(feature = "simd128", target_feature = "simd128")
+    ))]
+    #[inline(always)]
+    fn simd_butterflies(
+        re: &mut [Self],
+        im: &mut [Self],
+        tw_re: &[Self],
+        tw_im: &[Self],
+        mdiv2: usize,
+    ) -> Option<i64> {
+        if mdiv2 < 8 {
+            return None;
+        }
+
+        Some(wasm32::butterflies_i16x8(re, im, tw_re, tw_im, mdiv2))
+    }
+);
+impl_fixed!(i32, 31);
+
+/// Radix-2 Cooley-Tukey on split real and imaginary buffers, with a block exponent.
+#[inline(always)]
+fn fixed_fft<T: Fixed>(
+    input: &[Complex<T>],
+    output: &mut [Complex<T>],
+    scratch: &mut [T],
+    simd: bool,
+) -> u32 {
+    assert_eq!(input.len(), output.len());
+    assert!(crate::is_power_of_2(input.len()));
+    assert!(scratch.len() >= scratch_len(input.len()));
+
+    let n = input.len();
+    let n_log2 = numbits(n) - 1;
+
+    bit_reverse_copy(input, output);
+
+    let (re, scratch) = scratch.split_at_mut(n);
+    let (im, scratch) = scratch.split_at_mut(n);
+    let (tw_re, scratch) = scratch.split_at_mut(n);
+    let tw_im = &mut scratch[..n];
+
+    for ((re, im), x) in re.iter_mut().zip(im.iter_mut()).zip(output.iter()) {
+        (*re, *im) = (x.re, x.im);
+    }
+
+    // `w_m^j` for stage `m` is at index `m/2 + j`, so each stage's twiddles are contiguous. The
+    // last stage's are computed, in f64 where the recurrence's error stays far below a Q31 step,
+    // and the others are every other one of the next stage's.
+    let half = n / 2;
+    let wm = Complex::from_polar(1., -core::f64::consts::PI / half as f64);
+    let mut w = Complex::new(1., 0.);
+    for j in 0..half {
+        tw_re[half + j] = T::from_f64(w.re);
+        tw_im[half + j] = T::from_f64(w.im);
+        w *= wm;
+    }
+    for mdiv2 in (0..n_log2 - 1).rev().map(|s| 1 << s) {
+        for j in 0..mdiv2 {
+            tw_re[mdiv2 + j] = tw_re[2 * (mdiv2 + j)];
+            tw_im[mdiv2 + j] = tw_im[2 * (mdiv2 + j)];
+        }
+    }
+
+    let mut exponent = 0;
+    let mut peak = peak(re).max(peak(im));
+
+    for s in 1..=n_log2 {
+        let m: usize = 1 << s;
+        let mdiv2 = m >> 1;
+
+        let headroom = if mdiv2 <= 2 {
+            T::HEADROOM_EXACT
+        } else {
+            T::HEADROOM
+        };
+        exponent += block_shift(re, im, peak, headroom);
+
+        let (tw_re, tw_im) = (&tw_re[mdiv2..m], &tw_im[mdiv2..m]);
+        let simd_peak = match simd {
+            true => T::simd_butterflies(re, im, tw_re, tw_im, mdiv2),
+            false => None,
+        };
+        peak = match simd_peak {
+            Some(peak) => peak,
+            None => butterflies(re, im, tw_re, tw_im, mdiv2),
+        };
+    }
+
+    for ((y, &re), &im) in output.iter_mut().zip(re.iter()).zip(im.iter()) {
+        *y = Complex::new(re, im);
+    }
+
+    exponent
+}
+
+/// Largest magnitude in `x`.
+#[inline(always)]
+fn peak<T: Fixed>(x: &[T]) -> i64 {
+    x.iter().fold(0, |max, x| max.max(x.to_i64().abs()))
+}
+
+/// Shift the block right until every value is within `headroom`, returning the shift. `peak` is
+/// the largest magnitude in the block.
+#[inline(always)]
+fn block_shift<T: Fixed>(re: &mut [T], im: &mut [T], peak: i64, headroom: i64) -> u32 {
+    let mut shift = 0;
+    while round_shr(peak, shift) > headroom {
+        shift += 1;
+    }
+
+    if shift > 0 {
+        for x in re.iter_mut().chain(im.iter_mut()) {
+            *x = T::from_i64(round_shr(x.to_i64(), shift));
+        }
+    }
+
+    shift
+}
+
+#[inline(always)]
+fn round_shr(x: i64, shift: u32) -> i64 {
+    if shift == 0 {
+        x
+    } else {
+        (x + (1 << (shift - 1))) >> shift
+    }
+}
+
+/// One stage of butterflies, returning the largest magnitude of the outputs.
+#[inline(always)]
+fn butterflies<T: Fixed>(
+    re: &mut [T],
+    im: &mut [T],
+    tw_re: &[T],
+    tw_im: &[T],
+    mdiv2: usize,
+) -> i64 {
+    let (tw_re, tw_im) = (&tw_re[..mdiv2], &tw_im[..mdiv2]);
+    let mut peak = 0;
+
+    for (re, im) in re
+        .chunks_exact_mut(mdiv2 * 2)
+        .zip(im.chunks_exact_mut(mdiv2 * 2))
+    {
+        let (re_lo, re_hi) = re.split_at_mut(mdiv2);
+        let (im_lo, im_hi) = im.split_at_mut(mdiv2);
+
+        for j in 0..mdiv2 {
+            // t = w * x[hi]
+            let (wr, wi) = (tw_re[j], tw_im[j]);
+            let (xr, xi) = (re_hi[j], im_hi[j]);
+            let tr = xr.mul_q(wr).sub_sat(xi.mul_q(wi));
+            let ti = xr.mul_q(wi).add_sat(xi.mul_q(wr));
+
+            let (ur, ui) = (re_lo[j], im_lo[j]);
+            re_lo[j] = ur.add_sat(tr);
+            im_lo[j] = ui.add_sat(ti);
+            re_hi[j] = ur.sub_sat(tr);
+            im_hi[j] = ui.sub_sat(ti);
+
+            for y in [re_lo[j], im_lo[j], re_hi[j], im_hi[j]] {
+                peak = peak.max(y.to_i64().abs());
+            }
+        }
+    }
+
+    peak
+}
+
+#[cfg(all(
+    target_arch = "wasm32",
+    any(feature = "simd128", target_feature = "simd128")
+))]
+mod wasm32 {
+    use core::arch::wasm32::*;
+
+    #[inline(always)]
+    fn load(x: &[i16]) -> v128 {
+        let x = &x[..8];
+        // SAFETY: `x` has 8 lanes, and `v128_load` doesn't need alignment.
+        unsafe { v128_load(x.as_ptr() as *const v128) }
+    }
+
+    #[inline(always)]
+    fn store(x: &mut [i16], v: v128) {
+        let x = &mut x[..8];
+        // SAFETY: as in `load`.
+        unsafe { v128_store(x.as_mut_ptr() as *mut v128, v) }
+    }
+
+    #[inline(always)]
+    fn store_u16(x: &mut [u16; 8], v: v128) {
+        // SAFETY: as in `load`.
+        unsafe { v128_store(x.as_mut_ptr() as *mut v128, v) }
+    }
+
+    /// [`super::butterflies`] for `i16`, 8 butterflies at a time. `mdiv2` must be a multiple
+    /// of 8.
+    #[inline(always)]
+    pub(super) fn butterflies_i16x8(
+        re: &mut [i16],
+        im: &mut [i16],
+        tw_re: &[i16],
+        tw_im: &[i16],
+        mdiv2: usize,
+    ) -> i64 {
+        // Magnitudes as unsigned lanes, where `|i16::MIN|` fits
+        let mut peak = u16x8_splat(0);
+
+        for k in (0..re.len()).step_by(mdiv2 * 2) {
+            for j in (0..mdiv2).step_by(8) {
+                let (lo, hi) = (k + j, k + j + mdiv2);
+
+                let (wr, wi) = (load(&tw_re[j..]), load(&tw_im[j..]));
+                let (xr, xi) = (load(&re[hi..]), load(&im[hi..]));
+                let tr = i16x8_sub_sat(i16x8_q15mulr_sat(xr, wr), i16x8_q15mulr_sat(xi, wi));
+                let ti = i16x8_add_sat(i16x8_q15mulr_sat(xr, wi), i16x8_q15mulr_sat(xi, wr));
+
+                let (ur, ui) = (load(&re[lo..]), load(&im[lo..]));
+                let outputs = [
+                    i16x8_add_sat(ur, tr),
+                    i16x8_add_sat(ui, ti),
+                    i16x8_sub_sat(ur, tr),
+                    i16x8_sub_sat(ui, ti),
+                ];
+                store(&mut re[lo..], outputs[0]);
+                store(&mut im[lo..], outputs[1]);
+                store(&mut re[hi..], outputs[2]);
+                store(&mut im[hi..], outputs[3]);
+
+                for y in outputs {
+                    peak = u16x8_max(peak, i16x8_abs(y));
+                }
+            }
+        }
+
+        let mut lanes = [0_u16; 8];
+        store_u16(&mut lanes, peak);
+        lanes.into_iter().max().unwrap() as i64
+    }
+}
+
+/// Signal-to-quantization-noise ratio of `actual` scaled by `2^exponent / full_scale`, in dB.
+#[cfg(test)]
+fn sqnr_db<T: Fixed, F: crate::simd::Float>(
+    actual: &[Complex<T>],
+    exponent: u32,
+    full_scale: f64,
+    expected: &[Complex<F>],
+) -> f64 {
+    let scale = 2f64.powi(exponent as i32) / full_scale;
+    let (mut signal, mut noise) = (0., 0.);
+
+    for (a, e) in actual.iter().zip(expected) {
+        let e = Complex::new(e.re.to_f64().unwrap(), e.im.to_f64().unwrap());
+        let a = Complex::new(a.re.to_i64() as f64, a.im.to_i64() as f64) * scale;
+        signal += e.norm_sqr();
+        noise += (a - e).norm_sqr();
+    }
+
+    10. * (signal / noise).log10()
+}
+
+/// Uniform white noise at half of `full_scale`, quantized.
+#[cfg(test)]
+fn quantized_noise<T: Fixed>(size: usize, seed: u32, full_scale: f64) -> Vec<Complex<T>> {
+    let mut re = vec![0.; size];
+    let mut im = vec![0.; size];
+    crate::test_noise(seed, &mut re);
+    crate::test_noise(seed + 100, &mut im);
+
+    let quantize = |x: f32| T::from_i64((x as f64 * 0.5 * full_scale).round() as i64);
+    re.into_iter()
+        .zip(im)
+        .map(|(re, im)| Complex::new(quantize(re), quantize(im)))
+        .collect()
+}
+
+#[cfg(test)]
+type FloatFft<F> = fn(&[Complex<F>], &mut [Complex<F>]);
+
+/// The float kernels of this crate.
+#[cfg(test)]
+fn float_kernels<F: crate::simd::Float>() -> [(&'static str, FloatFft<F>); 5] {
+    [
+        ("dft", crate::dft::dft),
+        ("cooley_tukey", crate::cooley_tukey::cooley_tukey_fft),
+        (
+            "simd_cooley_tukey",
+            crate::simd_cooley_tukey::simd_cooley_tukey_fft,
+        ),
+        (
+            "simd_cooley_tukey2",
+            crate::simd_cooley_tukey2::simd_cooley_tukey_fft2,
+        ),
+        ("simd_cooley_tukey3", simd_cooley_tukey_fft3),
+    ]
+}
+
+/// `simd_cooley_tukey_fft3_complex`, which is only built with the `alloc` feature.
+#[cfg(test)]
+fn simd_cooley_tukey_fft3<F: crate::simd::Float>(input: &[Complex<F>], output: &mut [Complex<F>]) {
+    use crate::simd_cooley_tukey3::{simd_cooley_tukey_fft3_complex_with_scratch, Complex4};
+
+    let mut scratch = vec![Complex4::zero(); 2 * input.len()];
+    simd_cooley_tukey_fft3_complex_with_scratch(input, output, &mut scratch);
+}
+
+/// Lowest SQNR the Q15 transform may reach at `size` against a float kernel, on
+/// [`quantized_noise`] (about -11 dBFS RMS). Each stage adds rounding noise and may shift the
+/// block; measured values are 2 to 5 dB above this.
+#[cfg(test)]
+fn min_sqnr_db_q15(size: usize) -> f64 {
+    let stages = (numbits(size) - 1) as f64;
+
+    75. - 1.2 * stages
+}
+
+/// Q31 has 16 more bits.
+#[cfg(test)]
+fn min_sqnr_db_q31(size: usize) -> f64 {
+    min_sqnr_db_q15(size) + 6.02 * 16.
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_fft_q15_sqnr() {
+    let full_scale = 32768.;
+
+    for size in (1..=12).map(|s| 1 << s) {
+        let input = quantized_noise::<i16>(size, 7, full_scale);
+        let mut output = vec![Complex::new(0, 0); size];
+        let mut scratch = vec![0; scratch_len(size)];
+        let exponent = fft_q15_with_scratch(&input, &mut output, &mut scratch);
+
+        let float_input: Vec<_> = input
+            .iter()
+            .map(|x| Complex::new(x.re as f32, x.im as f32) / full_scale as f32)
+            .collect();
+
+        // Against every f32 kernel, whose own error is well below Q15's
+        for (name, fft) in float_kernels::<f32>() {
+            if name == "dft" && size > 1024 {
+                continue;
+            }
+
+            let mut expected = vec![Complex::new(0., 0.); size];
+            fft(&float_input, &mut expected);
+
+            let sqnr = sqnr_db(&output, exponent, full_scale, &expected);
+            assert!(
+                sqnr > min_sqnr_db_q15(size),
+                "q15 against {name} at {size}: {sqnr:.1} dB"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_fft_q31_sqnr() {
+    let full_scale = 2f64.powi(31);
+
+    for size in (1..=12).map(|s| 1 << s) {
+        let input = quantized_noise::<i32>(size, 11, full_scale);
+        let mut output = vec![Complex::new(0, 0); size];
+        let mut scratch = vec![0; scratch_len(size)];
+        let exponent = fft_q31_with_scratch(&input, &mut output, &mut scratch);
+
+        let float_input: Vec<_> = input
+            .iter()
+            .map(|x| Complex::new(x.re as f64, x.im as f64) / full_scale)
+            .collect();
+
+        // Q31 resolves more than f32 does, so compare with the kernels in f64
+        for (name, fft) in float_kernels::<f64>() {
+            if name == "dft" && size > 1024 {
+                continue;
+            }
+
+            let mut expected = vec![Complex::new(0., 0.); size];
+            fft(&float_input, &mut expected);
+
+            let sqnr = sqnr_db(&output, exponent, full_scale, &expected);
+            assert!(
+                sqnr > min_sqnr_db_q31(size),
+                "q31 against {name} at {size}: {sqnr:.1} dB"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_fft_q15_full_scale() {
+    // A full-scale complex tone puts all of its energy in one bin: the most growth there is
+    let size = 1024;
+    let bin = 37;
+    let input: Vec<_> = (0..size)
+        .map(|i| {
+            let theta = 2. * core::f64::consts::PI * (bin * i) as f64 / size as f64;
+            Complex::new(i16::from_f64(theta.cos()), i16::from_f64(theta.sin()))
+        })
+        .collect();
+    assert!(input.iter().any(|x| x.re == i16::MAX));
+
+    let mut output = vec![Complex::new(0, 0); size];
+    let mut scratch = vec![0; scratch_len(size)];
+    let exponent = fft_q15_with_scratch(&input, &mut output, &mut scratch);
+
+    let scale = 2f64.powi(exponent as i32) / 32768.;
+    for (k, y) in output.iter().enumerate() {
+        let magnitude = Complex::new(y.re as f64, y.im as f64).norm() * scale;
+        let expected = if k == bin { size as f64 } else { 0. };
+        approx::assert_abs_diff_eq!(magnitude, expected, epsilon = 1e-2 * size as f64);
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_fft_q15_simd_matches_scalar() {
+    for size in (1..=12).map(|s| 1 << s) {
+        let mut input = quantized_noise::<i16>(size, 3, 32768.);
+        input[0] = Complex::new(i16::MIN, i16::MAX);
+
+        let mut expected = vec![Complex::new(0, 0); size];
+        let mut scratch = vec![0; scratch_len(size)];
+        let expected_exponent = fixed_fft(&input, &mut expected, &mut scratch, false);
+
+        let mut output = vec![Complex::new(0, 0); size];
+        let exponent = fft_q15_with_scratch(&input, &mut output, &mut scratch);
+
+        assert_eq!(exponent, expected_exponent);
+        assert_eq!(output, expected, "size {size}");
+    }
+}
@@ -0,0 +1,274 @@
+//! Single DFT bins in O(n) with the Goertzel algorithm, for detectors that only need a few
+//! frequencies, such as DTMF or pilot tones.
+//!
+//! Bins need not be integers: `bin` is in cycles per block, so any frequency can be tracked with
+//! any block length. Results have the same scale and phase as the DFT of the block.
+
+use core::f64::consts::TAU;
+
+use num_complex::Complex;
+
+use crate::simd::Float;
+
+/// Streaming Goertzel filter for one bin of consecutive `block_len`-sample blocks.
+#[derive(Debug, Clone)]
+pub struct Goertzel<T> {
+    /// Angle per sample, in `f64` so the phase of long blocks stays exact
+    omega: f64,
+    /// `2 cos(omega)`
+    coeff: T,
+    /// `e^(-j omega)`
+    w: Complex<T>,
+    block_len: usize,
+    count: usize,
+    s1: T,
+    s2: T,
+}
+
+impl<T: Float> Goertzel<T> {
+    /// Bin `bin` of `block_len`-sample blocks.
+    pub fn new(bin: T, block_len: usize) -> Self {
+        assert!(block_len > 0);
+
+        let omega = TAU * bin.to_f64().unwrap() / block_len as f64;
+        let w = polar(-omega);
+
+        Self {
+            omega,
+            coeff: w.re + w.re,
+            w,
+            block_len,
+            count: 0,
+            s1: T::zero(),
+            s2: T::zero(),
+        }
+    }
+
+    /// The bin of `freq` Hz in `block_len`-sample blocks at `sample_rate`.
+    pub fn from_frequency(freq: T, sample_rate: T, block_len: usize) -> Self {
+        Self::new(
+            freq * T::from_usize(block_len).unwrap() / sample_rate,
+            block_len,
+        )
+    }
+
+    pub fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    /// Add one sample. Returns the bin at the end of each block, then starts the next.
+    #[inline]
+    pub fn push(&mut self, x: T) -> Option<Complex<T>> {
+        let s = x + self.coeff * self.s1 - self.s2;
+        self.s2 = self.s1;
+        self.s1 = s;
+        self.count += 1;
+
+        if self.count == self.block_len {
+            let y = self.value();
+            self.reset();
+            Some(y)
+        } else {
+            None
+        }
+    }
+
+    /// [`Goertzel::push`] every sample of `block`, returning the last bin completed, if any.
+    pub fn process(&mut self, block: &[T]) -> Option<Complex<T>> {
+        let mut last = None;
+        for &x in block {
+            if let Some(y) = self.push(x) {
+                last = Some(y);
+            }
+        }
+        last
+    }
+
+    /// Drop the samples of the current block.
+    pub fn reset(&mut self) {
+        self.count = 0;
+        self.s1 = T::zero();
+        self.s2 = T::zero();
+    }
+
+    /// DFT at `omega` of the samples pushed so far, with phase relative to the first of them.
+    fn value(&self) -> Complex<T> {
+        // The filter output, `sum x[m] e^(j omega (n - m))` for the latest sample `n`
+        let y = Complex::new(self.s1, T::zero()) - self.w * self.s2;
+
+        y * polar(-self.omega * (self.count - 1) as f64)
+    }
+}
+
+/// `e^(j angle)`, with the angle in `f64` for precision.
+pub(crate) fn polar<T: Float>(angle: f64) -> Complex<T> {
+    let (sin, cos) = num_traits::Float::sin_cos(angle);
+    Complex::new(T::from_f64(cos).unwrap(), T::from_f64(sin).unwrap())
+}
+
+/// DFT of `input` at `bin`, which need not be an integer.
+pub fn goertzel<T: Float>(input: &[T], bin: T) -> Complex<T> {
+    Goertzel::new(bin, input.len()).process(input).unwrap()
+}
+
+/// [`goertzel`] at each of `bins`.
+pub fn goertzel_bins<T: Float>(input: &[T], bins: &[T], output: &mut [Complex<T>]) {
+    assert_eq!(bins.len(), output.len());
+
+    for (y, &bin) in output.iter_mut().zip(bins) {
+        *y = goertzel(input, bin);
+    }
+}
+
+/// [`Goertzel`] filters for several bins of the same blocks.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct GoertzelBank<T> {
+    filters: alloc::vec::Vec<Goertzel<T>>,
+    results: alloc::vec::Vec<Complex<T>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Float> GoertzelBank<T> {
+    pub fn new(bins: &[T], block_len: usize) -> Self {
+        Self {
+            filters: bins
+                .iter()
+                .map(|&bin| Goertzel::new(bin, block_len))
+                .collect(),
+            results: alloc::vec![Complex::new(T::zero(), T::zero()); bins.len()],
+        }
+    }
+
+    pub fn from_frequencies(freqs: &[T], sample_rate: T, block_len: usize) -> Self {
+        let to_bin = T::from_usize(block_len).unwrap() / sample_rate;
+        let bins: alloc::vec::Vec<T> = freqs.iter().map(|&f| f * to_bin).collect();
+        Self::new(&bins, block_len)
+    }
+
+    /// Add one sample to every filter. Returns `true` at the end of each block, when
+    /// [`GoertzelBank::results`] has been updated.
+    #[inline]
+    pub fn push(&mut self, x: T) -> bool {
+        let mut done = false;
+        for (filter, result) in self.filters.iter_mut().zip(&mut self.results) {
+            if let Some(y) = filter.push(x) {
+                *result = y;
+                done = true;
+            }
+        }
+        done
+    }
+
+    /// [`GoertzelBank::push`] every sample of `block`. Returns `true` if any block ended.
+    pub fn process(&mut self, block: &[T]) -> bool {
+        let mut done = false;
+        for &x in block {
+            done |= self.push(x);
+        }
+        done
+    }
+
+    /// Bins of the last complete block, in the order they were given.
+    pub fn results(&self) -> &[Complex<T>] {
+        &self.results
+    }
+
+    pub fn reset(&mut self) {
+        self.filters.iter_mut().for_each(Goertzel::reset);
+        self.results
+            .iter_mut()
+            .for_each(|y| *y = Complex::new(T::zero(), T::zero()));
+    }
+}
+
+/// Direct `sum x[m] e^(-j 2 pi bin m / n)` in `f64`.
+#[cfg(test)]
+fn direct_dft(input: &[f64], bin: f64) -> Complex<f64> {
+    input
+        .iter()
+        .enumerate()
+        .map(|(m, &x)| x * Complex::from_polar(1., -TAU * bin * m as f64 / input.len() as f64))
+        .sum()
+}
+
+#[test]
+fn test_goertzel_matches_dft() {
+    use alloc::vec;
+    use num_traits::Zero;
+
+    let n = 64;
+    let mut input = vec![0.; n];
+    crate::test_noise(1, &mut input);
+    let input: alloc::vec::Vec<f64> = input.iter().map(|&x| x as f64).collect();
+
+    let complex: alloc::vec::Vec<_> = input.iter().map(|&x| Complex::new(x, 0.)).collect();
+    let mut expected = vec![Complex::zero(); n];
+    crate::dft::dft(&complex, &mut expected);
+
+    for (k, x) in expected.iter().enumerate() {
+        let y = goertzel(&input, k as f64);
+        approx::assert_abs_diff_eq!(y.re, x.re, epsilon = 1e-9);
+        approx::assert_abs_diff_eq!(y.im, x.im, epsilon = 1e-9);
+    }
+}
+
+#[test]
+fn test_goertzel_non_integer_bins() {
+    use alloc::vec;
+    use num_traits::Zero;
+
+    let mut input = vec![0.; 205];
+    crate::test_noise(2, &mut input);
+
+    let bins = [0.5, 10.3, 17.75, 101.9];
+    let mut output = [Complex::zero(); 4];
+    goertzel_bins(&input, &bins, &mut output);
+
+    let input: alloc::vec::Vec<f64> = input.iter().map(|&x| x as f64).collect();
+    for (y, &bin) in output.iter().zip(&bins) {
+        let x = direct_dft(&input, bin as f64);
+        // The recurrence in `f32` loses a few bits more than a direct sum would
+        approx::assert_abs_diff_eq!(y.re as f64, x.re, epsilon = 1e-3);
+        approx::assert_abs_diff_eq!(y.im as f64, x.im, epsilon = 1e-3);
+    }
+}
+
+#[test]
+fn test_goertzel_streaming() {
+    use alloc::{vec, vec::Vec};
+
+    let block_len = 100;
+    let mut input = vec![0.; 1050];
+    crate::test_noise(3, &mut input);
+
+    let mut filter = Goertzel::new(7.5, block_len);
+    let results: Vec<_> = input.iter().filter_map(|&x| filter.push(x)).collect();
+    assert_eq!(results.len(), 10);
+
+    for (y, block) in results.iter().zip(input.chunks_exact(block_len)) {
+        assert_eq!(*y, goertzel(block, 7.5));
+    }
+
+    // The last 50 samples are still pending, so the last block comes back
+    let mut filter = Goertzel::new(7.5, block_len);
+    assert_eq!(filter.process(&input), results.last().copied());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_goertzel_bank() {
+    use alloc::vec;
+
+    let mut bank = GoertzelBank::from_frequencies(&[697., 1209.], 8000., 205);
+    let mut tone = vec![0.; 205];
+    for (i, y) in tone.iter_mut().enumerate() {
+        *y = (TAU * 697. * i as f64 / 8000.).sin();
+    }
+    assert!(!bank.process(&tone[..204]));
+    assert!(bank.push(tone[204]));
+    let [row, col] = bank.results() else {
+        unreachable!()
+    };
+    assert!(row.norm() > 50. * col.norm(), "{row} {col}");
+}
@@ -0,0 +1,65 @@
+//! The DSP core of wasm-audio: FFT kernels, the portable SIMD layer they are written on,
+//! fixed-point transforms, the chirp-Z and constant-Q transforms, single-bin trackers (Goertzel
+//! and the sliding DFT), and fractional-octave band levels with frequency weighting.
+//!
+//! `no_std`, with no dependencies beyond `num-complex` and `num-traits` (maths from `libm`). The
+//! kernels write into caller-provided buffers; the few that need scratch space also have a
+//! variant taking it from the caller. The default `alloc` feature adds the allocating
+//! conveniences, and the analysers that own their buffers: [`goertzel::GoertzelBank`],
+//! [`sliding_dft`], [`chirp_z`], [`cqt`] and [`octave::OctaveFilterBank`].
+
+#![no_std]
+
+#[cfg(any(feature = "alloc", test))]
+extern crate alloc;
+#[cfg(test)]
+extern crate std;
+
+pub mod biquad;
+#[cfg(feature = "alloc")]
+pub mod chirp_z;
+pub mod cooley_tukey;
+#[cfg(feature = "alloc")]
+pub mod cqt;
+pub mod dft;
+pub mod fixed;
+pub mod goertzel;
+pub mod octave;
+pub mod simd;
+pub mod simd_cooley_tukey;
+pub mod simd_cooley_tukey2;
+pub mod simd_cooley_tukey3;
+#[cfg(feature = "alloc")]
+pub mod sliding_dft;
+pub mod weighting;
+
+/// Whether `n` is a power of two that a transform can split, so 2 or more.
+pub fn is_power_of_2(n: usize) -> bool {
+    n > 1 && n.is_power_of_two()
+}
+
+#[test]
+fn test_is_power_of_2() {
+    assert!(!is_power_of_2(0));
+    assert!(!is_power_of_2(1));
+    assert!(is_power_of_2(2));
+    assert!(is_power_of_2(1 << 20));
+    assert!(!is_power_of_2(3));
+    assert!(!is_power_of_2(1000));
+    assert!(!is_power_of_2((1 << 20) + 1));
+}
+
+/// Uniform white noise in `[-1, 1)` from a seeded xorshift, the same sequence as
+/// `wasm_audio::siggen::noise` with `NoiseColor::White`.
+#[cfg(test)]
+fn test_noise(seed: u32, output: &mut [f32]) {
+    let mut state = seed.max(1);
+
+    for y in output {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+
+        *y = (state >> 8) as f32 / (1 << 23) as f32 - 1.;
+    }
+}
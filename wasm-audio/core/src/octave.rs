@@ -0,0 +1,457 @@
+//! Octave and fractional-octave band levels as in IEC 61260-1, from FFT spectra or from a bank of
+//! band-pass filters.
+//!
+//! Bands follow the standard's base-10 system: the octave ratio is `G = 10^(3/10)`, band `x` of a
+//! `1 / b` octave series has its mid-band frequency at `1000 G^(x / b)` Hz for odd `b` or
+//! `1000 G^((2x + 1) / (2b))` Hz for even `b`, and its edges are `G^(1 / (2b))` either side of it.
+
+use core::{f64::consts::PI, ops::RangeInclusive};
+
+use num_complex::Complex;
+use num_traits::Float as Real;
+
+use crate::{biquad::Biquad, simd::Float, weighting::Weighting};
+
+/// The octave frequency ratio of the base-10 system, `10^(3/10)`.
+pub const G: f64 = 1.995_262_314_968_879_5;
+
+/// Mid-band frequency of band 0 of odd-fraction series, in Hz.
+pub const REFERENCE_FREQUENCY: f64 = 1000.;
+
+/// Exact mid-band frequency of band `index` of a `1 / fraction` octave series, in Hz.
+pub fn mid_frequency(fraction: usize, index: i32) -> f64 {
+    assert!(fraction > 0);
+
+    let (b, x) = (fraction as f64, index as f64);
+    let exponent = if fraction % 2 == 1 {
+        x / b
+    } else {
+        (2. * x + 1.) / (2. * b)
+    };
+    REFERENCE_FREQUENCY * Real::powf(G, exponent)
+}
+
+/// Lower and upper edges of band `index` of a `1 / fraction` octave series, in Hz.
+pub fn band_edges(fraction: usize, index: i32) -> (f64, f64) {
+    let mid = mid_frequency(fraction, index);
+    let ratio = Real::powf(G, 1. / (2 * fraction) as f64);
+    (mid / ratio, mid * ratio)
+}
+
+/// Performance class of IEC 61260-1:2014.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    One,
+    Two,
+}
+
+/// Acceptance limits on a filter's attenuation, relative to mid-band, at a breakpoint.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limit {
+    /// The breakpoint is at `G^exponent` times the mid-band frequency of an octave band, and either
+    /// side of it; see [`breakpoint`] for fractional-octave bands.
+    pub exponent: f64,
+    /// Least attenuation, in dB
+    pub min: f64,
+    /// Most attenuation, in dB, infinite in the stop band
+    pub max: f64,
+}
+
+const fn limit(exponent: f64, min: f64, max: f64) -> Limit {
+    Limit { exponent, min, max }
+}
+
+/// IEC 61260-1:2014 Table 1, class 1
+const CLASS_1: [Limit; 9] = [
+    limit(0., -0.4, 0.4),
+    limit(1. / 8., -0.4, 0.5),
+    limit(1. / 4., -0.4, 0.7),
+    limit(3. / 8., -0.4, 1.4),
+    limit(1. / 2., 1.2, 5.3),
+    limit(1., 16.6, f64::INFINITY),
+    limit(2., 40.5, f64::INFINITY),
+    limit(3., 60., f64::INFINITY),
+    limit(4., 70., f64::INFINITY),
+];
+
+/// IEC 61260-1:2014 Table 1, class 2
+const CLASS_2: [Limit; 9] = [
+    limit(0., -0.6, 0.6),
+    limit(1. / 8., -0.6, 0.7),
+    limit(1. / 4., -0.6, 0.9),
+    limit(3. / 8., -0.6, 1.7),
+    limit(1. / 2., 0.8, 5.5),
+    limit(1., 15.6, f64::INFINITY),
+    limit(2., 39.5, f64::INFINITY),
+    limit(3., 54., f64::INFINITY),
+    limit(4., 60., f64::INFINITY),
+];
+
+impl Class {
+    /// The acceptance limits at each breakpoint above mid-band; those below mirror them.
+    pub fn limits(self) -> &'static [Limit] {
+        match self {
+            Self::One => &CLASS_1,
+            Self::Two => &CLASS_2,
+        }
+    }
+}
+
+/// Frequency of the breakpoint at `G^exponent` for octave bands, relative to the mid-band
+/// frequency of a `1 / fraction` octave band: the octave's distance from mid-band, scaled down
+/// to the narrower band. Below mid-band, the breakpoints are at the reciprocals.
+pub fn breakpoint(fraction: usize, exponent: f64) -> f64 {
+    let scale = (Real::powf(G, 1. / (2 * fraction) as f64) - 1.) / (Real::sqrt(G) - 1.);
+    1. + scale * (Real::powf(G, exponent) - 1.)
+}
+
+/// A run of consecutive bands of a `1 / fraction` octave series.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OctaveBands {
+    fraction: usize,
+    indices: RangeInclusive<i32>,
+}
+
+impl OctaveBands {
+    /// Every band of a `1 / fraction` octave series with its mid-band frequency from `min_freq`
+    /// to `max_freq` Hz, such as 1 for octaves, 3 for third-octaves or 6 for sixth-octaves.
+    pub fn new(fraction: usize, min_freq: f64, max_freq: f64) -> Self {
+        assert!(fraction > 0);
+        assert!(0. < min_freq && min_freq <= max_freq);
+
+        // Inverse of `mid_frequency`, with some slack for rounding at exact mid-band frequencies
+        let b = fraction as f64;
+        let index = |freq: f64| {
+            let exponent = Real::log10(freq / REFERENCE_FREQUENCY) / 0.3;
+            if fraction % 2 == 1 {
+                b * exponent
+            } else {
+                b * exponent - 0.5
+            }
+        };
+        let first = Real::ceil(index(min_freq) - 1e-9) as i32;
+        let last = Real::floor(index(max_freq) + 1e-9) as i32;
+        assert!(
+            first <= last,
+            "no band between {min_freq} and {max_freq} Hz"
+        );
+
+        Self {
+            fraction,
+            indices: first..=last,
+        }
+    }
+
+    pub fn fraction(&self) -> usize {
+        self.fraction
+    }
+
+    /// Number of bands.
+    pub fn bands(&self) -> usize {
+        (self.indices.end() - self.indices.start() + 1) as usize
+    }
+
+    /// Index in the series of the `band`th band, 0 being the band at 1 kHz for odd fractions.
+    pub fn index(&self, band: usize) -> i32 {
+        assert!(band < self.bands());
+        self.indices.start() + band as i32
+    }
+
+    pub fn mid_frequency(&self, band: usize) -> f64 {
+        mid_frequency(self.fraction, self.index(band))
+    }
+
+    pub fn edges(&self, band: usize) -> (f64, f64) {
+        band_edges(self.fraction, self.index(band))
+    }
+
+    /// Fourth-order Butterworth band-pass filter for `band`, as four sections. Its edges are
+    /// pre-warped, so they fall exactly on the band edges at `sample_rate`, where the response is
+    /// 3 dB down; the upper edge must be below the Nyquist frequency.
+    ///
+    /// Pre-warping widens the bands near the Nyquist frequency, and a third-order filter's lower
+    /// skirt is then too shallow for class 1: fourth order meets it up to the highest bands.
+    pub fn filter<T: Float>(&self, band: usize, sample_rate: f64) -> [Biquad<T>; 4] {
+        let (f1, f2) = self.edges(band);
+        assert!(f2 < sample_rate / 2.);
+
+        let warp = |f: f64| 2. * sample_rate * Real::tan(PI * f / sample_rate);
+        let (w1, w2) = (warp(f1), warp(f2));
+        let (w0_squared, bandwidth) = (w1 * w2, w2 - w1);
+
+        // Each pole `p` of the low-pass prototype maps to the two roots of `s^2 - p B s + w0^2`.
+        // Their product is real and positive, so for the poles above the real axis, one root is
+        // above it and the other below, each making a section with its conjugate, a root of the
+        // conjugate pole. Each section has one of the band-pass's zeros at DC.
+        let section = |q: Complex<f64>| {
+            Biquad::bilinear(
+                [0., bandwidth, 0.],
+                [1., -2. * q.re, q.norm_sqr()],
+                sample_rate,
+                0.,
+            )
+        };
+        let roots = |angle: f64| {
+            let p = Complex::from_polar(1., angle);
+            let root = (p * p * bandwidth * bandwidth - 4. * w0_squared).sqrt();
+            [(p * bandwidth + root) / 2., (p * bandwidth - root) / 2.]
+        };
+
+        let [q1, q2] = roots(5. * PI / 8.);
+        let [q3, q4] = roots(7. * PI / 8.);
+        [section(q1), section(q2), section(q3), section(q4)]
+    }
+
+    /// Mean square of each band, weighted by `weighting`, from `spectrum`: the first half of the
+    /// FFT of `2 * spectrum.len()` samples at `sample_rate`, as from an unnormalised transform.
+    ///
+    /// Each bin's power is shared between the bands it overlaps in proportion to the overlap, so
+    /// bands narrower than a bin still get their share. As with Parseval's theorem, the result is
+    /// the mean square of the samples transformed: correct for the power a window removes, if one
+    /// was applied.
+    pub fn power_from_spectrum<T: Float>(
+        &self,
+        spectrum: &[Complex<T>],
+        sample_rate: f64,
+        weighting: Weighting,
+        output: &mut [T],
+    ) {
+        assert_eq!(output.len(), self.bands());
+
+        let n = 2 * spectrum.len();
+        let bin_width = sample_rate / n as f64;
+
+        for (band, y) in output.iter_mut().enumerate() {
+            let (f1, f2) = self.edges(band);
+            let (lower, upper) = (f1 / bin_width, f2 / bin_width);
+
+            // Bin `k` covers `k - 1/2` to `k + 1/2`, in bins
+            let first = (Real::round(lower) as usize).min(spectrum.len());
+            let last = (Real::round(upper) as usize + 1).min(spectrum.len());
+
+            let mut power = 0.;
+            for (k, x) in spectrum.iter().enumerate().take(last).skip(first) {
+                let overlap = upper.min(k as f64 + 0.5) - lower.max(k as f64 - 0.5);
+                if overlap > 0. {
+                    let one_sided = if k == 0 { 1. } else { 2. };
+                    let gain = weighting.gain(k as f64 * bin_width);
+                    power += overlap * one_sided * gain * gain * x.norm_sqr().to_f64().unwrap();
+                }
+            }
+
+            *y = T::from_f64(power / (n as f64 * n as f64)).unwrap();
+        }
+    }
+}
+
+/// Band levels from [`OctaveBands::filter`]s, integrated since the last reset.
+///
+/// The weighting is applied as its gain at each band's mid-band frequency, the usual way of
+/// weighting band levels, which is close but not exact for octave bands.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct OctaveFilterBank<T> {
+    bands: OctaveBands,
+    filters: alloc::vec::Vec<[Biquad<T>; 4]>,
+    /// Weighting's power gain at each mid-band frequency
+    weights: alloc::vec::Vec<f64>,
+    energy: alloc::vec::Vec<T>,
+    samples: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Float> OctaveFilterBank<T> {
+    pub fn new(bands: OctaveBands, sample_rate: f64, weighting: Weighting) -> Self {
+        let filters = (0..bands.bands())
+            .map(|band| bands.filter(band, sample_rate))
+            .collect();
+        let weights = (0..bands.bands())
+            .map(|band| Real::powi(weighting.gain(bands.mid_frequency(band)), 2))
+            .collect();
+        let energy = alloc::vec![T::zero(); bands.bands()];
+
+        Self {
+            bands,
+            filters,
+            weights,
+            energy,
+            samples: 0,
+        }
+    }
+
+    pub fn bands(&self) -> &OctaveBands {
+        &self.bands
+    }
+
+    /// Filter `block` through every band, adding to the energies.
+    pub fn process(&mut self, block: &[T]) {
+        for (filter, energy) in self.filters.iter_mut().zip(&mut self.energy) {
+            for &x in block {
+                let y = filter
+                    .iter_mut()
+                    .fold(x, |x, section| section.process_sample(x));
+                *energy = *energy + y * y;
+            }
+        }
+        self.samples += block.len();
+    }
+
+    /// Weighted mean square of each band since the last reset.
+    pub fn mean_squares(&self, output: &mut [T]) {
+        assert_eq!(output.len(), self.energy.len());
+
+        let samples = T::from_usize(self.samples.max(1)).unwrap();
+        for ((y, &energy), &weight) in output.iter_mut().zip(&self.energy).zip(&self.weights) {
+            *y = energy / samples * T::from_f64(weight).unwrap();
+        }
+    }
+
+    /// Clear the energies. The filters keep their state, so levels stay settled across resets.
+    pub fn reset(&mut self) {
+        self.energy.iter_mut().for_each(|x| *x = T::zero());
+        self.samples = 0;
+    }
+}
+
+#[test]
+fn test_mid_frequencies() {
+    // Nominal third-octave mid-band frequencies, which the exact ones round to
+    const NOMINAL: [f64; 30] = [
+        25., 31.5, 40., 50., 63., 80., 100., 125., 160., 200., 250., 315., 400., 500., 630., 800.,
+        1000., 1250., 1600., 2000., 2500., 3150., 4000., 5000., 6300., 8000., 10000., 12500.,
+        16000., 20000.,
+    ];
+
+    let bands = OctaveBands::new(3, 24., 21000.);
+    assert_eq!(bands.bands(), NOMINAL.len());
+    assert_eq!(bands.index(16), 0);
+    for (band, nominal) in NOMINAL.iter().enumerate() {
+        approx::assert_relative_eq!(bands.mid_frequency(band), nominal, max_relative = 0.02);
+    }
+
+    // Octaves are every third third-octave, and even fractions straddle 1 kHz
+    let octaves = OctaveBands::new(1, 31., 16000.);
+    assert_eq!(octaves.bands(), 10);
+    approx::assert_relative_eq!(octaves.mid_frequency(5), 1000.);
+    approx::assert_relative_eq!(octaves.mid_frequency(6), G * 1000.);
+    approx::assert_relative_eq!(band_edges(6, -1).1, band_edges(6, 0).0);
+    approx::assert_relative_eq!(band_edges(6, 0).0, 1000.);
+}
+
+#[test]
+fn test_filters_meet_class_1() {
+    let sample_rate = 48000.;
+
+    // Up to the highest bands below the Nyquist frequency, which pre-warping widens the most
+    for (fraction, max_freq) in [(1, 16000.), (3, 20000.), (6, 20000.)] {
+        let bands = OctaveBands::new(fraction, 20., max_freq);
+
+        for band in 0..bands.bands() {
+            let filter = bands.filter::<f64>(band, sample_rate);
+            let mid = bands.mid_frequency(band);
+
+            for limit in Class::One.limits() {
+                let ratio = breakpoint(fraction, limit.exponent);
+                for freq in [mid * ratio, mid / ratio] {
+                    if freq >= sample_rate / 2. {
+                        continue;
+                    }
+
+                    let response = crate::biquad::cascade_response(&filter, freq, sample_rate);
+                    let attenuation = -20. * Real::log10(response.norm());
+                    assert!(
+                        limit.min <= attenuation && attenuation <= limit.max,
+                        "1/{fraction} octave at {mid:.1} Hz: {attenuation:.2} dB at {freq:.1} Hz"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_filter_bank_sine() {
+    let sample_rate = 48000.;
+    let bands = OctaveBands::new(3, 100., 10000.);
+    let mut bank = OctaveFilterBank::<f64>::new(bands.clone(), sample_rate, Weighting::Z);
+
+    // A unit sine on the 1 kHz band, after the filters settle
+    let band = (0..bands.bands())
+        .find(|&band| bands.index(band) == 0)
+        .unwrap();
+    let sine: alloc::vec::Vec<f64> = (0..48000)
+        .map(|n| Real::sin(2. * PI * 1000. * n as f64 / sample_rate))
+        .collect();
+    bank.process(&sine[..24000]);
+    bank.reset();
+    bank.process(&sine[24000..]);
+
+    let mut levels = alloc::vec![0.; bands.bands()];
+    bank.mean_squares(&mut levels);
+    approx::assert_abs_diff_eq!(levels[band], 0.5, epsilon = 1e-3);
+
+    // The bands cross over 3 dB down at their edges, and a band away a fourth-order filter is
+    // over 23 dB down
+    assert!(
+        levels[band - 1] < 2.5e-3 && levels[band + 1] < 2.5e-3,
+        "{levels:?}"
+    );
+}
+
+#[test]
+fn test_power_from_spectrum() {
+    use alloc::vec;
+    use num_traits::Zero;
+
+    // A unit sine on a bin in the 1 kHz octave
+    let (n, sample_rate) = (8192, 48000.);
+    let bin = 171;
+    let freq = bin as f64 * sample_rate / n as f64;
+    let input: alloc::vec::Vec<_> = (0..n)
+        .map(|i| Complex::new(Real::sin(2. * PI * freq * i as f64 / sample_rate), 0.))
+        .collect();
+    let mut spectrum = vec![Complex::zero(); n];
+    crate::simd_cooley_tukey::simd_cooley_tukey_fft(&input, &mut spectrum);
+    let spectrum = &spectrum[..n / 2];
+
+    let bands = OctaveBands::new(1, 63., 16000.);
+    let mut power = vec![0.; bands.bands()];
+    bands.power_from_spectrum(spectrum, sample_rate, Weighting::Z, &mut power);
+    for (band, &p) in power.iter().enumerate() {
+        let expected = if bands.index(band) == 0 { 0.5 } else { 0. };
+        approx::assert_abs_diff_eq!(p, expected, epsilon = 1e-9);
+    }
+
+    // Weighted by the A curve's power gain at the sine's frequency
+    bands.power_from_spectrum(spectrum, sample_rate, Weighting::A, &mut power);
+    let gain = Weighting::A.gain(freq);
+    approx::assert_relative_eq!(power[4], 0.5 * gain * gain, max_relative = 1e-9);
+
+    // Bands covering the whole spectrum share every bin's power between them, with nothing lost
+    let mut noise = vec![0.; n];
+    crate::test_noise(8, &mut noise);
+    let mean = noise.iter().map(|&x| x as f64).sum::<f64>() / n as f64;
+    let input: alloc::vec::Vec<_> = noise
+        .iter()
+        .map(|&x| Complex::new(x as f64 - mean, 0.))
+        .collect();
+    let mut spectrum = vec![Complex::zero(); n];
+    crate::simd_cooley_tukey::simd_cooley_tukey_fft(&input, &mut spectrum);
+
+    let bands = OctaveBands::new(3, 3., 26000.);
+    let (lower, _) = bands.edges(0);
+    let (_, upper) = bands.edges(bands.bands() - 1);
+    assert!(lower < sample_rate / (2 * n) as f64 && upper > sample_rate / 2.);
+
+    let mut power = vec![0.; bands.bands()];
+    bands.power_from_spectrum(&spectrum[..n / 2], sample_rate, Weighting::Z, &mut power);
+    let mean_square = input.iter().map(|x| x.re * x.re).sum::<f64>() / n as f64;
+    // All but the Nyquist bin, which isn't in the half spectrum
+    let nyquist = spectrum[n / 2].norm_sqr() / (n as f64 * n as f64);
+    approx::assert_relative_eq!(
+        power.iter().sum::<f64>() + nyquist,
+        mean_square,
+        max_relative = 1e-9
+    );
+}
@@ -0,0 +1,1083 @@
+//! Small portable layer of 4-lane vectors, so each SIMD kernel is written once and runs on every
+//! target, in `f32` or `f64`.
+//!
+//! Each `f32` backend is a newtype around the target's 128-bit vector, and each `f64` backend a
+//! [`Pair`] of them. Kernels are generic over [`Simd4`] and marked `#[inline(always)]` all the
+//! way down, so that they compile to straight vector code inside whichever `#[target_feature]`
+//! function instantiates them. The web build only enables `simd128` on the kernel entry points,
+//! and AVX2 is only enabled on the entry points a host picks at runtime
+//! (`wasm_audio::dispatch`). For the same reason kernels use `for` loops rather than closures,
+//! which would not inherit the feature.
+
+use core::{
+    fmt::Debug,
+    ops::{Add, Mul, Sub},
+};
+
+use num_traits::{FloatConst, FromPrimitive, Signed};
+
+/// Sample type of the transforms: `f32` or `f64`.
+///
+/// The bounds cover rustfft's `FftNum`, so hosts can run rustfft on the same type.
+pub trait Float:
+    num_traits::Float + FloatConst + FromPrimitive + Signed + Debug + Send + Sync + 'static
+{
+    /// Fastest backend for this type that every CPU of the target supports
+    type Native: Simd4<Elem = Self>;
+}
+
+impl Float for f32 {
+    type Native = Native;
+}
+
+impl Float for f64 {
+    type Native = Native64;
+}
+
+/// Four lanes of [`Simd4::Elem`]. Lane comments below list the result as
+/// `[lane0, lane1, lane2, lane3]`.
+pub trait Simd4: Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> {
+    type Elem: Float;
+
+    /// Backend name, for reports and test messages
+    const NAME: &'static str;
+
+    fn new(x0: Self::Elem, x1: Self::Elem, x2: Self::Elem, x3: Self::Elem) -> Self;
+    fn splat(x: Self::Elem) -> Self;
+    fn to_array(self) -> [Self::Elem; 4];
+
+    /// `self * b + c`, fused on backends with FMA
+    fn mul_add(self, b: Self, c: Self) -> Self;
+    /// `self * b - c`, fused on backends with FMA
+    fn mul_sub(self, b: Self, c: Self) -> Self;
+
+    /// `[x1, x0, x3, x2]`
+    fn swap_pairs(self) -> Self;
+    /// `[x0, x0, x2, x2]`
+    fn dup_even(self) -> Self;
+    /// `[x1, x1, x3, x3]`
+    fn dup_odd(self) -> Self;
+    /// `[a0, a1, b0, b1]`
+    fn low_halves(self, b: Self) -> Self;
+    /// `[a2, a3, b2, b3]`
+    fn high_halves(self, b: Self) -> Self;
+    /// `[a0, b1, a2, b3]`
+    fn blend_odd(self, b: Self) -> Self;
+}
+
+/// `f32` backend every CPU of the target supports. On x86_64, `wasm_audio::dispatch` upgrades to
+/// AVX2 at runtime where available. On wasm, `simd128` is used unless the `simd128` feature is
+/// disabled (and the target feature isn't enabled globally), so the module loads without SIMD
+/// support.
+#[cfg(all(
+    target_arch = "wasm32",
+    any(feature = "simd128", target_feature = "simd128")
+))]
+pub type Native = Simd128;
+#[cfg(target_arch = "x86_64")]
+pub type Native = Sse2;
+#[cfg(target_arch = "aarch64")]
+pub type Native = Neon;
+#[cfg(not(any(
+    all(
+        target_arch = "wasm32",
+        any(feature = "simd128", target_feature = "simd128")
+    ),
+    target_arch = "x86_64",
+    target_arch = "aarch64"
+)))]
+pub type Native = Scalar;
+
+/// `f64` counterpart of [`Native`], with the same cfgs.
+#[cfg(all(
+    target_arch = "wasm32",
+    any(feature = "simd128", target_feature = "simd128")
+))]
+pub type Native64 = Pair<Simd128F64>;
+#[cfg(target_arch = "x86_64")]
+pub type Native64 = Pair<Sse2F64>;
+#[cfg(target_arch = "aarch64")]
+pub type Native64 = Pair<NeonF64>;
+#[cfg(not(any(
+    all(
+        target_arch = "wasm32",
+        any(feature = "simd128", target_feature = "simd128")
+    ),
+    target_arch = "x86_64",
+    target_arch = "aarch64"
+)))]
+pub type Native64 = Scalar<f64>;
+
+/// Implement the arithmetic operators with one intrinsic each.
+// Targets on the scalar backend have no intrinsics to implement them with
+#[cfg_attr(
+    not(any(
+        all(
+            target_arch = "wasm32",
+            any(feature = "simd128", target_feature = "simd128")
+        ),
+        target_arch = "x86_64",
+        target_arch = "aarch64"
+    )),
+    allow(unused_macros)
+)]
+macro_rules! impl_ops {
+    ($t:ident, $add:ident, $sub:ident, $mul:ident) => {
+        impl_ops!(@impl [], $t, $add, $sub, $mul);
+    };
+    // For intrinsics that are `unsafe` to call, where the backend type itself guarantees the
+    // target feature is present
+    (unsafe $t:ident, $add:ident, $sub:ident, $mul:ident) => {
+        impl_ops!(@impl [unsafe], $t, $add, $sub, $mul);
+    };
+    (@impl [$($unsafe:tt)?], $t:ident, $add:ident, $sub:ident, $mul:ident) => {
+        impl Add for $t {
+            type Output = Self;
+
+            #[inline(always)]
+            fn add(self, rhs: Self) -> Self {
+                Self($($unsafe)? { $add(self.0, rhs.0) })
+            }
+        }
+
+        impl Sub for $t {
+            type Output = Self;
+
+            #[inline(always)]
+            fn sub(self, rhs: Self) -> Self {
+                Self($($unsafe)? { $sub(self.0, rhs.0) })
+            }
+        }
+
+        impl Mul for $t {
+            type Output = Self;
+
+            #[inline(always)]
+            fn mul(self, rhs: Self) -> Self {
+                Self($($unsafe)? { $mul(self.0, rhs.0) })
+            }
+        }
+    };
+}
+
+/// Plain arrays, for targets without SIMD and as the reference for the other backends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scalar<T = f32>(pub [T; 4]);
+
+fn scalar_add<T: Float>(a: [T; 4], b: [T; 4]) -> [T; 4] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]]
+}
+
+fn scalar_sub<T: Float>(a: [T; 4], b: [T; 4]) -> [T; 4] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2], a[3] - b[3]]
+}
+
+fn scalar_mul<T: Float>(a: [T; 4], b: [T; 4]) -> [T; 4] {
+    [a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
+}
+
+impl<T: Float> Add for Scalar<T> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self {
+        Self(scalar_add(self.0, rhs.0))
+    }
+}
+
+impl<T: Float> Sub for Scalar<T> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(self, rhs: Self) -> Self {
+        Self(scalar_sub(self.0, rhs.0))
+    }
+}
+
+impl<T: Float> Mul for Scalar<T> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, rhs: Self) -> Self {
+        Self(scalar_mul(self.0, rhs.0))
+    }
+}
+
+impl<T: Float> Simd4 for Scalar<T> {
+    type Elem = T;
+
+    const NAME: &'static str = "scalar";
+
+    #[inline(always)]
+    fn new(x0: T, x1: T, x2: T, x3: T) -> Self {
+        Self([x0, x1, x2, x3])
+    }
+
+    #[inline(always)]
+    fn splat(x: T) -> Self {
+        Self([x; 4])
+    }
+
+    #[inline(always)]
+    fn to_array(self) -> [T; 4] {
+        self.0
+    }
+
+    // Not `Float::mul_add`, which is a slow library call without hardware FMA
+    #[inline(always)]
+    fn mul_add(self, b: Self, c: Self) -> Self {
+        self * b + c
+    }
+
+    #[inline(always)]
+    fn mul_sub(self, b: Self, c: Self) -> Self {
+        self * b - c
+    }
+
+    #[inline(always)]
+    fn swap_pairs(self) -> Self {
+        let [x0, x1, x2, x3] = self.0;
+        Self([x1, x0, x3, x2])
+    }
+
+    #[inline(always)]
+    fn dup_even(self) -> Self {
+        let [x0, _, x2, _] = self.0;
+        Self([x0, x0, x2, x2])
+    }
+
+    #[inline(always)]
+    fn dup_odd(self) -> Self {
+        let [_, x1, _, x3] = self.0;
+        Self([x1, x1, x3, x3])
+    }
+
+    #[inline(always)]
+    fn low_halves(self, b: Self) -> Self {
+        Self([self.0[0], self.0[1], b.0[0], b.0[1]])
+    }
+
+    #[inline(always)]
+    fn high_halves(self, b: Self) -> Self {
+        Self([self.0[2], self.0[3], b.0[2], b.0[3]])
+    }
+
+    #[inline(always)]
+    fn blend_odd(self, b: Self) -> Self {
+        Self([self.0[0], b.0[1], self.0[2], b.0[3]])
+    }
+}
+
+/// Two `f64` lanes, the most a 128-bit vector holds. [`Pair`] puts two together to make a
+/// [`Simd4`].
+pub trait F64x2: Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> {
+    const NAME: &'static str;
+
+    fn new(x0: f64, x1: f64) -> Self;
+    fn splat(x: f64) -> Self;
+    fn to_array(self) -> [f64; 2];
+
+    /// `self * b + c`
+    fn mul_add(self, b: Self, c: Self) -> Self;
+    /// `self * b - c`
+    fn mul_sub(self, b: Self, c: Self) -> Self;
+
+    /// `[x1, x0]`
+    fn swap(self) -> Self;
+    /// `[x0, x0]`
+    fn dup_low(self) -> Self;
+    /// `[x1, x1]`
+    fn dup_high(self) -> Self;
+    /// `[a0, b1]`
+    fn blend_high(self, b: Self) -> Self;
+}
+
+/// Four `f64` lanes as `[low, high]` halves. The half shuffles of [`Simd4`] are free.
+#[derive(Debug, Clone, Copy)]
+pub struct Pair<V>(pub V, pub V);
+
+impl<V: F64x2> Add for Pair<V> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0, self.1 + rhs.1)
+    }
+}
+
+impl<V: F64x2> Sub for Pair<V> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0, self.1 - rhs.1)
+    }
+}
+
+impl<V: F64x2> Mul for Pair<V> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, rhs: Self) -> Self {
+        Self(self.0 * rhs.0, self.1 * rhs.1)
+    }
+}
+
+impl<V: F64x2> Simd4 for Pair<V> {
+    type Elem = f64;
+
+    const NAME: &'static str = V::NAME;
+
+    #[inline(always)]
+    fn new(x0: f64, x1: f64, x2: f64, x3: f64) -> Self {
+        Self(V::new(x0, x1), V::new(x2, x3))
+    }
+
+    #[inline(always)]
+    fn splat(x: f64) -> Self {
+        Self(V::splat(x), V::splat(x))
+    }
+
+    #[inline(always)]
+    fn to_array(self) -> [f64; 4] {
+        let [x0, x1] = self.0.to_array();
+        let [x2, x3] = self.1.to_array();
+        [x0, x1, x2, x3]
+    }
+
+    #[inline(always)]
+    fn mul_add(self, b: Self, c: Self) -> Self {
+        Self(self.0.mul_add(b.0, c.0), self.1.mul_add(b.1, c.1))
+    }
+
+    #[inline(always)]
+    fn mul_sub(self, b: Self, c: Self) -> Self {
+        Self(self.0.mul_sub(b.0, c.0), self.1.mul_sub(b.1, c.1))
+    }
+
+    #[inline(always)]
+    fn swap_pairs(self) -> Self {
+        Self(self.0.swap(), self.1.swap())
+    }
+
+    #[inline(always)]
+    fn dup_even(self) -> Self {
+        Self(self.0.dup_low(), self.1.dup_low())
+    }
+
+    #[inline(always)]
+    fn dup_odd(self) -> Self {
+        Self(self.0.dup_high(), self.1.dup_high())
+    }
+
+    #[inline(always)]
+    fn low_halves(self, b: Self) -> Self {
+        Self(self.0, b.0)
+    }
+
+    #[inline(always)]
+    fn high_halves(self, b: Self) -> Self {
+        Self(self.1, b.1)
+    }
+
+    #[inline(always)]
+    fn blend_odd(self, b: Self) -> Self {
+        Self(self.0.blend_high(b.0), self.1.blend_high(b.1))
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "relaxed-simd"))]
+pub use self::wasm32::Relaxed;
+#[cfg(all(
+    target_arch = "wasm32",
+    any(feature = "simd128", target_feature = "simd128")
+))]
+pub use self::wasm32::{Simd128, Simd128F64};
+
+#[cfg(all(
+    target_arch = "wasm32",
+    any(feature = "simd128", target_feature = "simd128")
+))]
+mod wasm32 {
+    use super::*;
+    use core::arch::wasm32::*;
+
+    /// wasm `simd128`. Only emits vector instructions inside a function with `simd128` enabled.
+    #[derive(Debug, Clone, Copy)]
+    pub struct Simd128(pub v128);
+
+    impl_ops!(Simd128, f32x4_add, f32x4_sub, f32x4_mul);
+
+    impl Simd4 for Simd128 {
+        type Elem = f32;
+
+        const NAME: &'static str = "simd128";
+
+        #[inline(always)]
+        fn new(x0: f32, x1: f32, x2: f32, x3: f32) -> Self {
+            Self(f32x4(x0, x1, x2, x3))
+        }
+
+        #[inline(always)]
+        fn splat(x: f32) -> Self {
+            Self(f32x4_splat(x))
+        }
+
+        #[inline(always)]
+        fn to_array(self) -> [f32; 4] {
+            [
+                f32x4_extract_lane::<0>(self.0),
+                f32x4_extract_lane::<1>(self.0),
+                f32x4_extract_lane::<2>(self.0),
+                f32x4_extract_lane::<3>(self.0),
+            ]
+        }
+
+        #[inline(always)]
+        fn mul_add(self, b: Self, c: Self) -> Self {
+            self * b + c
+        }
+
+        #[inline(always)]
+        fn mul_sub(self, b: Self, c: Self) -> Self {
+            self * b - c
+        }
+
+        #[inline(always)]
+        fn swap_pairs(self) -> Self {
+            Self(i32x4_shuffle::<1, 0, 3, 2>(self.0, self.0))
+        }
+
+        #[inline(always)]
+        fn dup_even(self) -> Self {
+            Self(i32x4_shuffle::<0, 0, 2, 2>(self.0, self.0))
+        }
+
+        #[inline(always)]
+        fn dup_odd(self) -> Self {
+            Self(i32x4_shuffle::<1, 1, 3, 3>(self.0, self.0))
+        }
+
+        #[inline(always)]
+        fn low_halves(self, b: Self) -> Self {
+            Self(i32x4_shuffle::<0, 1, 4, 5>(self.0, b.0))
+        }
+
+        #[inline(always)]
+        fn high_halves(self, b: Self) -> Self {
+            Self(i32x4_shuffle::<2, 3, 6, 7>(self.0, b.0))
+        }
+
+        #[inline(always)]
+        fn blend_odd(self, b: Self) -> Self {
+            Self(i32x4_shuffle::<0, 5, 2, 7>(self.0, b.0))
+        }
+    }
+
+    /// wasm `simd128` as `f64x2`, with the same caveat as [`Simd128`].
+    #[derive(Debug, Clone, Copy)]
+    pub struct Simd128F64(pub v128);
+
+    impl_ops!(Simd128F64, f64x2_add, f64x2_sub, f64x2_mul);
+
+    impl F64x2 for Simd128F64 {
+        const NAME: &'static str = "simd128";
+
+        #[inline(always)]
+        fn new(x0: f64, x1: f64) -> Self {
+            Self(f64x2(x0, x1))
+        }
+
+        #[inline(always)]
+        fn splat(x: f64) -> Self {
+            Self(f64x2_splat(x))
+        }
+
+        #[inline(always)]
+        fn to_array(self) -> [f64; 2] {
+            [
+                f64x2_extract_lane::<0>(self.0),
+                f64x2_extract_lane::<1>(self.0),
+            ]
+        }
+
+        #[inline(always)]
+        fn mul_add(self, b: Self, c: Self) -> Self {
+            self * b + c
+        }
+
+        #[inline(always)]
+        fn mul_sub(self, b: Self, c: Self) -> Self {
+            self * b - c
+        }
+
+        #[inline(always)]
+        fn swap(self) -> Self {
+            Self(i64x2_shuffle::<1, 0>(self.0, self.0))
+        }
+
+        #[inline(always)]
+        fn dup_low(self) -> Self {
+            Self(i64x2_shuffle::<0, 0>(self.0, self.0))
+        }
+
+        #[inline(always)]
+        fn dup_high(self) -> Self {
+            Self(i64x2_shuffle::<1, 1>(self.0, self.0))
+        }
+
+        #[inline(always)]
+        fn blend_high(self, b: Self) -> Self {
+            Self(i64x2_shuffle::<0, 3>(self.0, b.0))
+        }
+    }
+
+    /// [`Simd128`] with multiply-adds from the relaxed-simd proposal. Only emits vector
+    /// instructions inside a function with both `simd128` and `relaxed-simd` enabled.
+    ///
+    /// The engine decides whether `f32x4.relaxed_madd` is fused, so results can differ in the
+    /// last bits between browsers and CPUs. Kernels on this backend are only required to stay
+    /// within the error bounds checked in [`crate::accuracy`].
+    #[cfg(feature = "relaxed-simd")]
+    #[derive(Debug, Clone, Copy)]
+    pub struct Relaxed(pub v128);
+
+    #[cfg(feature = "relaxed-simd")]
+    impl_ops!(Relaxed, f32x4_add, f32x4_sub, f32x4_mul);
+
+    #[cfg(feature = "relaxed-simd")]
+    impl Simd4 for Relaxed {
+        type Elem = f32;
+
+        const NAME: &'static str = "relaxed-simd";
+
+        #[inline(always)]
+        fn new(x0: f32, x1: f32, x2: f32, x3: f32) -> Self {
+            Self(f32x4(x0, x1, x2, x3))
+        }
+
+        #[inline(always)]
+        fn splat(x: f32) -> Self {
+            Self(f32x4_splat(x))
+        }
+
+        #[inline(always)]
+        fn to_array(self) -> [f32; 4] {
+            Simd128(self.0).to_array()
+        }
+
+        #[inline(always)]
+        fn mul_add(self, b: Self, c: Self) -> Self {
+            Self(f32x4_relaxed_madd(self.0, b.0, c.0))
+        }
+
+        #[inline(always)]
+        fn mul_sub(self, b: Self, c: Self) -> Self {
+            Self(f32x4_relaxed_madd(self.0, b.0, f32x4_neg(c.0)))
+        }
+
+        // The shuffles are the same instructions as `Simd128`
+
+        #[inline(always)]
+        fn swap_pairs(self) -> Self {
+            Self(Simd128(self.0).swap_pairs().0)
+        }
+
+        #[inline(always)]
+        fn dup_even(self) -> Self {
+            Self(Simd128(self.0).dup_even().0)
+        }
+
+        #[inline(always)]
+        fn dup_odd(self) -> Self {
+            Self(Simd128(self.0).dup_odd().0)
+        }
+
+        #[inline(always)]
+        fn low_halves(self, b: Self) -> Self {
+            Self(Simd128(self.0).low_halves(Simd128(b.0)).0)
+        }
+
+        #[inline(always)]
+        fn high_halves(self, b: Self) -> Self {
+            Self(Simd128(self.0).high_halves(Simd128(b.0)).0)
+        }
+
+        #[inline(always)]
+        fn blend_odd(self, b: Self) -> Self {
+            Self(Simd128(self.0).blend_odd(Simd128(b.0)).0)
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub(crate) use self::x86_64::Avx2;
+/// [`Simd4::NAME`] of the crate-private AVX2 backend, run by
+/// [`crate::simd_cooley_tukey::simd_cooley_tukey_fft_avx2`].
+#[cfg(target_arch = "x86_64")]
+pub const AVX2_NAME: &str = Avx2::NAME;
+#[cfg(target_arch = "x86_64")]
+pub use self::x86_64::{Sse2, Sse2F64};
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64 {
+    use super::*;
+    use core::arch::x86_64::*;
+
+    /// `_MM_SHUFFLE(z, y, x, w)`: `[a[w], a[x], b[y], b[z]]` from `_mm_shuffle_ps(a, b)`
+    const fn shuffle_mask(w: i32, x: i32, y: i32, z: i32) -> i32 {
+        (z << 6) | (y << 4) | (x << 2) | w
+    }
+
+    #[inline(always)]
+    fn to_array(x: __m128) -> [f32; 4] {
+        let mut out = [0.; 4];
+        // SAFETY: `out` has room for 4 lanes, and `storeu` has no alignment requirement.
+        unsafe { _mm_storeu_ps(out.as_mut_ptr(), x) };
+        out
+    }
+
+    /// SSE2, which every x86_64 CPU has, so its intrinsics are always safe to call.
+    #[derive(Debug, Clone, Copy)]
+    pub struct Sse2(pub __m128);
+
+    impl_ops!(unsafe Sse2, _mm_add_ps, _mm_sub_ps, _mm_mul_ps);
+
+    impl Simd4 for Sse2 {
+        type Elem = f32;
+
+        const NAME: &'static str = "sse2";
+
+        #[inline(always)]
+        fn new(x0: f32, x1: f32, x2: f32, x3: f32) -> Self {
+            Self(unsafe { _mm_setr_ps(x0, x1, x2, x3) })
+        }
+
+        #[inline(always)]
+        fn splat(x: f32) -> Self {
+            Self(unsafe { _mm_set1_ps(x) })
+        }
+
+        #[inline(always)]
+        fn to_array(self) -> [f32; 4] {
+            to_array(self.0)
+        }
+
+        #[inline(always)]
+        fn mul_add(self, b: Self, c: Self) -> Self {
+            self * b + c
+        }
+
+        #[inline(always)]
+        fn mul_sub(self, b: Self, c: Self) -> Self {
+            self * b - c
+        }
+
+        #[inline(always)]
+        fn swap_pairs(self) -> Self {
+            Self(unsafe { _mm_shuffle_ps::<{ shuffle_mask(1, 0, 3, 2) }>(self.0, self.0) })
+        }
+
+        #[inline(always)]
+        fn dup_even(self) -> Self {
+            Self(unsafe { _mm_shuffle_ps::<{ shuffle_mask(0, 0, 2, 2) }>(self.0, self.0) })
+        }
+
+        #[inline(always)]
+        fn dup_odd(self) -> Self {
+            Self(unsafe { _mm_shuffle_ps::<{ shuffle_mask(1, 1, 3, 3) }>(self.0, self.0) })
+        }
+
+        #[inline(always)]
+        fn low_halves(self, b: Self) -> Self {
+            Self(unsafe { _mm_movelh_ps(self.0, b.0) })
+        }
+
+        #[inline(always)]
+        fn high_halves(self, b: Self) -> Self {
+            Self(unsafe { _mm_movehl_ps(b.0, self.0) })
+        }
+
+        #[inline(always)]
+        fn blend_odd(self, b: Self) -> Self {
+            // No blend before SSE4.1: gather [a0, a2, b1, b3], then reorder
+            let t = unsafe { _mm_shuffle_ps::<{ shuffle_mask(0, 2, 1, 3) }>(self.0, b.0) };
+            Self(unsafe { _mm_shuffle_ps::<{ shuffle_mask(0, 2, 1, 3) }>(t, t) })
+        }
+    }
+
+    /// SSE2 as `f64x2`, always safe to call for the same reason as [`Sse2`].
+    #[derive(Debug, Clone, Copy)]
+    pub struct Sse2F64(pub __m128d);
+
+    impl_ops!(unsafe Sse2F64, _mm_add_pd, _mm_sub_pd, _mm_mul_pd);
+
+    impl F64x2 for Sse2F64 {
+        const NAME: &'static str = "sse2";
+
+        #[inline(always)]
+        fn new(x0: f64, x1: f64) -> Self {
+            Self(unsafe { _mm_setr_pd(x0, x1) })
+        }
+
+        #[inline(always)]
+        fn splat(x: f64) -> Self {
+            Self(unsafe { _mm_set1_pd(x) })
+        }
+
+        #[inline(always)]
+        fn to_array(self) -> [f64; 2] {
+            let mut out = [0.; 2];
+            // SAFETY: `out` has room for 2 lanes, and `storeu` has no alignment requirement.
+            unsafe { _mm_storeu_pd(out.as_mut_ptr(), self.0) };
+            out
+        }
+
+        #[inline(always)]
+        fn mul_add(self, b: Self, c: Self) -> Self {
+            self * b + c
+        }
+
+        #[inline(always)]
+        fn mul_sub(self, b: Self, c: Self) -> Self {
+            self * b - c
+        }
+
+        #[inline(always)]
+        fn swap(self) -> Self {
+            Self(unsafe { _mm_shuffle_pd::<0b01>(self.0, self.0) })
+        }
+
+        #[inline(always)]
+        fn dup_low(self) -> Self {
+            Self(unsafe { _mm_unpacklo_pd(self.0, self.0) })
+        }
+
+        #[inline(always)]
+        fn dup_high(self) -> Self {
+            Self(unsafe { _mm_unpackhi_pd(self.0, self.0) })
+        }
+
+        #[inline(always)]
+        fn blend_high(self, b: Self) -> Self {
+            // Low lane from the second argument, high lane from the first
+            Self(unsafe { _mm_move_sd(b.0, self.0) })
+        }
+    }
+
+    /// AVX2 with FMA: fused multiply-add and single-instruction shuffles and blends.
+    ///
+    /// Crate-private because its intrinsics are only safe to call on a CPU with both features.
+    /// It must only be used from `#[target_feature(enable = "avx2,fma")]` functions, which are
+    /// `unsafe` to call until the host has detected them.
+    #[derive(Debug, Clone, Copy)]
+    pub(crate) struct Avx2(__m128);
+
+    impl_ops!(unsafe Avx2, _mm_add_ps, _mm_sub_ps, _mm_mul_ps);
+
+    impl Simd4 for Avx2 {
+        type Elem = f32;
+
+        const NAME: &'static str = "avx2";
+
+        #[inline(always)]
+        fn new(x0: f32, x1: f32, x2: f32, x3: f32) -> Self {
+            Self(unsafe { _mm_setr_ps(x0, x1, x2, x3) })
+        }
+
+        #[inline(always)]
+        fn splat(x: f32) -> Self {
+            Self(unsafe { _mm_set1_ps(x) })
+        }
+
+        #[inline(always)]
+        fn to_array(self) -> [f32; 4] {
+            to_array(self.0)
+        }
+
+        #[inline(always)]
+        fn mul_add(self, b: Self, c: Self) -> Self {
+            Self(unsafe { _mm_fmadd_ps(self.0, b.0, c.0) })
+        }
+
+        #[inline(always)]
+        fn mul_sub(self, b: Self, c: Self) -> Self {
+            Self(unsafe { _mm_fmsub_ps(self.0, b.0, c.0) })
+        }
+
+        #[inline(always)]
+        fn swap_pairs(self) -> Self {
+            Self(unsafe { _mm_permute_ps::<{ shuffle_mask(1, 0, 3, 2) }>(self.0) })
+        }
+
+        #[inline(always)]
+        fn dup_even(self) -> Self {
+            Self(unsafe { _mm_moveldup_ps(self.0) })
+        }
+
+        #[inline(always)]
+        fn dup_odd(self) -> Self {
+            Self(unsafe { _mm_movehdup_ps(self.0) })
+        }
+
+        #[inline(always)]
+        fn low_halves(self, b: Self) -> Self {
+            Self(unsafe { _mm_movelh_ps(self.0, b.0) })
+        }
+
+        #[inline(always)]
+        fn high_halves(self, b: Self) -> Self {
+            Self(unsafe { _mm_movehl_ps(b.0, self.0) })
+        }
+
+        #[inline(always)]
+        fn blend_odd(self, b: Self) -> Self {
+            Self(unsafe { _mm_blend_ps::<0b1010>(self.0, b.0) })
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub use self::aarch64::{Neon, NeonF64};
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64 {
+    use super::*;
+    use core::arch::aarch64::*;
+
+    /// NEON, which every aarch64 CPU has, so its intrinsics are always safe to call.
+    #[derive(Debug, Clone, Copy)]
+    pub struct Neon(pub float32x4_t);
+
+    impl_ops!(unsafe Neon, vaddq_f32, vsubq_f32, vmulq_f32);
+
+    impl Simd4 for Neon {
+        type Elem = f32;
+
+        const NAME: &'static str = "neon";
+
+        #[inline(always)]
+        fn new(x0: f32, x1: f32, x2: f32, x3: f32) -> Self {
+            let lanes = [x0, x1, x2, x3];
+            // SAFETY: `lanes` holds 4 floats.
+            Self(unsafe { vld1q_f32(lanes.as_ptr()) })
+        }
+
+        #[inline(always)]
+        fn splat(x: f32) -> Self {
+            Self(unsafe { vdupq_n_f32(x) })
+        }
+
+        #[inline(always)]
+        fn to_array(self) -> [f32; 4] {
+            let mut out = [0.; 4];
+            // SAFETY: `out` has room for 4 floats.
+            unsafe { vst1q_f32(out.as_mut_ptr(), self.0) };
+            out
+        }
+
+        #[inline(always)]
+        fn mul_add(self, b: Self, c: Self) -> Self {
+            Self(unsafe { vfmaq_f32(c.0, self.0, b.0) })
+        }
+
+        #[inline(always)]
+        fn mul_sub(self, b: Self, c: Self) -> Self {
+            // vfmsq computes c - a * b
+            Self(unsafe { vnegq_f32(vfmsq_f32(c.0, self.0, b.0)) })
+        }
+
+        #[inline(always)]
+        fn swap_pairs(self) -> Self {
+            Self(unsafe { vrev64q_f32(self.0) })
+        }
+
+        #[inline(always)]
+        fn dup_even(self) -> Self {
+            Self(unsafe { vtrn1q_f32(self.0, self.0) })
+        }
+
+        #[inline(always)]
+        fn dup_odd(self) -> Self {
+            Self(unsafe { vtrn2q_f32(self.0, self.0) })
+        }
+
+        #[inline(always)]
+        fn low_halves(self, b: Self) -> Self {
+            Self(unsafe { vcombine_f32(vget_low_f32(self.0), vget_low_f32(b.0)) })
+        }
+
+        #[inline(always)]
+        fn high_halves(self, b: Self) -> Self {
+            Self(unsafe { vcombine_f32(vget_high_f32(self.0), vget_high_f32(b.0)) })
+        }
+
+        #[inline(always)]
+        fn blend_odd(self, b: Self) -> Self {
+            Self(unsafe { vtrn1q_f32(self.0, vrev64q_f32(b.0)) })
+        }
+    }
+
+    /// NEON as `f64x2`, always safe to call for the same reason as [`Neon`].
+    #[derive(Debug, Clone, Copy)]
+    pub struct NeonF64(pub float64x2_t);
+
+    impl_ops!(unsafe NeonF64, vaddq_f64, vsubq_f64, vmulq_f64);
+
+    impl F64x2 for NeonF64 {
+        const NAME: &'static str = "neon";
+
+        #[inline(always)]
+        fn new(x0: f64, x1: f64) -> Self {
+            let lanes = [x0, x1];
+            // SAFETY: `lanes` holds 2 doubles.
+            Self(unsafe { vld1q_f64(lanes.as_ptr()) })
+        }
+
+        #[inline(always)]
+        fn splat(x: f64) -> Self {
+            Self(unsafe { vdupq_n_f64(x) })
+        }
+
+        #[inline(always)]
+        fn to_array(self) -> [f64; 2] {
+            let mut out = [0.; 2];
+            // SAFETY: `out` has room for 2 doubles.
+            unsafe { vst1q_f64(out.as_mut_ptr(), self.0) };
+            out
+        }
+
+        #[inline(always)]
+        fn mul_add(self, b: Self, c: Self) -> Self {
+            Self(unsafe { vfmaq_f64(c.0, self.0, b.0) })
+        }
+
+        #[inline(always)]
+        fn mul_sub(self, b: Self, c: Self) -> Self {
+            // vfmsq computes c - a * b
+            Self(unsafe { vnegq_f64(vfmsq_f64(c.0, self.0, b.0)) })
+        }
+
+        #[inline(always)]
+        fn swap(self) -> Self {
+            Self(unsafe { vextq_f64::<1>(self.0, self.0) })
+        }
+
+        #[inline(always)]
+        fn dup_low(self) -> Self {
+            Self(unsafe { vtrn1q_f64(self.0, self.0) })
+        }
+
+        #[inline(always)]
+        fn dup_high(self) -> Self {
+            Self(unsafe { vtrn2q_f64(self.0, self.0) })
+        }
+
+        #[inline(always)]
+        fn blend_high(self, b: Self) -> Self {
+            Self(unsafe { vcopyq_laneq_f64::<1, 1>(self.0, b.0) })
+        }
+    }
+}
+
+/// Check every operation of `V` against [`Scalar`].
+#[cfg(test)]
+fn check_backend<V: Simd4>() {
+    // All exactly representable, in either precision
+    let lanes = |x: [f32; 4]| x.map(|x| V::Elem::from_f32(x).unwrap());
+    let a = lanes([1.5, -2.25, 3.125, 4.0625]);
+    let b = lanes([-0.5, 0.75, 8., -16.]);
+    let c = lanes([10., 20., 30., 40.]);
+    let seven = lanes([7.; 4]);
+
+    let v = |x: [V::Elem; 4]| V::new(x[0], x[1], x[2], x[3]);
+    let s = Scalar::<V::Elem>;
+
+    assert_eq!(v(a).to_array(), a, "{}", V::NAME);
+    assert_eq!(V::splat(seven[0]).to_array(), seven, "{}", V::NAME);
+    assert_eq!((v(a) + v(b)).to_array(), (s(a) + s(b)).0, "{}", V::NAME);
+    assert_eq!((v(a) - v(b)).to_array(), (s(a) - s(b)).0, "{}", V::NAME);
+    assert_eq!((v(a) * v(b)).to_array(), (s(a) * s(b)).0, "{}", V::NAME);
+    // Exact in both fused and unfused arithmetic for these values
+    assert_eq!(
+        v(a).mul_add(v(b), v(c)).to_array(),
+        s(a).mul_add(s(b), s(c)).0,
+        "{}",
+        V::NAME
+    );
+    assert_eq!(
+        v(a).mul_sub(v(b), v(c)).to_array(),
+        s(a).mul_sub(s(b), s(c)).0,
+        "{}",
+        V::NAME
+    );
+    assert_eq!(
+        v(a).swap_pairs().to_array(),
+        s(a).swap_pairs().0,
+        "{}",
+        V::NAME
+    );
+    assert_eq!(v(a).dup_even().to_array(), s(a).dup_even().0, "{}", V::NAME);
+    assert_eq!(v(a).dup_odd().to_array(), s(a).dup_odd().0, "{}", V::NAME);
+    assert_eq!(
+        v(a).low_halves(v(b)).to_array(),
+        s(a).low_halves(s(b)).0,
+        "{}",
+        V::NAME
+    );
+    assert_eq!(
+        v(a).high_halves(v(b)).to_array(),
+        s(a).high_halves(s(b)).0,
+        "{}",
+        V::NAME
+    );
+    assert_eq!(
+        v(a).blend_odd(v(b)).to_array(),
+        s(a).blend_odd(s(b)).0,
+        "{}",
+        V::NAME
+    );
+}
+
+#[test]
+fn test_scalar_backend() {
+    assert_eq!(
+        Scalar([1., 2., 3., 4.]).blend_odd(Scalar([5., 6., 7., 8.])),
+        Scalar([1., 6., 3., 8.])
+    );
+    assert_eq!(
+        Scalar([1., 2., 3., 4.]).high_halves(Scalar([5., 6., 7., 8.])),
+        Scalar([3., 4., 7., 8.])
+    );
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_backends() {
+    check_backend::<Scalar>();
+    check_backend::<Native>();
+    check_backend::<Scalar<f64>>();
+    check_backend::<Native64>();
+
+    #[cfg(target_arch = "x86_64")]
+    if std::is_x86_feature_detected!("avx2") && std::is_x86_feature_detected!("fma") {
+        #[target_feature(enable = "avx2,fma")]
+        unsafe fn check_avx2() {
+            check_backend::<Avx2>();
+        }
+
+        // SAFETY: the features were detected above.
+        unsafe { check_avx2() };
+    }
+
+    #[cfg(all(target_arch = "wasm32", feature = "relaxed-simd"))]
+    {
+        #[target_feature(enable = "simd128,relaxed-simd")]
+        fn check_relaxed() {
+            check_backend::<Relaxed>();
+        }
+
+        check_relaxed();
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{
+    cooley_tukey::{bit_reverse_copy, numbits},
+    simd::{Float, Simd4},
+};
+use num_complex::Complex;
+use num_traits::{Float as _, FloatConst, FromPrimitive, One, Zero};
+
+/// [`simd_cooley_tukey_fft_with`] on the fastest backend for `T` on this target.
+#[cfg_attr(
+    all(target_arch = "wasm32", feature = "simd128"),
+    target_feature(enable = "simd128")
+)]
+pub fn simd_cooley_tukey_fft<T: Float>(input: &[Complex<T>], output: &mut [Complex<T>]) {
+    simd_cooley_tukey_fft_with::<T::Native>(input, output);
+}
+
+/// [`simd_cooley_tukey_fft_with`] on AVX2 and FMA.
+///
+/// # Safety
+///
+/// The CPU must support both features, e.g. as checked by `is_x86_feature_detected!`.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2,fma")]
+pub unsafe fn simd_cooley_tukey_fft_avx2(input: &[Complex<f32>], output: &mut [Complex<f32>]) {
+    simd_cooley_tukey_fft_with::<crate::simd::Avx2>(input, output);
+}
+
+/// Cooley-Tukey doing the same butterfly of 4 neighbouring blocks at once, once blocks are
+/// small enough that there are 4 of them.
+#[inline(always)]
+pub fn simd_cooley_tukey_fft_with<V: Simd4>(
+    input: &[Complex<V::Elem>],
+    output: &mut [Complex<V::Elem>],
+) {
+    let two_pi = V::Elem::PI() + V::Elem::PI();
+
+    // const NTWO_PI_I: Complex<f32> = Complex {
+    //     re: 0.,
+    //     im: -2.0 * PI,
+    // };
+
+    assert_eq!(input.len(), output.len());
+    assert!(crate::is_power_of_2(input.len()));
+
+    let n = input.len();
+
+    bit_reverse_copy(input, output);
+
+    // let n = output.len();
+    let n_log2 = numbits(n) - 1;
+
+    for s in 1..=n_log2 {
+        let m: usize = 1 << s;
+        let mdiv2 = m >> 1;
+        // let wm = Complex::exp(NTWO_PI_I / m as f32);
+        let theta = -two_pi / V::Elem::from_usize(m).unwrap();
+        let wm = Complex {
+            re: theta.cos(),
+            im: theta.sin(),
+        };
+
+        // Check if there are 4 or more steps
+        if m * 4 <= n {
+            let m2 = m << 1;
+            let m3 = m2 + m;
+
+            let wm_re_x4 = V::splat(wm.re);
+            let wm_im_x4 = V::splat(wm.im);
+
+            for k in (0..n).step_by(m * 4) {
+                let mut w_re_x4 = V::splat(V::Elem::one());
+                let mut w_im_x4 = V::splat(V::Elem::zero());
+
+                for j in 0..mdiv2 {
+                    let idx_left_0 = k + j;
+                    let idx_right_0 = idx_left_0 + mdiv2;
+
+                    let (right_re_x4, right_im_x4) = complex_to_simd::<V>(
+                        &output[idx_right_0],
+                        &output[idx_right_0 + m],
+                        &output[idx_right_0 + m2],
+                        &output[idx_right_0 + m3],
+                    );
+
+                    // let t = w * output[k + j + mdiv2];
+                    let t_re_x4 = w_re_x4.mul_sub(right_re_x4, w_im_x4 * right_im_x4);
+                    let t_im_x4 = w_re_x4.mul_add(right_im_x4, w_im_x4 * right_re_x4);
+
+                    // let u = output[k + j];
+                    let (u_re_x4, u_im_x4) = complex_to_simd::<V>(
+                        &output[idx_left_0],
+                        &output[idx_left_0 + m],
+                        &output[idx_left_0 + m2],
+                        &output[idx_left_0 + m3],
+                    );
+
+                    // output[k + j] = u + t;
+                    // output[k + j + mdiv2] = u - t;
+                    let output_left_re_x4 = u_re_x4 + t_re_x4;
+                    let output_left_im_x4 = u_im_x4 + t_im_x4;
+                    let output_right_re_x4 = u_re_x4 - t_re_x4;
+                    let output_right_im_x4 = u_im_x4 - t_im_x4;
+
+                    (
+                        output[idx_left_0],
+                        output[idx_left_0 + m],
+                        output[idx_left_0 + m2],
+                        output[idx_left_0 + m3],
+                    ) = complex_from_simd(output_left_re_x4, output_left_im_x4);
+                    (
+                        output[idx_right_0],
+                        output[idx_right_0 + m],
+                        output[idx_right_0 + m2],
+                        output[idx_right_0 + m3],
+                    ) = complex_from_simd(output_right_re_x4, output_right_im_x4);
+
+                    // w *= wm;
+                    let tmp_w_im = w_re_x4.mul_add(wm_im_x4, w_im_x4 * wm_re_x4);
+                    w_re_x4 = w_re_x4.mul_sub(wm_re_x4, w_im_x4 * wm_im_x4);
+                    w_im_x4 = tmp_w_im;
+                }
+            }
+        } else {
+            // Only need to do 1 or 2 steps
+            for k in (0..n).step_by(m) {
+                let mut w = Complex::new(V::Elem::one(), V::Elem::zero());
+
+                for j in 0..mdiv2 {
+                    let t = w * output[k + j + mdiv2];
+                    let u = output[k + j];
+                    output[k + j] = u + t;
+                    output[k + j + mdiv2] = u - t;
+                    w = w * wm;
+                }
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn complex_to_simd<V: Simd4>(
+    c0: &Complex<V::Elem>,
+    c1: &Complex<V::Elem>,
+    c2: &Complex<V::Elem>,
+    c3: &Complex<V::Elem>,
+) -> (V, V) {
+    let re_x4 = V::new(c0.re, c1.re, c2.re, c3.re);
+    let im_x4 = V::new(c0.im, c1.im, c2.im, c3.im);
+
+    (re_x4, im_x4)
+}
+
+/// The four lanes of a pair of real and imaginary vectors.
+type ComplexLanes<T> = (Complex<T>, Complex<T>, Complex<T>, Complex<T>);
+
+#[inline(always)]
+fn complex_from_simd<V: Simd4>(re_x4: V, im_x4: V) -> ComplexLanes<V::Elem> {
+    let [re0, re1, re2, re3] = re_x4.to_array();
+    let [im0, im1, im2, im3] = im_x4.to_array();
+
+    (
+        Complex::new(re0, im0),
+        Complex::new(re1, im1),
+        Complex::new(re2, im2),
+        Complex::new(re3, im3),
+    )
+}
+
+#[test]
+fn test_simd_cooley_tukey_fft_backends() {
+    use crate::cooley_tukey::cooley_tukey_fft;
+    use alloc::{vec, vec::Vec};
+
+    let mut signal = vec![0.; 256];
+    crate::test_noise(3, &mut signal);
+    let input: Vec<_> = signal.iter().map(|&x| Complex::new(x, 0.)).collect();
+
+    let mut expected = vec![Complex::new(0., 0.); input.len()];
+    cooley_tukey_fft(&input, &mut expected);
+
+    let mut output = vec![Complex::new(0., 0.); input.len()];
+    simd_cooley_tukey_fft_with::<crate::simd::Scalar>(&input, &mut output);
+    for (a, b) in output.iter().zip(&expected) {
+        approx::assert_abs_diff_eq!((a - b).norm(), 0., epsilon = 1e-4);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    cooley_tukey::{bit_reverse_copy, numbits},
+    simd::{Float, Simd4},
+};
+use num_complex::Complex;
+use num_traits::{Float as _, FloatConst, FromPrimitive, One, Zero};
+
+/// [`simd_cooley_tukey_fft2_with`] on the fastest backend for `T` on this target.
+#[cfg_attr(
+    all(target_arch = "wasm32", feature = "simd128"),
+    target_feature(enable = "simd128")
+)]
+pub fn simd_cooley_tukey_fft2<T: Float>(input: &[Complex<T>], output: &mut [Complex<T>]) {
+    simd_cooley_tukey_fft2_with::<T::Native>(input, output);
+}
+
+/// [`simd_cooley_tukey_fft2_with`] on relaxed-simd multiply-adds, which the engine may fuse.
+#[cfg(all(target_arch = "wasm32", feature = "relaxed-simd"))]
+#[target_feature(enable = "simd128,relaxed-simd")]
+pub fn simd_cooley_tukey_fft2_relaxed(input: &[Complex<f32>], output: &mut [Complex<f32>]) {
+    simd_cooley_tukey_fft2_with::<crate::simd::Relaxed>(input, output);
+}
+
+/// Cooley-Tukey computing each butterfly's product and the next twiddle together.
+#[inline(always)]
+pub fn simd_cooley_tukey_fft2_with<V: Simd4>(
+    input: &[Complex<V::Elem>],
+    output: &mut [Complex<V::Elem>],
+) {
+    assert_eq!(input.len(), output.len());
+    assert!(crate::is_power_of_2(input.len()));
+
+    let two_pi = V::Elem::PI() + V::Elem::PI();
+
+    let n = input.len();
+
+    bit_reverse_copy(input, output);
+
+    // let n = output.len();
+    let n_log2 = numbits(n) - 1;
+
+    for s in 1..=n_log2 {
+        let m: usize = 1 << s;
+        let mdiv2 = m >> 1;
+        let theta = -two_pi / V::Elem::from_usize(m).unwrap();
+        let wm = Complex {
+            re: theta.cos(),
+            im: theta.sin(),
+        };
+
+        for k in (0..n).step_by(m) {
+            let mut w = Complex::new(V::Elem::one(), V::Elem::zero());
+
+            for j in 0..mdiv2 {
+                // let t = w * output[k + j + mdiv2];
+                // w *= wm;
+                let t;
+                (t, w) = simd_complex_mul::<V>(w, output[k + j + mdiv2], w, wm);
+
+                let u = output[k + j];
+
+                output[k + j] = u + t;
+                output[k + j + mdiv2] = u - t;
+            }
+        }
+    }
+}
+
+/// Calculate `left0 * right0` and `left1 * right1` in parallel using SIMD.
+#[inline(always)]
+fn simd_complex_mul<V: Simd4>(
+    left0: Complex<V::Elem>,
+    right0: Complex<V::Elem>,
+    left1: Complex<V::Elem>,
+    right1: Complex<V::Elem>,
+) -> (Complex<V::Elem>, Complex<V::Elem>) {
+    // In all calculations, both pairs will do the same operations.
+    // So, we only need to reason about the first 2 lanes as if they were f32x2:
+    //   (a + ib)*(c + id) = (ac - bd) + i(bc + ad)
+
+    // a | b
+    let a_b = V::new(left0.re, left0.im, left1.re, left1.im);
+    // c | c
+    let c_c = V::new(right0.re, right0.re, right1.re, right1.re);
+    // d | d
+    let d_d = V::new(right0.im, right0.im, right1.im, right1.im);
+
+    // b | a - Get by swapping lanes of a_b
+    let b_a = a_b.swap_pairs();
+
+    // bd | ad
+    let bd_ad = b_a * d_d;
+
+    // ac-bd |        - Real output
+    let acmbd_bcmad = a_b.mul_sub(c_c, bd_ad);
+    //       | bc+ad  - Imaginary output
+    let acpbd_bcpad = a_b.mul_add(c_c, bd_ad);
+
+    let [re0, _, re1, _] = acmbd_bcmad.to_array();
+    let [_, im0, _, im1] = acpbd_bcpad.to_array();
+
+    (Complex::new(re0, im0), Complex::new(re1, im1))
+}
+
+#[cfg(test)]
+fn check_simd_complex_mul<V: Simd4>() {
+    use approx::assert_abs_diff_eq;
+    use num_traits::ToPrimitive;
+
+    let complex = |re: f32, im: f32| {
+        Complex::new(
+            V::Elem::from_f32(re).unwrap(),
+            V::Elem::from_f32(im).unwrap(),
+        )
+    };
+    let norm = |x: Complex<V::Elem>| x.norm().to_f64().unwrap();
+    let epsilon = V::Elem::epsilon().to_f64().unwrap();
+
+    let a = complex(1.23, 2.34);
+    let b = complex(0.56, 1.11);
+
+    let (out0, out1) = simd_complex_mul::<V>(a, b, a, b);
+
+    assert_abs_diff_eq!(norm(out0), norm(out1), epsilon = epsilon);
+    assert_abs_diff_eq!(norm(a * b), norm(out0), epsilon = epsilon);
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_simd_complex_mul() {
+    check_simd_complex_mul::<crate::simd::Scalar>();
+    check_simd_complex_mul::<crate::simd::Native>();
+    check_simd_complex_mul::<crate::simd::Scalar<f64>>();
+    check_simd_complex_mul::<crate::simd::Native64>();
+}
@@ -0,0 +1,270 @@
+use crate::{
+    cooley_tukey::{bit_reverse_copy, numbits},
+    simd::{self, Float, Simd4},
+};
+use core::ops::{Add, Sub};
+use num_complex::Complex;
+use num_traits::{Float as _, FloatConst, FromPrimitive, One, Zero};
+
+/// [`simd_cooley_tukey_fft3_with`] with `simd128` enabled on wasm, for buffers of
+/// [`Complex4`] on [`simd::Native`] or [`simd::Native64`].
+#[cfg_attr(
+    all(target_arch = "wasm32", feature = "simd128"),
+    target_feature(enable = "simd128")
+)]
+pub fn simd_cooley_tukey_fft3<V: Simd4>(input: &[Complex4<V>], output: &mut [Complex4<V>]) {
+    simd_cooley_tukey_fft3_with(input, output);
+}
+
+/// Cooley-Tukey keeping every value in a vector register between butterflies.
+#[inline(always)]
+pub fn simd_cooley_tukey_fft3_with<V: Simd4>(input: &[Complex4<V>], output: &mut [Complex4<V>]) {
+    assert_eq!(input.len(), output.len());
+    assert!(crate::is_power_of_2(input.len()));
+
+    let two_pi = V::Elem::PI() + V::Elem::PI();
+
+    let n = input.len();
+
+    bit_reverse_copy(input, output);
+
+    // let n = output.len();
+    let n_log2 = numbits(n) - 1;
+
+    for s in 1..=n_log2 {
+        let m: usize = 1 << s;
+        let mdiv2 = m >> 1;
+        let theta = -two_pi / V::Elem::from_usize(m).unwrap();
+        let wm = Complex4::new(theta.cos(), theta.sin());
+
+        for k in (0..n).step_by(m) {
+            let mut w = Complex4::one();
+
+            for j in 0..mdiv2 {
+                // let t = w * output[k + j + mdiv2];
+                // w *= wm;
+                let t;
+                (t, w) = mul_parallel(w, output[k + j + mdiv2], w, wm);
+
+                let u = output[k + j];
+
+                output[k + j] = u + t;
+                output[k + j + mdiv2] = u - t;
+            }
+        }
+    }
+}
+
+/// [`simd_cooley_tukey_fft3`] on plain complex buffers, for comparison with the other kernels.
+#[cfg(feature = "alloc")]
+#[cfg_attr(
+    all(target_arch = "wasm32", feature = "simd128"),
+    target_feature(enable = "simd128")
+)]
+pub fn simd_cooley_tukey_fft3_complex<T: Float>(input: &[Complex<T>], output: &mut [Complex<T>]) {
+    let mut scratch = alloc::vec![Complex4::zero(); 2 * input.len()];
+    simd_cooley_tukey_fft3_complex_with::<T::Native>(input, output, &mut scratch);
+}
+
+/// [`simd_cooley_tukey_fft3_complex`] converting through caller-provided `scratch`, which must
+/// hold at least `2 * input.len()` vectors.
+#[cfg_attr(
+    all(target_arch = "wasm32", feature = "simd128"),
+    target_feature(enable = "simd128")
+)]
+pub fn simd_cooley_tukey_fft3_complex_with_scratch<T: Float>(
+    input: &[Complex<T>],
+    output: &mut [Complex<T>],
+    scratch: &mut [Complex4<T::Native>],
+) {
+    simd_cooley_tukey_fft3_complex_with(input, output, scratch);
+}
+
+/// [`simd_cooley_tukey_fft3_complex`] on relaxed-simd multiply-adds, which the engine may fuse.
+#[cfg(all(target_arch = "wasm32", feature = "relaxed-simd", feature = "alloc"))]
+#[target_feature(enable = "simd128,relaxed-simd")]
+pub fn simd_cooley_tukey_fft3_relaxed(input: &[Complex<f32>], output: &mut [Complex<f32>]) {
+    let mut scratch = alloc::vec![Complex4::zero(); 2 * input.len()];
+    simd_cooley_tukey_fft3_complex_with::<simd::Relaxed>(input, output, &mut scratch);
+}
+
+#[inline(always)]
+fn simd_cooley_tukey_fft3_complex_with<V: Simd4>(
+    input: &[Complex<V::Elem>],
+    output: &mut [Complex<V::Elem>],
+    scratch: &mut [Complex4<V>],
+) {
+    assert_eq!(input.len(), output.len());
+    assert!(scratch.len() >= 2 * input.len());
+
+    let (input_x4, scratch) = scratch.split_at_mut(input.len());
+    let output_x4 = &mut scratch[..input.len()];
+
+    for (x4, &x) in input_x4.iter_mut().zip(input) {
+        *x4 = x.into();
+    }
+
+    simd_cooley_tukey_fft3_with(input_x4, output_x4);
+
+    for (out, &x) in output.iter_mut().zip(output_x4.iter()) {
+        *out = x.into();
+    }
+}
+
+/// SIMD vector wrapper around complex `a + jb` of the form `a | b | X | X`
+#[derive(Clone, Copy)]
+pub struct Complex4<V = simd::Native>(V);
+
+impl<V: Simd4> Complex4<V> {
+    #[inline(always)]
+    pub fn new(re: V::Elem, im: V::Elem) -> Self {
+        let zero = V::Elem::zero();
+        Self(V::new(re, im, zero, zero))
+    }
+
+    #[inline(always)]
+    pub fn real(re: V::Elem) -> Self {
+        Self::new(re, V::Elem::zero())
+    }
+
+    #[inline(always)]
+    pub fn one() -> Self {
+        Self::real(V::Elem::one())
+    }
+
+    #[inline(always)]
+    pub fn zero() -> Self {
+        Self(V::splat(V::Elem::zero()))
+    }
+
+    #[inline(always)]
+    pub fn norm(&self) -> V::Elem {
+        let [a, b, _, _] = self.0.to_array();
+
+        (a * a + b * b).sqrt()
+    }
+}
+
+impl<V: Simd4> Add for Complex4<V> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl<V: Simd4> Sub for Complex4<V> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl<V: Simd4> From<Complex<V::Elem>> for Complex4<V> {
+    #[inline(always)]
+    fn from(x: Complex<V::Elem>) -> Self {
+        Self::new(x.re, x.im)
+    }
+}
+
+impl<V: Simd4> From<Complex4<V>> for Complex<V::Elem> {
+    #[inline(always)]
+    fn from(x: Complex4<V>) -> Self {
+        let [re, im, _, _] = x.0.to_array();
+
+        Complex { re, im }
+    }
+}
+
+/// Calculate `left0 * right0` and `left1 * right1` in parallel using SIMD.
+#[inline(always)]
+fn mul_parallel<V: Simd4>(
+    left0: Complex4<V>,
+    right0: Complex4<V>,
+    left1: Complex4<V>,
+    right1: Complex4<V>,
+) -> (Complex4<V>, Complex4<V>) {
+    // In all calculations, both pairs will do the same operations.
+    // So, we only need to reason about the first 2 lanes as if they were f32x2:
+    //   (a + ib)*(c + id) = (ac - bd) + i(bc + ad)
+
+    // a | b
+    let a_b = left0.0.low_halves(left1.0);
+    // c | d
+    let c_d = right0.0.low_halves(right1.0);
+    // c | c
+    let c_c = c_d.dup_even();
+    // d | d
+    let d_d = c_d.dup_odd();
+
+    // b | a
+    let b_a = a_b.swap_pairs();
+
+    // bd | ad
+    let bd_ad = b_a * d_d;
+
+    // ac-bd |        - Real output
+    let acmbd_bcmad = a_b.mul_sub(c_c, bd_ad);
+    //       | bc+ad  - Imaginary output
+    let acpbd_bcpad = a_b.mul_add(c_c, bd_ad);
+
+    // ac-bd | bc+ad for each pair
+    let out = acmbd_bcmad.blend_odd(acpbd_bcpad);
+
+    (Complex4(out), Complex4(out.high_halves(out)))
+}
+
+// fn log_vector(msg: &str, v: v128) {
+//     use wasm_bindgen::JsValue;
+//     use web_sys::console;
+//     console::log_5(
+//         &JsValue::from_str(msg),
+//         &JsValue::from_f64(f32x4_extract_lane::<0>(v) as f64),
+//         &JsValue::from_f64(f32x4_extract_lane::<1>(v) as f64),
+//         &JsValue::from_f64(f32x4_extract_lane::<2>(v) as f64),
+//         &JsValue::from_f64(f32x4_extract_lane::<3>(v) as f64),
+//     )
+// }
+
+#[cfg(test)]
+fn check_mul_parallel<V: Simd4>() {
+    use approx::assert_abs_diff_eq;
+    use num_traits::ToPrimitive;
+
+    let complex = |re: f32, im: f32| {
+        Complex::new(
+            V::Elem::from_f32(re).unwrap(),
+            V::Elem::from_f32(im).unwrap(),
+        )
+    };
+    let epsilon = V::Elem::epsilon().to_f64().unwrap();
+
+    let a = complex(1.23, 2.34);
+    let b = complex(0.56, 1.11);
+
+    let (out0, out1) = mul_parallel::<V>(a.into(), b.into(), a.into(), b.into());
+
+    assert_abs_diff_eq!(
+        out0.norm().to_f64().unwrap(),
+        out1.norm().to_f64().unwrap(),
+        epsilon = epsilon
+    );
+    assert_abs_diff_eq!(
+        (a * b).norm().to_f64().unwrap(),
+        out0.norm().to_f64().unwrap(),
+        epsilon = epsilon
+    );
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_mul_parallel() {
+    check_mul_parallel::<simd::Scalar>();
+    check_mul_parallel::<simd::Native>();
+    check_mul_parallel::<simd::Scalar<f64>>();
+    check_mul_parallel::<simd::Native64>();
+}
@@ -0,0 +1,191 @@
+//! DFT bins of the latest `window_len` samples, updated every sample in O(1) per bin.
+//!
+//! Each bin follows `Y[n] = x[n] + z Y[n - 1] - z^N x[n - N]` with `z = e^(j omega)`, the
+//! recurrence of the sliding DFT. Its pole is on the unit circle, so rounding errors are never
+//! damped and grow over time. To keep them bounded, each bin is also summed directly over the
+//! samples since the last `window_len` boundary, and that sum replaces the bin when it covers a
+//! whole window. This costs about as much again as the updates, but every sample costs the same,
+//! with no periodic recomputation of the window.
+
+use core::f64::consts::TAU;
+
+use alloc::vec::Vec;
+use num_complex::Complex;
+use num_traits::Zero;
+
+use crate::{goertzel::polar, simd::Float};
+
+#[derive(Debug, Clone)]
+struct Bin<T> {
+    /// `e^(j omega)`
+    z: Complex<T>,
+    /// `e^(j omega N)`, 1 for integer bins
+    z_n: Complex<T>,
+    /// `e^(-j omega (N - 1))`, moving the phase reference to the oldest sample of the window
+    phase: Complex<T>,
+    y: Complex<T>,
+    /// `sum x[n - i] z^i` over the samples since the last resync
+    fresh: Complex<T>,
+}
+
+/// Sliding DFT of several bins of a `window_len`-sample window.
+///
+/// Bins need not be integers: `bin` is in cycles per window. Results have the scale and phase of
+/// the DFT of the window, oldest sample first. Until `window_len` samples have arrived, the
+/// missing ones count as zeros.
+#[derive(Debug, Clone)]
+pub struct SlidingDft<T> {
+    bins: Vec<Bin<T>>,
+    /// The window, as a ring with the oldest sample at `pos`
+    history: Vec<T>,
+    pos: usize,
+    since_resync: usize,
+}
+
+impl<T: Float> SlidingDft<T> {
+    pub fn new(bins: &[T], window_len: usize) -> Self {
+        assert!(window_len > 0);
+
+        let bins = bins
+            .iter()
+            .map(|bin| {
+                let omega = TAU * bin.to_f64().unwrap() / window_len as f64;
+                Bin {
+                    z: polar(omega),
+                    z_n: polar(omega * window_len as f64),
+                    phase: polar(-omega * (window_len - 1) as f64),
+                    y: Complex::zero(),
+                    fresh: Complex::zero(),
+                }
+            })
+            .collect();
+
+        Self {
+            bins,
+            history: alloc::vec![T::zero(); window_len],
+            pos: 0,
+            since_resync: 0,
+        }
+    }
+
+    pub fn from_frequencies(freqs: &[T], sample_rate: T, window_len: usize) -> Self {
+        let to_bin = T::from_usize(window_len).unwrap() / sample_rate;
+        let bins: Vec<T> = freqs.iter().map(|&f| f * to_bin).collect();
+        Self::new(&bins, window_len)
+    }
+
+    pub fn window_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Slide the window by one sample, in O(1) per bin.
+    #[inline]
+    pub fn push(&mut self, x: T) {
+        let oldest = core::mem::replace(&mut self.history[self.pos], x);
+        self.pos += 1;
+        if self.pos == self.history.len() {
+            self.pos = 0;
+        }
+
+        for bin in &mut self.bins {
+            bin.y = bin.z * bin.y + x - bin.z_n * oldest;
+            // Horner's rule from the oldest sample
+            bin.fresh = bin.z * bin.fresh + x;
+        }
+
+        self.since_resync += 1;
+        if self.since_resync == self.history.len() {
+            self.resync();
+        }
+    }
+
+    /// [`SlidingDft::push`] every sample of `block`.
+    pub fn process(&mut self, block: &[T]) {
+        for &x in block {
+            self.push(x);
+        }
+    }
+
+    /// Bin `index` of the current window.
+    pub fn bin(&self, index: usize) -> Complex<T> {
+        let bin = &self.bins[index];
+        bin.y * bin.phase
+    }
+
+    /// Every bin of the current window, in the order they were given.
+    pub fn bins(&self, output: &mut [Complex<T>]) {
+        assert_eq!(output.len(), self.bins.len());
+
+        for (index, y) in output.iter_mut().enumerate() {
+            *y = self.bin(index);
+        }
+    }
+
+    /// Clear the window.
+    pub fn reset(&mut self) {
+        self.history.iter_mut().for_each(|x| *x = T::zero());
+        self.pos = 0;
+        self.since_resync = 0;
+        self.bins.iter_mut().for_each(|bin| {
+            bin.y = Complex::zero();
+            bin.fresh = Complex::zero();
+        });
+    }
+
+    /// Replace every bin with its direct sum, which now covers the window, dropping the error the
+    /// updates have accumulated.
+    fn resync(&mut self) {
+        for bin in &mut self.bins {
+            bin.y = core::mem::replace(&mut bin.fresh, Complex::zero());
+        }
+
+        self.since_resync = 0;
+    }
+}
+
+#[test]
+fn test_sliding_dft() {
+    use alloc::{vec, vec::Vec};
+
+    let window_len = 256;
+    let bins = [0., 3., 10.5, 127.25];
+    let mut sdft = SlidingDft::new(&bins, window_len);
+
+    // Long enough for rounding to drift without the resync
+    let mut input = vec![0.; 100_000];
+    crate::test_noise(4, &mut input);
+
+    let mut output = [Complex::zero(); 4];
+    for (n, &x) in input.iter().enumerate() {
+        sdft.push(x);
+
+        // Check at varying offsets from the resync
+        if n % 9973 == 0 && n >= window_len {
+            let window: Vec<f64> = input[n + 1 - window_len..=n]
+                .iter()
+                .map(|&x| x as f64)
+                .collect();
+            sdft.bins(&mut output);
+
+            for (y, &bin) in output.iter().zip(&bins) {
+                let x = crate::goertzel::goertzel(&window, bin as f64);
+                approx::assert_abs_diff_eq!(y.re as f64, x.re, epsilon = 1e-3);
+                approx::assert_abs_diff_eq!(y.im as f64, x.im, epsilon = 1e-3);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_sliding_dft_partial_window() {
+    let mut sdft = SlidingDft::from_frequencies(&[2000.], 8000., 8);
+    sdft.process(&[1., 0., -1.]);
+
+    // `[0, 0, 0, 0, 0, 1, 0, -1]` at a quarter of the sample rate
+    let y = sdft.bin(0);
+    approx::assert_abs_diff_eq!(y.re, 0., epsilon = 1e-6);
+    approx::assert_abs_diff_eq!(y.im, -2., epsilon = 1e-6);
+
+    sdft.reset();
+    assert_eq!(sdft.bin(0), Complex::zero());
+}
@@ -0,0 +1,348 @@
+//! Frequency weighting curves: A, C and Z of IEC 61672-1, B of IEC 60651, D of IEC 60537 and the
+//! noise weighting of ITU-R BS.468-4, all normalised to exactly 0 dB at 1 kHz.
+//!
+//! [`Weighting::gain`] is each standard's analytic curve, for weighting spectra, and
+//! [`Weighting::filter`] its analogue network mapped to a sample rate by the bilinear transform,
+//! for weighting signals.
+
+use core::f64::consts::PI;
+
+use num_complex::Complex;
+use num_traits::Float as Real;
+
+use crate::{
+    biquad::{cascade_response, Biquad},
+    simd::Float,
+};
+
+/// Pole frequencies of IEC 61672-1, in Hz
+const F1: f64 = 20.598997;
+const F2: f64 = 107.65265;
+const F3: f64 = 737.86223;
+const F4: f64 = 12194.217;
+
+/// B weighting's pole between `F1` and `F4`, in Hz
+const F5: f64 = 158.5;
+
+/// Poles of the ITU-R 468 network, in Hz: the roots of the standard's sixth-order denominator,
+/// two real and two conjugate pairs
+const ITU_468_REAL: [f64; 2] = [-4122.702066134758, -9975.063123930304];
+const ITU_468_COMPLEX: [Complex<f64>; 2] = [
+    Complex::new(-3758.52916301072, 5790.04233660895),
+    Complex::new(-2983.15993789067, 9940.84264570956),
+];
+
+/// An analogue section `(b, a)` in `s`, as for [`Biquad::bilinear`], or `None` to pass through
+type Section = Option<([f64; 3], [f64; 3])>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weighting {
+    /// Loudness at moderate levels, for most noise measurements
+    A,
+    /// Loudness at higher levels, between A and C; withdrawn from sound level meters
+    B,
+    /// Flatter, for peak levels and loud, low-pitched noise
+    C,
+    /// Aircraft noise, with a peak around 3 kHz
+    D,
+    /// Audibility of noise in broadcast and recording equipment, with a peak at 6.3 kHz
+    Itu468,
+    /// Flat
+    Z,
+}
+
+impl Weighting {
+    /// Amplitude gain at `freq` Hz.
+    pub fn gain(self, freq: f64) -> f64 {
+        self.unnormalised(freq) / self.unnormalised(1000.)
+    }
+
+    /// Gain at `freq` Hz, in dB.
+    pub fn gain_db(self, freq: f64) -> f64 {
+        20. * Real::log10(self.gain(freq))
+    }
+
+    /// Gain at each of the first `output.len()` bins of an `fft_len`-point transform at
+    /// `sample_rate`, for multiplying its magnitudes.
+    pub fn curve<T: Float>(self, sample_rate: f64, fft_len: usize, output: &mut [T]) {
+        assert!(output.len() <= fft_len);
+
+        for (k, y) in output.iter_mut().enumerate() {
+            *y = T::from_f64(self.gain(k as f64 * sample_rate / fft_len as f64)).unwrap();
+        }
+    }
+
+    /// Weight the whole of `spectrum`, an FFT of samples at `sample_rate`, in place: the
+    /// negative frequencies in its upper half as well as the positive.
+    pub fn apply<T: Float>(self, spectrum: &mut [Complex<T>], sample_rate: f64) {
+        let n = spectrum.len();
+
+        for (k, x) in spectrum.iter_mut().enumerate() {
+            let freq = k.min(n - k) as f64 * sample_rate / n as f64;
+            let gain = T::from_f64(self.gain(freq)).unwrap();
+            *x = Complex::new(x.re * gain, x.im * gain);
+        }
+    }
+
+    /// The weighting network at `sample_rate`, as three sections in series, unused ones passing
+    /// their input through.
+    ///
+    /// Mapped by the bilinear transform and scaled to 0 dB at 1 kHz: it follows the analogue
+    /// curve at low frequencies, but falls increasingly short of it towards the Nyquist
+    /// frequency, where the transform squeezes everything above. At 48 kHz, A weighting is within
+    /// 0.1 dB to 5 kHz and 1 dB to 8 kHz.
+    pub fn filter<T: Float>(self, sample_rate: f64) -> [Biquad<T>; 3] {
+        let design = |gain: f64| {
+            let mut sections = self.sections().map(|section| match section {
+                Some((b, a)) => Biquad::bilinear(b, a, sample_rate, 0.),
+                None => Biquad::new([T::one(), T::zero(), T::zero()], [T::zero(); 2]),
+            });
+            if let Some((b, a)) = self.sections()[0] {
+                sections[0] = Biquad::bilinear(b.map(|x| x * gain), a, sample_rate, 0.);
+            }
+            sections
+        };
+
+        let gain = cascade_response(&design(1.), 1000., sample_rate).norm();
+        design(1. / gain)
+    }
+
+    /// The analogue network, each section with unit gain in its pass band.
+    fn sections(self) -> [Section; 3] {
+        let w = |f: f64| 2. * PI * f;
+        let high_pass = |f: f64| Some(([1., 0., 0.], [1., 2. * w(f), w(f) * w(f)]));
+        let low_pass = |f: f64| Some(([0., 0., w(f) * w(f)], [1., 2. * w(f), w(f) * w(f)]));
+        let resonance = |pole: Complex<f64>| {
+            let (re, norm) = (w(pole.re), w(1.) * w(1.) * pole.norm_sqr());
+            Some(([0., 0., norm], [1., -2. * re, norm]))
+        };
+
+        match self {
+            Self::A => [
+                high_pass(F1),
+                Some(([1., 0., 0.], [1., w(F2) + w(F3), w(F2) * w(F3)])),
+                low_pass(F4),
+            ],
+            Self::B => [
+                high_pass(F1),
+                Some(([0., 1., 0.], [0., 1., w(F5)])),
+                low_pass(F4),
+            ],
+            Self::C => [high_pass(F1), low_pass(F4), None],
+            Self::D => [
+                // IEC 60537's transfer function, in rad/s
+                Some(([0., 9064.8, 0.], [1., 9064.8, 1776.3 * 7288.5])),
+                Some(([1., 6532., 4.0975e7], [1., 21514., 3.8836e8])),
+                None,
+            ],
+            Self::Itu468 => {
+                let [r1, r2] = ITU_468_REAL.map(w);
+                let [c1, c2] = ITU_468_COMPLEX;
+                [
+                    Some(([0., -(r1 + r2), 0.], [1., -(r1 + r2), r1 * r2])),
+                    resonance(c1),
+                    resonance(c2),
+                ]
+            }
+            Self::Z => [None; 3],
+        }
+    }
+
+    fn unnormalised(self, freq: f64) -> f64 {
+        let f2 = freq * freq;
+        let square = |x: f64| x * x;
+
+        match self {
+            Self::A => {
+                square(F4) * square(f2)
+                    / ((f2 + square(F1))
+                        * Real::sqrt((f2 + square(F2)) * (f2 + square(F3)))
+                        * (f2 + square(F4)))
+            }
+            Self::B => {
+                square(F4) * f2 * freq
+                    / ((f2 + square(F1)) * Real::sqrt(f2 + square(F5)) * (f2 + square(F4)))
+            }
+            Self::C => square(F4) * f2 / ((f2 + square(F1)) * (f2 + square(F4))),
+            Self::D => {
+                let h = (square(1037918.48 - f2) + 1080768.16 * f2)
+                    / (square(9837328. - f2) + 11723776. * f2);
+                freq * Real::sqrt(h / ((f2 + 79919.29) * (f2 + 1345600.)))
+            }
+            Self::Itu468 => {
+                let h1 = -4.737338981378384e-24 * f2 * f2 * f2 + 2.043828333606125e-15 * f2 * f2
+                    - 1.363894795463638e-07 * f2
+                    + 1.;
+                let h2 = (1.306612257412824e-19 * f2 * f2 - 2.118150887518656e-11 * f2
+                    + 5.559488023498642e-04)
+                    * freq;
+                freq / Real::sqrt(h1 * h1 + h2 * h2)
+            }
+            Self::Z => 1.,
+        }
+    }
+}
+
+/// Exact base-10 one-third-octave frequencies from 10 Hz to 20 kHz, as in the standard's tables.
+#[cfg(test)]
+pub(crate) fn third_octaves() -> impl Iterator<Item = f64> {
+    (-20..=13).map(|x| 1000. * Real::powf(10f64, x as f64 / 10.))
+}
+
+#[test]
+fn test_a_weighting_table() {
+    // IEC 61672-1 Table 3, rounded to 0.1 dB
+    const A: [f64; 34] = [
+        -70.4, -63.4, -56.7, -50.5, -44.7, -39.4, -34.6, -30.2, -26.2, -22.5, -19.1, -16.1, -13.4,
+        -10.9, -8.6, -6.6, -4.8, -3.2, -1.9, -0.8, 0.0, 0.6, 1.0, 1.2, 1.3, 1.2, 1.0, 0.5, -0.1,
+        -1.1, -2.5, -4.3, -6.6, -9.3,
+    ];
+
+    for (f, expected) in third_octaves().zip(A) {
+        approx::assert_abs_diff_eq!(Weighting::A.gain_db(f), expected, epsilon = 0.05);
+    }
+}
+
+#[test]
+fn test_c_and_z_weighting_tables() {
+    // IEC 61672-1 Table 3, rounded to 0.1 dB
+    const C: [f64; 34] = [
+        -14.3, -11.2, -8.5, -6.2, -4.4, -3.0, -2.0, -1.3, -0.8, -0.5, -0.3, -0.2, -0.1, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, -0.1, -0.2, -0.3, -0.5, -0.8, -1.3, -2.0, -3.0, -4.4,
+        -6.2, -8.5, -11.2,
+    ];
+
+    for (f, expected) in third_octaves().zip(C) {
+        approx::assert_abs_diff_eq!(Weighting::C.gain_db(f), expected, epsilon = 0.05);
+        assert_eq!(Weighting::Z.gain_db(f), 0.);
+    }
+}
+
+#[test]
+fn test_b_weighting_table() {
+    // IEC 60651 Table IV, rounded to 0.1 dB
+    const B: [f64; 34] = [
+        -38.2, -33.2, -28.5, -24.2, -20.4, -17.1, -14.2, -11.6, -9.3, -7.4, -5.6, -4.2, -3.0, -2.0,
+        -1.3, -0.8, -0.5, -0.3, -0.1, 0.0, 0.0, 0.0, 0.0, -0.1, -0.2, -0.4, -0.7, -1.2, -1.9, -2.9,
+        -4.3, -6.1, -8.4, -11.1,
+    ];
+
+    for (f, expected) in third_octaves().zip(B) {
+        approx::assert_abs_diff_eq!(Weighting::B.gain_db(f), expected, epsilon = 0.05);
+    }
+}
+
+#[test]
+fn test_itu_468_weighting_table() {
+    // ITU-R BS.468-4 Table 1, at its nominal frequencies
+    const ITU_468: [(f64, f64); 21] = [
+        (31.5, -29.9),
+        (63., -23.9),
+        (100., -19.8),
+        (200., -13.8),
+        (400., -7.8),
+        (800., -1.9),
+        (1000., 0.0),
+        (2000., 5.6),
+        (3150., 9.0),
+        (4000., 10.5),
+        (5000., 11.7),
+        (6300., 12.2),
+        (7100., 12.0),
+        (8000., 11.4),
+        (9000., 10.1),
+        (10000., 8.1),
+        (12500., 0.0),
+        (14000., -5.3),
+        (16000., -11.7),
+        (20000., -22.2),
+        (31500., -42.7),
+    ];
+
+    for (f, expected) in ITU_468 {
+        approx::assert_abs_diff_eq!(Weighting::Itu468.gain_db(f), expected, epsilon = 0.06);
+    }
+}
+
+#[test]
+fn test_d_weighting() {
+    // IEC 60537 defines D weighting by its transfer function rather than a table
+    let h = |f: f64| {
+        let s = Complex::new(0., 2. * PI * f);
+        s * (s * s + 6532. * s + 4.0975e7)
+            / ((s + 1776.3) * (s + 7288.5) * (s * s + 21514. * s + 3.8836e8))
+    };
+
+    for f in third_octaves() {
+        let expected = 20. * Real::log10(h(f).norm() / h(1000.).norm());
+        approx::assert_abs_diff_eq!(Weighting::D.gain_db(f), expected, epsilon = 1e-3);
+    }
+}
+
+#[test]
+fn test_weighting_filters() {
+    let sample_rate = 48000.;
+    let db = |x: Complex<f64>| 20. * Real::log10(x.norm());
+
+    for weighting in [
+        Weighting::A,
+        Weighting::B,
+        Weighting::C,
+        Weighting::D,
+        Weighting::Itu468,
+        Weighting::Z,
+    ] {
+        let filter = weighting.filter::<f64>(sample_rate);
+        approx::assert_abs_diff_eq!(
+            db(cascade_response(&filter, 1000., sample_rate)),
+            0.,
+            epsilon = 1e-9
+        );
+
+        // The bilinear transform maps the analogue response at `warp(f)` to `f` exactly, so the
+        // networks match the analytic curves at every frequency, to the rounding of the standards'
+        // constants
+        let warp = |f: f64| sample_rate / PI * Real::tan(PI * f / sample_rate);
+        for f in third_octaves().filter(|&f| f < sample_rate / 2.) {
+            let response = db(cascade_response(&filter, f, sample_rate));
+            let expected = weighting.gain_db(warp(f)) - weighting.gain_db(warp(1000.));
+            approx::assert_abs_diff_eq!(response, expected, epsilon = 1e-3);
+        }
+    }
+
+    // And close to them, short of the Nyquist frequency
+    let filter = Weighting::A.filter::<f64>(sample_rate);
+    for f in third_octaves().filter(|&f| f < 8500.) {
+        let error = db(cascade_response(&filter, f, sample_rate)) - Weighting::A.gain_db(f);
+        let tolerance = if f < 5500. { 0.1 } else { 1. };
+        assert!(error.abs() < tolerance, "{error} dB at {f} Hz");
+    }
+}
+
+#[test]
+fn test_weighting_spectrum() {
+    use alloc::vec;
+    use num_traits::Zero;
+
+    // A-weighting a spectrum scales both halves of each bin alike, so the signal stays real
+    let (n, sample_rate) = (1024, 8192.);
+    let mut input = vec![0.; n];
+    crate::test_noise(11, &mut input);
+    let input: alloc::vec::Vec<_> = input.iter().map(|&x| Complex::new(x, 0.)).collect();
+    let mut spectrum = vec![Complex::zero(); n];
+    crate::simd_cooley_tukey::simd_cooley_tukey_fft(&input, &mut spectrum);
+
+    let mut weighted = spectrum.clone();
+    Weighting::A.apply(&mut weighted, sample_rate);
+    let mut curve = vec![0f32; n / 2];
+    Weighting::A.curve(sample_rate, n, &mut curve);
+
+    assert_eq!(weighted[0], Complex::zero());
+    for k in 1..n / 2 {
+        let scale = spectrum[k].norm();
+        assert!((weighted[k] - spectrum[k] * curve[k]).norm() <= 1e-6 * scale);
+        assert!((weighted[n - k] - weighted[k].conj()).norm() <= 1e-4 * scale);
+    }
+    // 1 kHz is bin 125
+    approx::assert_relative_eq!(curve[125], 1.);
+}
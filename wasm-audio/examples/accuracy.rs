@@ -0,0 +1,22 @@
+//! Print the accuracy of every FFT implementation against an `f64` reference DFT, in `f32` and
+//! then in `f64`.
+//!
+//! ```text
+//! cargo run --release --example accuracy
+//! ```
+
+use wasm_audio::accuracy::{report, report_f64, REPORT_HEADER};
+
+fn main() {
+    let sizes: Vec<usize> = (4..=14).map(|bits| 1 << bits).collect();
+
+    println!("f32\n{REPORT_HEADER}");
+    for row in report(&sizes, 4096) {
+        println!("{row}");
+    }
+
+    println!("\nf64\n{REPORT_HEADER}");
+    for row in report_f64(&sizes, 4096) {
+        println!("{row}");
+    }
+}
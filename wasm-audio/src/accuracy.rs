@@ -0,0 +1,438 @@
+//! Numerical accuracy of the FFT implementations against an `f64` reference DFT.
+
+use std::fmt;
+
+use rustfft::{num_complex::Complex, num_traits::Zero, FftPlanner};
+
+use crate::{
+    siggen::{self, NoiseColor, Waveform},
+    simd::Float,
+};
+
+/// Forward transform with the same signature as [`crate::cooley_tukey::cooley_tukey_fft`].
+pub type FftFn<T = f32> = fn(&[Complex<T>], &mut [Complex<T>]);
+
+fn rustfft<T: Float>(input: &[Complex<T>], output: &mut [Complex<T>]) {
+    output.copy_from_slice(input);
+    FftPlanner::new()
+        .plan_fft_forward(input.len())
+        .process(output);
+}
+
+/// Every forward transform in the crate that is generic over the sample type, with a display
+/// name.
+pub fn algorithms_for<T: Float>() -> Vec<(&'static str, FftFn<T>)> {
+    #[allow(unused_mut)]
+    let mut algorithms: Vec<(&'static str, FftFn<T>)> = vec![
+        ("dft", crate::dft::dft),
+        ("cooley_tukey", crate::cooley_tukey::cooley_tukey_fft),
+        ("rustfft", rustfft),
+        (
+            "simd_cooley_tukey",
+            crate::simd_cooley_tukey::simd_cooley_tukey_fft,
+        ),
+        (
+            "simd_cooley_tukey2",
+            crate::simd_cooley_tukey2::simd_cooley_tukey_fft2,
+        ),
+        (
+            "simd_cooley_tukey3",
+            crate::simd_cooley_tukey3::simd_cooley_tukey_fft3_complex,
+        ),
+    ];
+
+    #[cfg(feature = "parallel")]
+    algorithms.push(("parallel", crate::parallel::parallel_fft));
+
+    algorithms
+}
+
+/// Every `f32` forward transform in the crate available on this target, with a display name.
+pub fn algorithms() -> Vec<(&'static str, FftFn)> {
+    let mut algorithms = algorithms_for();
+
+    // Whichever kernel `dispatch::kernel()` picked for this machine
+    algorithms.push(("dispatch", crate::dispatch::fft));
+
+    #[cfg(all(target_arch = "wasm32", feature = "relaxed-simd"))]
+    algorithms.extend([
+        (
+            "simd_cooley_tukey2_relaxed",
+            crate::simd_cooley_tukey2::simd_cooley_tukey_fft2_relaxed as FftFn,
+        ),
+        (
+            "simd_cooley_tukey3_relaxed",
+            crate::simd_cooley_tukey3::simd_cooley_tukey_fft3_relaxed,
+        ),
+    ]);
+
+    algorithms
+}
+
+/// DFT computed in `f64`, with twiddle angles reduced exactly before calling `sin`/`cos`.
+pub fn reference_dft(input: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let n = input.len();
+
+    let twiddles: Vec<_> = (0..n)
+        .map(|i| Complex::from_polar(1., -2. * std::f64::consts::PI * i as f64 / n as f64))
+        .collect();
+
+    (0..n)
+        .map(|k| {
+            input
+                .iter()
+                .enumerate()
+                .map(|(j, x)| x * twiddles[(j * k) % n])
+                .sum()
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorMetrics {
+    pub max_abs: f64,
+    pub rms: f64,
+    /// Reference energy over error energy
+    pub snr_db: f64,
+}
+
+impl ErrorMetrics {
+    pub fn compare<T: Float>(actual: &[Complex<T>], reference: &[Complex<f64>]) -> Self {
+        assert_eq!(actual.len(), reference.len());
+
+        let mut max_abs: f64 = 0.;
+        let mut error_energy = 0.;
+        let mut signal_energy = 0.;
+
+        for (a, r) in actual.iter().zip(reference) {
+            let a = Complex::new(a.re.to_f64().unwrap(), a.im.to_f64().unwrap());
+            let e = (a - r).norm();
+            max_abs = max_abs.max(e);
+            error_energy += e * e;
+            signal_energy += r.norm_sqr();
+        }
+
+        Self {
+            max_abs,
+            rms: (error_energy / actual.len() as f64).sqrt(),
+            snr_db: 10. * (signal_energy / error_energy).log10(),
+        }
+    }
+
+    /// Combine with another measurement, keeping the worst of each metric.
+    pub fn worst(self, other: Self) -> Self {
+        Self {
+            max_abs: self.max_abs.max(other.max_abs),
+            rms: self.rms.max(other.rms),
+            snr_db: self.snr_db.min(other.snr_db),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccuracyReport {
+    pub algorithm: &'static str,
+    pub size: usize,
+    /// Forward transform against [`reference_dft`]
+    pub forward: ErrorMetrics,
+    /// `inverse(forward(x))` against `x`, with the inverse computed by conjugation
+    pub round_trip: ErrorMetrics,
+}
+
+impl AccuracyReport {
+    pub fn measure<T: Float>(algorithm: &'static str, fft: FftFn<T>, signal: &[f32]) -> Self {
+        let reference = reference_dft(&to_complex_f64(signal));
+        Self::measure_with_reference(algorithm, fft, signal, &reference)
+    }
+
+    /// Same as [`AccuracyReport::measure`], with the reference spectrum of `signal` given.
+    pub fn measure_with_reference<T: Float>(
+        algorithm: &'static str,
+        fft: FftFn<T>,
+        signal: &[f32],
+        reference: &[Complex<f64>],
+    ) -> Self {
+        let n = signal.len();
+        let input: Vec<_> = signal
+            .iter()
+            .map(|&x| Complex::new(T::from_f32(x).unwrap(), T::zero()))
+            .collect();
+
+        let mut spectrum = vec![Complex::zero(); n];
+        fft(&input, &mut spectrum);
+        let forward = ErrorMetrics::compare(&spectrum, reference);
+
+        // ifft(X) = conj(fft(conj(X))) / n
+        let conj: Vec<_> = spectrum.iter().map(|x| x.conj()).collect();
+        let mut round_trip = vec![Complex::zero(); n];
+        fft(&conj, &mut round_trip);
+        round_trip
+            .iter_mut()
+            .for_each(|x| *x = x.conj() / T::from_usize(n).unwrap());
+        let round_trip = ErrorMetrics::compare(&round_trip, &to_complex_f64(signal));
+
+        Self {
+            algorithm,
+            size: n,
+            forward,
+            round_trip,
+        }
+    }
+
+    /// Combine with another report, keeping the worst of each metric.
+    pub fn worst(self, other: Self) -> Self {
+        Self {
+            forward: self.forward.worst(other.forward),
+            round_trip: self.round_trip.worst(other.round_trip),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for AccuracyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<26} {:>6} {:>10.2e} {:>10.2e} {:>8.1} {:>10.2e} {:>8.1}",
+            self.algorithm,
+            self.size,
+            self.forward.max_abs,
+            self.forward.rms,
+            self.forward.snr_db,
+            self.round_trip.max_abs,
+            self.round_trip.snr_db,
+        )
+    }
+}
+
+/// Column headings matching the [`AccuracyReport`] `Display` output.
+pub const REPORT_HEADER: &str =
+    "algorithm                    size    max abs        rms  SNR(dB)  rt max abs  rt SNR(dB)";
+
+fn to_complex_f64(signal: &[f32]) -> Vec<Complex<f64>> {
+    signal.iter().map(|&x| Complex::new(x as f64, 0.)).collect()
+}
+
+fn test_signals(size: usize) -> Vec<Vec<f32>> {
+    let mut noise = vec![0.; size];
+    siggen::noise(NoiseColor::White, 42, &mut noise);
+
+    // Fundamental on a non-integer bin, so every bin has energy
+    let mut saw = vec![0.; size];
+    siggen::oscillator(Waveform::Sawtooth, 0.0371, 1., &mut saw);
+
+    let mut impulse = vec![0.; size];
+    siggen::impulse(size / 3, &mut impulse);
+
+    vec![noise, saw, impulse]
+}
+
+/// Worst-case accuracy of every algorithm over a set of generated signals at each size,
+/// skipping the O(n²) DFT above `max_dft_size`.
+pub fn report(sizes: &[usize], max_dft_size: usize) -> Vec<AccuracyReport> {
+    report_with(&algorithms(), sizes, max_dft_size)
+}
+
+/// Same as [`report`], for the `f64` transforms.
+pub fn report_f64(sizes: &[usize], max_dft_size: usize) -> Vec<AccuracyReport> {
+    report_with(&algorithms_for::<f64>(), sizes, max_dft_size)
+}
+
+fn report_with<T: Float>(
+    algorithms: &[(&'static str, FftFn<T>)],
+    sizes: &[usize],
+    max_dft_size: usize,
+) -> Vec<AccuracyReport> {
+    let mut reports = Vec::new();
+
+    for &size in sizes {
+        let signals = test_signals(size);
+        let references: Vec<_> = signals
+            .iter()
+            .map(|signal| reference_dft(&to_complex_f64(signal)))
+            .collect();
+
+        for &(name, fft) in algorithms {
+            if name == "dft" && size > max_dft_size {
+                continue;
+            }
+
+            let report = signals
+                .iter()
+                .zip(&references)
+                .map(|(signal, reference)| {
+                    AccuracyReport::measure_with_reference(name, fft, signal, reference)
+                })
+                .reduce(AccuracyReport::worst)
+                .unwrap();
+            reports.push(report);
+        }
+    }
+
+    reports
+}
+
+#[test]
+fn test_reference_dft() {
+    use approx::assert_abs_diff_eq;
+
+    // The reference must itself be much more accurate than the f32 kernels
+    let n = 64;
+    let input: Vec<_> = (0..n).map(|i| Complex::new((i % 7) as f64, 0.)).collect();
+    let spectrum = reference_dft(&input);
+
+    assert_abs_diff_eq!(
+        spectrum[0].re,
+        input.iter().map(|x| x.re).sum(),
+        epsilon = 1e-12
+    );
+
+    let round_trip: Vec<_> = reference_dft(&spectrum.iter().map(|x| x.conj()).collect::<Vec<_>>())
+        .iter()
+        .map(|x| x.conj() / n as f64)
+        .collect();
+    for (a, b) in round_trip.iter().zip(&input) {
+        assert_abs_diff_eq!((a - b).norm(), 0., epsilon = 1e-12);
+    }
+}
+
+/// The algorithm whose thresholds `name` shares. The SIMD kernels use the same recurrence for
+/// twiddles as the scalar Cooley-Tukey, so share its bounds, as does `parallel`, which only splits
+/// transforms above the sizes checked here.
+#[cfg(test)]
+fn threshold_family(name: &str) -> &str {
+    if name.contains("cooley_tukey") || name == "dispatch" || name == "parallel" {
+        "cooley_tukey"
+    } else {
+        name
+    }
+}
+
+/// Minimum forward and round-trip SNR in dB for each algorithm, pinned a few dB below the
+/// measured values so regressions are caught.
+#[cfg(test)]
+fn min_snr_db(name: &str, size: usize) -> (f64, f64) {
+    match (threshold_family(name), size) {
+        ("dft", 0..=64) => (100., 98.),
+        ("dft", 0..=256) => (88., 86.),
+        ("dft", _) => (77., 75.),
+        ("cooley_tukey", 0..=256) => (120., 115.),
+        ("cooley_tukey", 0..=2048) => (107., 103.),
+        ("cooley_tukey", _) => (90., 85.),
+        _ => (135., 130.),
+    }
+}
+
+/// Largest forward max abs, forward RMS and round-trip max abs errors for each algorithm, about
+/// three times the measured values. The test signals have unit amplitude, so the spectra, and
+/// with them the forward errors, grow with the size.
+#[cfg(test)]
+fn max_error(name: &str, size: usize) -> (f64, f64, f64) {
+    match (threshold_family(name), size) {
+        ("dft", 0..=64) => (5e-4, 1e-4, 4e-5),
+        ("dft", 0..=256) => (4e-3, 8e-4, 1.6e-4),
+        ("dft", _) => (7e-2, 6e-3, 8e-4),
+        ("cooley_tukey", 0..=256) => (1.2e-4, 2e-5, 6e-6),
+        ("cooley_tukey", 0..=2048) => (2.5e-3, 1.2e-4, 2e-5),
+        ("cooley_tukey", _) => (7e-2, 2e-3, 1.5e-4),
+        _ => (2.5e-4, 1.5e-5, 1e-6),
+    }
+}
+
+/// Check `report` against the SNR and error thresholds.
+#[cfg(test)]
+fn assert_within(report: &AccuracyReport, min_snr_db: (f64, f64), max_error: (f64, f64, f64)) {
+    let (forward_snr, round_trip_snr) = min_snr_db;
+    let (forward_max_abs, forward_rms, round_trip_max_abs) = max_error;
+
+    assert!(report.forward.snr_db > forward_snr, "{report}");
+    assert!(report.round_trip.snr_db > round_trip_snr, "{report}");
+    assert!(report.forward.max_abs < forward_max_abs, "{report}");
+    assert!(report.forward.rms < forward_rms, "{report}");
+    assert!(report.round_trip.max_abs < round_trip_max_abs, "{report}");
+}
+
+#[test]
+fn test_accuracy_thresholds() {
+    for report in report(&[16, 64, 256, 1024, 4096], 256) {
+        assert_within(
+            &report,
+            min_snr_db(report.algorithm, report.size),
+            max_error(report.algorithm, report.size),
+        );
+    }
+}
+
+/// Same as [`min_snr_db`], for the `f64` transforms.
+#[cfg(test)]
+fn min_snr_db_f64(name: &str, size: usize) -> (f64, f64) {
+    match (threshold_family(name), size) {
+        ("dft", 0..=64) => (275., 273.),
+        ("dft", 0..=256) => (262., 261.),
+        ("dft", _) => (251., 248.),
+        ("cooley_tukey", 0..=256) => (287., 283.),
+        ("cooley_tukey", 0..=2048) => (275., 271.),
+        ("cooley_tukey", _) => (262., 258.),
+        _ => (288., 300.),
+    }
+}
+
+/// Same as [`max_error`], for the `f64` transforms.
+#[cfg(test)]
+fn max_error_f64(name: &str, size: usize) -> (f64, f64, f64) {
+    match (threshold_family(name), size) {
+        ("dft", 0..=64) => (7e-13, 1.5e-13, 7e-14),
+        ("dft", 0..=256) => (9e-12, 1.5e-12, 3e-13),
+        ("dft", _) => (1e-10, 1e-11, 1.5e-12),
+        ("cooley_tukey", 0..=256) => (4e-13, 6e-14, 1.5e-14),
+        ("cooley_tukey", 0..=2048) => (1.2e-11, 6e-13, 1.2e-13),
+        ("cooley_tukey", _) => (1.2e-10, 3.5e-12, 3e-13),
+        _ => (5e-12, 2.5e-13, 2.5e-15),
+    }
+}
+
+#[test]
+fn test_accuracy_thresholds_f64() {
+    for report in report_f64(&[16, 64, 256, 1024, 4096], 256) {
+        assert_within(
+            &report,
+            min_snr_db_f64(report.algorithm, report.size),
+            max_error_f64(report.algorithm, report.size),
+        );
+    }
+}
+
+/// Whether relaxed multiply-adds are fused is up to the engine, so the relaxed kernels can only
+/// be held to error bounds: the usual thresholds, and at most twice the error energy (3 dB) of
+/// the same kernel with separate multiplies and adds. Fusing moves the twiddle recurrence's
+/// rounding either way, measured from -1.3 to +4 dB in V8 on x86_64.
+#[cfg(all(test, target_arch = "wasm32", feature = "relaxed-simd"))]
+#[wasm_bindgen_test::wasm_bindgen_test]
+fn test_relaxed_accuracy() {
+    let reports = report(&[16, 64, 256, 1024, 4096], 0);
+
+    for relaxed in reports.iter().filter(|r| r.algorithm.ends_with("_relaxed")) {
+        let unfused = reports
+            .iter()
+            .find(|r| {
+                r.size == relaxed.size
+                    && r.algorithm == relaxed.algorithm.trim_end_matches("_relaxed")
+            })
+            .unwrap();
+
+        assert_within(
+            relaxed,
+            min_snr_db(relaxed.algorithm, relaxed.size),
+            max_error(relaxed.algorithm, relaxed.size),
+        );
+
+        assert!(
+            relaxed.forward.snr_db > unfused.forward.snr_db - 3.,
+            "{relaxed}\n{unfused}"
+        );
+        assert!(
+            relaxed.round_trip.snr_db > unfused.round_trip.snr_db - 3.,
+            "{relaxed}\n{unfused}"
+        );
+    }
+}
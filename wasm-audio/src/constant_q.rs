@@ -0,0 +1,165 @@
+use rustfft::{num_complex::Complex, num_traits::Zero};
+use wasm_bindgen::prelude::*;
+
+use crate::{cqt::Cqt, stream::HopRing};
+
+/// Streaming constant-Q analyser and chromagram, driven like [`crate::StreamProcessor`].
+///
+/// Samples are pushed in blocks into a ring buffer of [`WasmCqt::fft_len`] samples, which is
+/// transformed every `hop` samples once full. The kernels are computed in [`WasmCqt::new`], which
+/// takes a while for low `min_freq` or many bins per octave. The analysis itself never allocates
+/// after that, though from JS each block passed to [`WasmCqt::process`] is copied into the wasm
+/// heap by wasm-bindgen.
+#[wasm_bindgen]
+pub struct WasmCqt {
+    cqt: Cqt<f32>,
+    ring: HopRing,
+
+    bins: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+}
+
+#[wasm_bindgen]
+impl WasmCqt {
+    /// `bins` bins from `min_freq` Hz, `bins_per_octave` to the octave, analysed every `hop`
+    /// samples. For [`WasmCqt::chroma`], `bins_per_octave` should be a multiple of 12.
+    pub fn new(
+        sample_rate: f32,
+        min_freq: f32,
+        bins: usize,
+        bins_per_octave: usize,
+        hop: usize,
+    ) -> Self {
+        crate::set_panic_hook();
+
+        let cqt = Cqt::new(sample_rate as f64, min_freq as f64, bins, bins_per_octave);
+        let fft_len = cqt.fft_len();
+        let scratch = vec![Complex::zero(); cqt.scratch_len()];
+
+        Self {
+            cqt,
+            ring: HopRing::new(fft_len, hop),
+            bins: vec![Complex::zero(); bins],
+            scratch,
+        }
+    }
+
+    /// Samples per frame, set by the window of the lowest bin.
+    pub fn fft_len(&self) -> usize {
+        self.cqt.fft_len()
+    }
+
+    pub fn bins(&self) -> usize {
+        self.cqt.bins()
+    }
+
+    pub fn hop(&self) -> usize {
+        self.ring.hop()
+    }
+
+    /// Centre frequency of each bin, in Hz.
+    pub fn frequencies(&self, output: &mut [f32]) {
+        assert_eq!(output.len(), self.bins());
+
+        for (k, y) in output.iter_mut().enumerate() {
+            *y = self.cqt.frequency(k) as f32;
+        }
+    }
+
+    /// Total number of frames analysed since construction or the last [`WasmCqt::reset`].
+    pub fn frames(&self) -> u32 {
+        self.ring.frames()
+    }
+
+    /// Push a block of samples, analysing as many times as the hop size requires.
+    ///
+    /// Returns `true` if the bins were updated during this call.
+    pub fn process(&mut self, block: &[f32]) -> bool {
+        self.ring.push(block, |frame| {
+            self.cqt
+                .process_with_scratch(frame, &mut self.bins, &mut self.scratch);
+        })
+    }
+
+    /// `log10 |X|` of each bin of the latest frame. A sine of amplitude `a` on a bin gives
+    /// `log10(a / 2)`.
+    pub fn spectrum(&self, output: &mut [f32]) {
+        assert_eq!(output.len(), self.bins.len());
+
+        for (y, x) in output.iter_mut().zip(&self.bins) {
+            *y = x.norm().log10();
+        }
+    }
+
+    /// Energy of each of the 12 pitch classes from C in the latest frame, the strongest being 1.
+    pub fn chroma(&self, output: &mut [f32]) {
+        self.cqt.chroma(&self.bins, output);
+    }
+
+    /// Forget all buffered samples. The latest bins are kept.
+    pub fn reset(&mut self) {
+        self.ring.reset();
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_cqt_chords() {
+    // C4 to B5, three bins to the semitone
+    let sample_rate = 8000.;
+    let mut cqt = WasmCqt::new(sample_rate, 261.63, 72, 36, 512);
+    assert_eq!(cqt.fft_len(), 2048);
+
+    // C major, then A minor, each long enough that the latest frame holds only that chord
+    let mut chroma = [0.; 12];
+    let mut signal = vec![0.; 2560];
+    for (chord, classes) in [
+        ([261.63, 329.63, 392.], [0, 4, 7]),
+        ([440., 523.25, 659.25], [9, 0, 4]),
+    ] {
+        crate::siggen::multitone(&chord, sample_rate, &mut signal);
+
+        let mut updated = false;
+        for block in signal.chunks(crate::RENDER_QUANTUM) {
+            updated |= cqt.process(block);
+        }
+        assert!(updated);
+
+        cqt.chroma(&mut chroma);
+        for (class, &x) in chroma.iter().enumerate() {
+            if classes.contains(&class) {
+                assert!(x > 0.5, "class {class} at {x} in {chroma:?}");
+            } else {
+                assert!(x < 0.1, "class {class} at {x} in {chroma:?}");
+            }
+        }
+    }
+    // At 2048 samples, then every 512 to 5120
+    assert_eq!(cqt.frames(), 7);
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_cqt_spectrum() {
+    let sample_rate = 8000.;
+    let mut cqt = WasmCqt::new(sample_rate, 110., 48, 12, 1024);
+    let mut frequencies = vec![0.; 48];
+    let mut spectrum = vec![0.; 48];
+    cqt.frequencies(&mut frequencies);
+
+    let mut signal = vec![0.; cqt.fft_len()];
+    crate::siggen::multitone(&[frequencies[30]], sample_rate, &mut signal);
+    assert!(cqt.process(&signal));
+    cqt.spectrum(&mut spectrum);
+
+    let peak = (0..48)
+        .max_by(|&a, &b| spectrum[a].total_cmp(&spectrum[b]))
+        .unwrap();
+    assert_eq!(peak, 30);
+    approx::assert_abs_diff_eq!(spectrum[30], 0.5f32.log10(), epsilon = 1e-2);
+
+    cqt.reset();
+    assert_eq!(cqt.frames(), 0);
+}
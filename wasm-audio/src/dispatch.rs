@@ -0,0 +1,130 @@
+//! Forward FFT on the fastest of the crate's kernels that the running machine supports.
+//!
+//! On x86_64 the CPU is checked once for AVX2 and FMA, falling back to SSE2. Other targets have
+//! a single choice, made at compile time: NEON on aarch64, and on wasm either `simd128` or the
+//! scalar backend, depending on which of the two web builds is loaded (see the `simd128`
+//! feature).
+//!
+//! Only the crate's own kernels are candidates. rustfft ([`crate::Algorithm::Lib`]) is faster
+//! than all of them, natively and on wasm.
+
+use std::{fmt, sync::OnceLock};
+
+use rustfft::num_complex::Complex;
+
+use crate::{
+    accuracy::FftFn,
+    simd::{self, Simd4},
+    simd_cooley_tukey::simd_cooley_tukey_fft,
+    simd_cooley_tukey3::simd_cooley_tukey_fft3_complex,
+};
+
+/// Kernel chosen by [`kernel`].
+#[derive(Debug, Clone, Copy)]
+pub struct Kernel {
+    pub algorithm: &'static str,
+    /// [`Simd4::NAME`] of the SIMD backend
+    pub backend: &'static str,
+    pub fft: FftFn,
+}
+
+impl fmt::Display for Kernel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.algorithm, self.backend)
+    }
+}
+
+/// The kernel [`fft`] runs, detected on first use.
+pub fn kernel() -> Kernel {
+    static KERNEL: OnceLock<Kernel> = OnceLock::new();
+
+    *KERNEL.get_or_init(detect)
+}
+
+/// Forward transform with the same signature as [`crate::cooley_tukey::cooley_tukey_fft`].
+pub fn fft(input: &[Complex<f32>], output: &mut [Complex<f32>]) {
+    (kernel().fft)(input, output);
+}
+
+// Milliseconds per transform, from the native benchmark and from wasm-bench under wasmtime:
+//
+//   size   x86_64 SSE2             wasm simd128            wasm scalar
+//          simd1  simd2  simd3     simd1  simd2  simd3     simd1  simd2  simd3
+//    256   0.009  0.010  0.011     0.012  0.014  0.010     0.016  0.014  0.013
+//   1024   0.043  0.047  0.047     0.076  0.084  0.061     0.071  0.065  0.076
+//   4096   0.193  0.222  0.222     0.328  0.343  0.270     0.326  0.305  0.323
+//
+// `simd_cooley_tukey` is built for AVX2 too, which takes it to 0.028 ms at 1024 points. So
+// `simd_cooley_tukey3` runs on wasm with `simd128`, and `simd_cooley_tukey` everywhere else;
+// without `simd128` the three are within 10% of each other.
+fn detect() -> Kernel {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+        return Kernel {
+            algorithm: "simd_cooley_tukey",
+            backend: simd::AVX2_NAME,
+            fft: simd_cooley_tukey_fft_avx2,
+        };
+    }
+
+    if cfg!(all(
+        target_arch = "wasm32",
+        any(feature = "simd128", target_feature = "simd128")
+    )) {
+        return Kernel {
+            algorithm: "simd_cooley_tukey3",
+            backend: simd::Native::NAME,
+            fft: simd_cooley_tukey_fft3_complex,
+        };
+    }
+
+    Kernel {
+        algorithm: "simd_cooley_tukey",
+        backend: simd::Native::NAME,
+        fft: simd_cooley_tukey_fft,
+    }
+}
+
+/// Only handed out by [`detect`] once AVX2 and FMA are known to be present.
+#[cfg(target_arch = "x86_64")]
+fn simd_cooley_tukey_fft_avx2(input: &[Complex<f32>], output: &mut [Complex<f32>]) {
+    // SAFETY: see above.
+    unsafe { crate::simd_cooley_tukey::simd_cooley_tukey_fft_avx2(input, output) }
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_dispatch() {
+    use crate::cooley_tukey::cooley_tukey_fft;
+
+    let kernel = kernel();
+    let algorithm = if kernel.backend == "simd128" {
+        "simd_cooley_tukey3"
+    } else {
+        "simd_cooley_tukey"
+    };
+    assert_eq!(
+        kernel.to_string(),
+        format!("{algorithm} ({})", kernel.backend)
+    );
+
+    #[cfg(target_arch = "x86_64")]
+    assert_eq!(
+        kernel.backend == "avx2",
+        is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")
+    );
+
+    let mut signal = vec![0.; 1024];
+    crate::siggen::noise(crate::siggen::NoiseColor::White, 5, &mut signal);
+    let input: Vec<_> = signal.iter().map(|&x| Complex::new(x, 0.)).collect();
+
+    let mut expected = vec![Complex::new(0., 0.); input.len()];
+    cooley_tukey_fft(&input, &mut expected);
+
+    let mut output = vec![Complex::new(0., 0.); input.len()];
+    fft(&input, &mut output);
+    for (a, b) in output.iter().zip(&expected) {
+        approx::assert_abs_diff_eq!((a - b).norm(), 0., epsilon = 1e-3);
+    }
+}
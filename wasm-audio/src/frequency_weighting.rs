@@ -0,0 +1,178 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{biquad::Biquad, weighting::Weighting};
+
+/// Frequency weighting curve, for spectra, signals and band levels.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrequencyWeighting {
+    A,
+    B,
+    C,
+    D,
+    Itu468,
+    Z,
+}
+
+impl From<FrequencyWeighting> for Weighting {
+    fn from(weighting: FrequencyWeighting) -> Self {
+        match weighting {
+            FrequencyWeighting::A => Weighting::A,
+            FrequencyWeighting::B => Weighting::B,
+            FrequencyWeighting::C => Weighting::C,
+            FrequencyWeighting::D => Weighting::D,
+            FrequencyWeighting::Itu468 => Weighting::Itu468,
+            FrequencyWeighting::Z => Weighting::Z,
+        }
+    }
+}
+
+/// A weighting curve sampled at the bins of the spectra from [`crate::WasmFft`], which depend on
+/// the transform size and sample rate.
+#[wasm_bindgen]
+pub struct WasmWeightingCurve {
+    /// `log10` of the amplitude gain at each bin
+    log_gains: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl WasmWeightingCurve {
+    /// The curve at the `fft_len / 2` bins of an `fft_len`-point transform at `sample_rate`.
+    pub fn new(weighting: FrequencyWeighting, sample_rate: f32, fft_len: usize) -> Self {
+        crate::set_panic_hook();
+        assert!(crate::is_power_of_2(fft_len));
+
+        let mut gains = vec![0f64; fft_len / 2];
+        Weighting::from(weighting).curve(sample_rate as f64, fft_len, &mut gains);
+
+        Self {
+            log_gains: gains.iter().map(|gain| gain.log10() as f32).collect(),
+        }
+    }
+
+    pub fn bins(&self) -> usize {
+        self.log_gains.len()
+    }
+
+    /// Gain at each bin, in dB.
+    pub fn gains_db(&self, output: &mut [f32]) {
+        assert_eq!(output.len(), self.bins());
+
+        for (y, &x) in output.iter_mut().zip(&self.log_gains) {
+            *y = 20. * x;
+        }
+    }
+
+    /// Weight a `log10 |X|` spectrum from [`crate::WasmFft`] in place. Most curves are zero at DC,
+    /// where the spectrum goes to minus infinity.
+    pub fn apply(&self, spectrum: &mut [f32]) {
+        assert_eq!(spectrum.len(), self.bins());
+
+        for (y, &x) in spectrum.iter_mut().zip(&self.log_gains) {
+            *y += x;
+        }
+    }
+}
+
+/// A weighting network for signals: the analogue filter mapped to the sample rate, run in `f64`.
+///
+/// It follows the curve closely at low frequencies, but falls short of it towards the Nyquist
+/// frequency; see [`Weighting::filter`]. The filter holds no buffers of its own, so
+/// [`WasmWeightingFilter::process`] never allocates for Rust callers; from JS, wasm-bindgen copies
+/// each block into the wasm heap and back out.
+#[wasm_bindgen]
+pub struct WasmWeightingFilter {
+    sections: [Biquad<f64>; 3],
+}
+
+#[wasm_bindgen]
+impl WasmWeightingFilter {
+    pub fn new(weighting: FrequencyWeighting, sample_rate: f32) -> Self {
+        crate::set_panic_hook();
+
+        Self {
+            sections: Weighting::from(weighting).filter(sample_rate as f64),
+        }
+    }
+
+    /// Weight a block of samples in place, continuing from the previous block.
+    pub fn process(&mut self, block: &mut [f32]) {
+        for x in block {
+            let y = self
+                .sections
+                .iter_mut()
+                .fold(*x as f64, |x, section| section.process_sample(x));
+            *x = y as f32;
+        }
+    }
+
+    /// Clear the filter state, as before the first block.
+    pub fn reset(&mut self) {
+        self.sections.iter_mut().for_each(Biquad::reset);
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_weighting_filter_sines() {
+    // IEC 61672-1 Table 3 at 48 kHz, where the network is within 0.1 dB of the curve
+    let sample_rate = 48000.;
+    let mut signal = vec![0.; 9600];
+
+    for (weighting, freq, expected) in [
+        (FrequencyWeighting::A, 100., -19.1),
+        (FrequencyWeighting::A, 1000., 0.),
+        (FrequencyWeighting::A, 2500., 1.3),
+        (FrequencyWeighting::C, 31.5, -3.0),
+        (FrequencyWeighting::Itu468, 4000., 10.5),
+    ] {
+        let mut filter = WasmWeightingFilter::new(weighting, sample_rate);
+        crate::siggen::multitone(&[freq], sample_rate, &mut signal);
+
+        // A block at a time, once settled
+        for block in signal.chunks_mut(crate::RENDER_QUANTUM) {
+            filter.process(block);
+        }
+        let peak = signal[4800..].iter().fold(0f32, |a, &b| a.max(b.abs()));
+        approx::assert_abs_diff_eq!(20. * peak.log10(), expected, epsilon = 0.1);
+
+        filter.reset();
+        let mut silence = [0.; 4];
+        filter.process(&mut silence);
+        assert_eq!(silence, [0.; 4]);
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_weighting_curve() {
+    // At 8192 Hz, 1024 samples put the FFT bins every 8 Hz
+    let (sample_rate, n) = (8192., 1024);
+    let mut signal = vec![0.; n];
+    crate::siggen::noise(crate::siggen::NoiseColor::Pink, 9, &mut signal);
+
+    let mut spectrum = vec![0.; n / 2];
+    crate::WasmFft::new().lib_fft(&signal, &mut spectrum);
+    let unweighted = spectrum.clone();
+
+    let curve = WasmWeightingCurve::new(FrequencyWeighting::A, sample_rate, n);
+    let mut gains = vec![0.; curve.bins()];
+    curve.gains_db(&mut gains);
+    curve.apply(&mut spectrum);
+
+    // 1 kHz is bin 125, and 100 Hz between bins 12 and 13
+    approx::assert_abs_diff_eq!(gains[125], 0., epsilon = 1e-6);
+    assert!(-19.9 < gains[12] && gains[13] < -18.4);
+    assert_eq!(spectrum[0], f32::NEG_INFINITY);
+    for k in 1..n / 2 {
+        approx::assert_abs_diff_eq!(spectrum[k], unweighted[k] + gains[k] / 20., epsilon = 1e-5);
+    }
+}
+
+#[test]
+#[should_panic(expected = "is_power_of_2(fft_len)")]
+fn test_weighting_curve_not_power_of_2() {
+    WasmWeightingCurve::new(FrequencyWeighting::A, 48000., 1000);
+}
@@ -0,0 +1,255 @@
+use std::f64::consts::PI;
+
+use rustfft::{num_complex::Complex, num_traits::Zero};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    octave::{OctaveBands, OctaveFilterBank},
+    simd_cooley_tukey::simd_cooley_tukey_fft,
+    weighting::Weighting,
+    FrequencyWeighting,
+};
+
+/// Level in dB of a mean square, relative to a full-scale square wave, so a full-scale sine is
+/// at -3.01 dB.
+fn decibels(mean_square: f64) -> f32 {
+    (10. * mean_square.log10()) as f32
+}
+
+/// Octave or fractional-octave band levels of frames of [`WasmOctaveBands::fft_len`] samples,
+/// from their spectra.
+///
+/// Frames are Hann windowed, and the window's loss of power is made up, so a steady signal reads
+/// the same as through [`WasmOctaveFilterBank`]. Bands narrower than a few FFT bins smear into
+/// their neighbours; for low third-octaves, use long frames or the filter bank. After
+/// construction, [`WasmOctaveBands::process`] doesn't allocate itself, but from JS, wasm-bindgen
+/// copies the frame and the levels through the wasm heap on every call.
+#[wasm_bindgen]
+pub struct WasmOctaveBands {
+    bands: OctaveBands,
+    sample_rate: f64,
+    weighting: Weighting,
+    window: Vec<f64>,
+    input: Vec<Complex<f64>>,
+    spectrum: Vec<Complex<f64>>,
+    power: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl WasmOctaveBands {
+    /// The `1 / fraction` octave bands with mid-band frequencies from `min_freq` to `max_freq`
+    /// Hz, such as 1 for octaves or 3 for third-octaves, from frames of `fft_len` samples, a
+    /// power of two.
+    pub fn new(
+        sample_rate: f32,
+        fraction: usize,
+        min_freq: f32,
+        max_freq: f32,
+        fft_len: usize,
+    ) -> Self {
+        crate::set_panic_hook();
+        assert!(crate::is_power_of_2(fft_len));
+
+        let bands = OctaveBands::new(fraction, min_freq as f64, max_freq as f64);
+
+        // Hann, scaled to unit mean square so it keeps the power of stationary signals
+        let hann = |n: usize| 0.5 - 0.5 * (2. * PI * n as f64 / fft_len as f64).cos();
+        let mean_square = (0..fft_len).map(|n| hann(n) * hann(n)).sum::<f64>() / fft_len as f64;
+        let window = (0..fft_len).map(|n| hann(n) / mean_square.sqrt()).collect();
+
+        Self {
+            power: vec![0.; bands.bands()],
+            bands,
+            sample_rate: sample_rate as f64,
+            weighting: Weighting::Z,
+            window,
+            input: vec![Complex::zero(); fft_len],
+            spectrum: vec![Complex::zero(); fft_len],
+        }
+    }
+
+    pub fn set_weighting(&mut self, weighting: FrequencyWeighting) {
+        self.weighting = weighting.into();
+    }
+
+    pub fn fft_len(&self) -> usize {
+        self.input.len()
+    }
+
+    pub fn bands(&self) -> usize {
+        self.bands.bands()
+    }
+
+    /// Exact mid-band frequency of each band, in Hz.
+    pub fn mid_frequencies(&self, output: &mut [f32]) {
+        assert_eq!(output.len(), self.bands());
+
+        for (band, y) in output.iter_mut().enumerate() {
+            *y = self.bands.mid_frequency(band) as f32;
+        }
+    }
+
+    /// Level of each band in a frame, in dB relative to full scale.
+    pub fn process(&mut self, frame: &[f32], output: &mut [f32]) {
+        assert_eq!(frame.len(), self.input.len());
+        assert_eq!(output.len(), self.bands());
+
+        for ((y, &x), &w) in self.input.iter_mut().zip(frame).zip(&self.window) {
+            *y = Complex::new(x as f64 * w, 0.);
+        }
+        simd_cooley_tukey_fft(&self.input, &mut self.spectrum);
+
+        let half = self.spectrum.len() / 2;
+        self.bands.power_from_spectrum(
+            &self.spectrum[..half],
+            self.sample_rate,
+            self.weighting,
+            &mut self.power,
+        );
+
+        for (y, &power) in output.iter_mut().zip(&self.power) {
+            *y = decibels(power);
+        }
+    }
+}
+
+/// Octave or fractional-octave band levels through a bank of IEC 61260-1 class 1 band-pass
+/// filters, integrated from the last reset: equivalent continuous levels, `Leq`.
+///
+/// Unlike [`WasmOctaveBands`], low bands are as sharp as high ones, at the cost of four biquads
+/// per sample per band; every band's upper edge must be below the Nyquist frequency. Rust callers
+/// get no allocation after construction; JS callers pay for wasm-bindgen's copy of each block.
+#[wasm_bindgen]
+pub struct WasmOctaveFilterBank {
+    bank: OctaveFilterBank<f64>,
+    block: Vec<f64>,
+    mean_squares: Vec<f64>,
+}
+
+#[wasm_bindgen]
+impl WasmOctaveFilterBank {
+    /// The `1 / fraction` octave bands with mid-band frequencies from `min_freq` to `max_freq`
+    /// Hz, such as 1 for octaves or 3 for third-octaves. The weighting is applied at each band's
+    /// mid-band frequency.
+    pub fn new(
+        sample_rate: f32,
+        fraction: usize,
+        min_freq: f32,
+        max_freq: f32,
+        weighting: FrequencyWeighting,
+    ) -> Self {
+        crate::set_panic_hook();
+
+        let bands = OctaveBands::new(fraction, min_freq as f64, max_freq as f64);
+        let bank = OctaveFilterBank::new(bands, sample_rate as f64, weighting.into());
+        let mean_squares = vec![0.; bank.bands().bands()];
+
+        Self {
+            bank,
+            block: vec![0.; crate::RENDER_QUANTUM],
+            mean_squares,
+        }
+    }
+
+    pub fn bands(&self) -> usize {
+        self.bank.bands().bands()
+    }
+
+    /// Exact mid-band frequency of each band, in Hz.
+    pub fn mid_frequencies(&self, output: &mut [f32]) {
+        assert_eq!(output.len(), self.bands());
+
+        for (band, y) in output.iter_mut().enumerate() {
+            *y = self.bank.bands().mid_frequency(band) as f32;
+        }
+    }
+
+    /// Filter a block of any length into the bands.
+    pub fn process(&mut self, block: &[f32]) {
+        // Through the bank in `f64`, a render quantum at a time
+        for chunk in block.chunks(self.block.len()) {
+            let samples = &mut self.block[..chunk.len()];
+            for (y, &x) in samples.iter_mut().zip(chunk) {
+                *y = x as f64;
+            }
+            self.bank.process(samples);
+        }
+    }
+
+    /// Level of each band since the last reset, in dB relative to full scale.
+    pub fn levels(&mut self, output: &mut [f32]) {
+        assert_eq!(output.len(), self.bands());
+
+        self.bank.mean_squares(&mut self.mean_squares);
+        for (y, &mean_square) in output.iter_mut().zip(&self.mean_squares) {
+            *y = decibels(mean_square);
+        }
+    }
+
+    /// Start integrating again. The filters keep their state.
+    pub fn reset(&mut self) {
+        self.bank.reset();
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_octave_bands_sine() {
+    // A full-scale sine at 1 kHz reads -3 dB in its band, whichever weighting
+    let sample_rate = 48000.;
+    let mut analyser = WasmOctaveBands::new(sample_rate, 3, 100., 10000., 8192);
+    let mut frequencies = vec![0.; analyser.bands()];
+    let mut levels = vec![0.; analyser.bands()];
+    analyser.mid_frequencies(&mut frequencies);
+    let band = frequencies.iter().position(|&f| f == 1000.).unwrap();
+
+    let mut signal = vec![0.; 8192];
+    crate::siggen::multitone(&[1000.], sample_rate, &mut signal);
+
+    for weighting in [
+        FrequencyWeighting::Z,
+        FrequencyWeighting::A,
+        FrequencyWeighting::C,
+    ] {
+        analyser.set_weighting(weighting);
+        analyser.process(&signal, &mut levels);
+        approx::assert_abs_diff_eq!(levels[band], -3.01, epsilon = 0.05);
+        assert!(
+            levels[band - 2] < -60. && levels[band + 2] < -60.,
+            "{levels:?}"
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_octave_filter_bank_matches_spectrum() {
+    // Pink noise has equal power in each band, and both analysers should agree on it
+    let sample_rate = 48000.;
+    let mut signal = vec![0.; 65536];
+    crate::siggen::noise(crate::siggen::NoiseColor::Pink, 5, &mut signal);
+
+    let mut analyser = WasmOctaveBands::new(sample_rate, 1, 250., 8000., signal.len());
+    let mut spectral = vec![0.; analyser.bands()];
+    analyser.set_weighting(FrequencyWeighting::A);
+    analyser.process(&signal, &mut spectral);
+
+    let mut bank = WasmOctaveFilterBank::new(sample_rate, 1, 250., 8000., FrequencyWeighting::A);
+    let mut filtered = vec![0.; bank.bands()];
+    bank.process(&signal[..8192]);
+    bank.reset();
+    bank.process(&signal);
+    bank.levels(&mut filtered);
+
+    // The filters' skirts let in a little from either side, and the weighting is taken at
+    // mid-band rather than across the band
+    approx::assert_abs_diff_eq!(filtered.as_slice(), spectral.as_slice(), epsilon = 0.5);
+}
+
+#[test]
+#[should_panic(expected = "is_power_of_2(fft_len)")]
+fn test_octave_bands_not_power_of_2() {
+    WasmOctaveBands::new(48000., 3, 100., 10000., 6000);
+}
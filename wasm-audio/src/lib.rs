@@ -1,13 +1,43 @@
-mod cooley_tukey;
-mod dft;
-mod simd_cooley_tukey;
-mod simd_cooley_tukey2;
-mod simd_cooley_tukey3;
+//! Browser bindings and host-side tools around [`wasm_audio_core`]: the `WasmFft` wasm-bindgen
+//! API, signal generators, WAV files, streaming, runtime kernel dispatch and accuracy reports.
+
+pub mod accuracy;
+mod constant_q;
+pub mod dispatch;
+mod frequency_weighting;
+mod levels;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+mod plan;
+pub mod protocol;
+pub mod siggen;
+pub mod spsc;
+mod stream;
+mod tone;
+#[cfg(target_os = "wasi")]
+mod wasi_bench;
 mod wasmfft;
+mod wav;
+mod zoom;
 
 // use wasm_bindgen::prelude::*;
 
+// The kernels keep their paths from before the core was split out
+pub use wasm_audio_core::{
+    cooley_tukey, dft, fixed, simd, simd_cooley_tukey, simd_cooley_tukey2, simd_cooley_tukey3,
+};
+
+pub use wasm_audio_core::{biquad, chirp_z, cqt, goertzel, octave, sliding_dft, weighting};
+
+pub use constant_q::*;
+pub use frequency_weighting::*;
+pub use levels::*;
+pub use plan::*;
+pub use stream::*;
+pub use tone::*;
 pub use wasmfft::*;
+pub use wav::*;
+pub use zoom::*;
 
 pub(crate) fn set_panic_hook() {
     // When the `console_error_panic_hook` feature is enabled, we can call the
@@ -24,12 +54,4 @@ pub(crate) fn set_panic_hook() {
 // #[global_allocator]
 // static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-pub(crate) fn is_power_of_2(mut n: usize) -> bool {
-    while n > 1 {
-        n >>= 1;
-        if n == 1 {
-            return true;
-        }
-    }
-    false
-}
+pub(crate) use wasm_audio_core::is_power_of_2;
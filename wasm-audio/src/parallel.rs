@@ -0,0 +1,130 @@
+//! Large transforms split across a rayon thread pool, with the `parallel` feature.
+//!
+//! Natively this is rayon's global pool. On the web, the pool is a set of Web Workers, which
+//! must be started from JS with `await initThreadPool(navigator.hardwareConcurrency)` before the
+//! first call, and the module must be built with wasm threads (see the README).
+
+use rayon::prelude::*;
+use rustfft::{num_complex::Complex, num_traits::Zero};
+
+use crate::{simd::Float, simd_cooley_tukey::simd_cooley_tukey_fft};
+
+#[cfg(target_arch = "wasm32")]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
+/// Transforms up to this size run on a single thread. Larger ones are split into transforms of
+/// this size, which fit in the L2 cache of current CPUs.
+pub const CHUNK: usize = 1 << 12;
+
+/// Forward transform on every thread of the pool, with the same signature as
+/// [`crate::cooley_tukey::cooley_tukey_fft`].
+///
+/// The `n / CHUNK` interleaved subsequences `input[r], input[r + n / CHUNK], ...` are transformed
+/// independently with [`simd_cooley_tukey_fft`]. The remaining radix-2 stages combine them, with
+/// the butterflies of each stage split into `CHUNK`-long runs across the pool.
+pub fn parallel_fft<T: Float>(input: &[Complex<T>], output: &mut [Complex<T>]) {
+    assert_eq!(input.len(), output.len());
+    assert!(crate::is_power_of_2(input.len()));
+
+    let n = input.len();
+    if n <= CHUNK {
+        simd_cooley_tukey_fft(input, output);
+        return;
+    }
+
+    let chunks = n / CHUNK;
+    let chunk_bits = chunks.trailing_zeros();
+
+    // After a full bit reversal, chunk `c` would hold the subsequence starting at `input[r]`, with
+    // `r` the bit reversal of `c`, itself bit reversed. The first stages would then transform it.
+    output
+        .par_chunks_exact_mut(CHUNK)
+        .enumerate()
+        .for_each_init(
+            || vec![Complex::zero(); CHUNK],
+            |subsequence, (c, output)| {
+                let r = c.reverse_bits() >> (usize::BITS - chunk_bits);
+                for (x, &y) in subsequence
+                    .iter_mut()
+                    .zip(input[r..].iter().step_by(chunks))
+                {
+                    *x = y;
+                }
+                simd_cooley_tukey_fft(subsequence, output);
+            },
+        );
+
+    let twiddles: Vec<Complex<T>> = (0..n / 2)
+        .into_par_iter()
+        .map(|j| {
+            let w = Complex::from_polar(1., -std::f64::consts::TAU * j as f64 / n as f64);
+            Complex::new(T::from_f64(w.re).unwrap(), T::from_f64(w.im).unwrap())
+        })
+        .collect();
+
+    let mut m = 2 * CHUNK;
+    while m <= n {
+        let stride = n / m;
+
+        output.par_chunks_exact_mut(m).for_each(|group| {
+            let (lower, upper) = group.split_at_mut(m / 2);
+
+            lower
+                .par_chunks_mut(CHUNK)
+                .zip(upper.par_chunks_mut(CHUNK))
+                .enumerate()
+                .for_each(|(run, (lower, upper))| {
+                    let twiddles = twiddles[run * CHUNK * stride..].iter().step_by(stride);
+                    for ((u, t), &w) in lower.iter_mut().zip(upper).zip(twiddles) {
+                        let wt = w * *t;
+                        *t = *u - wt;
+                        *u = *u + wt;
+                    }
+                });
+        });
+
+        m *= 2;
+    }
+}
+
+#[test]
+fn test_parallel_fft() {
+    use rustfft::FftPlanner;
+
+    use crate::siggen::{self, NoiseColor};
+
+    // Enough for three combining stages
+    let n = CHUNK << 3;
+    let mut re = vec![0.; n];
+    let mut im = vec![0.; n];
+    siggen::noise(NoiseColor::White, 7, &mut re);
+    siggen::noise(NoiseColor::Pink, 8, &mut im);
+    let input: Vec<_> = re
+        .iter()
+        .zip(&im)
+        .map(|(&re, &im)| Complex::new(re as f64, im as f64))
+        .collect();
+
+    let mut expected = input.clone();
+    FftPlanner::new().plan_fft_forward(n).process(&mut expected);
+
+    let mut output = vec![Complex::zero(); n];
+    parallel_fft(&input, &mut output);
+
+    let error = output
+        .iter()
+        .zip(&expected)
+        .map(|(y, x)| (y - x).norm_sqr())
+        .sum::<f64>()
+        .sqrt();
+    let norm = expected.iter().map(|x| x.norm_sqr()).sum::<f64>().sqrt();
+    assert!(error / norm < 1e-13, "relative error {}", error / norm);
+}
+
+#[test]
+#[should_panic(expected = "is_power_of_2(input.len())")]
+fn test_parallel_fft_not_power_of_2() {
+    let input = vec![Complex::new(0f32, 0.); 3 * CHUNK];
+    let mut output = input.clone();
+    parallel_fft(&input, &mut output);
+}
@@ -0,0 +1,320 @@
+use std::sync::Arc;
+
+use rustfft::{num_complex::Complex, num_traits::Zero, Fft, FftPlanner};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    accuracy::FftFn,
+    simd_cooley_tukey3::{simd_cooley_tukey_fft3_complex_with_scratch, Complex4},
+};
+
+/// Transform run by a [`Plan`].
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// rustfft
+    Lib,
+    Dft,
+    CooleyTukey,
+    SimdCooleyTukey,
+    SimdCooleyTukey2,
+    SimdCooleyTukey3,
+    /// The fastest of the crate's own kernels for this machine and build, as picked by
+    /// [`crate::dispatch::kernel`]. [`Algorithm::Lib`] is faster still.
+    Auto,
+}
+
+#[derive(Clone)]
+enum Transform {
+    Lib(Arc<dyn Fft<f32>>),
+    Kernel(FftFn),
+    SimdCooleyTukey3,
+}
+
+/// A transform of one algorithm and size, for real-time threads.
+///
+/// Planning may allocate, as may [`Workspace::new`], which allocates every buffer the plan needs.
+/// [`Plan::process`] then never allocates, so both can be set up ahead of time and the process
+/// calls made from an audio thread. A plan can be shared by several workspaces.
+///
+/// That holds for Rust callers. From JS, wasm-bindgen copies `process`'s slices through the wasm
+/// heap on every call, so use [`Plan::process_shared`] on the workspace's shared buffers instead.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct Plan {
+    algorithm: Algorithm,
+    len: usize,
+    transform: Transform,
+}
+
+/// Buffers for the [`Plan`] it was made for.
+///
+/// The shared input and output buffers let JS fill and read them in place, through
+/// [`Workspace::shared_input_view`] and [`Workspace::shared_output_view`].
+#[wasm_bindgen]
+pub struct Workspace {
+    algorithm: Algorithm,
+    len: usize,
+    shared_input: Vec<f32>,
+    shared_output: Vec<f32>,
+    input: Vec<Complex<f32>>,
+    output: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    scratch_x4: Vec<Complex4>,
+}
+
+#[wasm_bindgen]
+impl Plan {
+    pub fn new(algorithm: Algorithm, len: usize) -> Self {
+        crate::set_panic_hook();
+        assert!(crate::is_power_of_2(len));
+
+        let transform = match algorithm {
+            Algorithm::Lib => Transform::Lib(FftPlanner::new().plan_fft_forward(len)),
+            Algorithm::Dft => Transform::Kernel(crate::dft::dft),
+            Algorithm::CooleyTukey => Transform::Kernel(crate::cooley_tukey::cooley_tukey_fft),
+            Algorithm::SimdCooleyTukey => {
+                Transform::Kernel(crate::simd_cooley_tukey::simd_cooley_tukey_fft)
+            }
+            Algorithm::SimdCooleyTukey2 => {
+                Transform::Kernel(crate::simd_cooley_tukey2::simd_cooley_tukey_fft2)
+            }
+            Algorithm::SimdCooleyTukey3 => Transform::SimdCooleyTukey3,
+            // `simd_cooley_tukey3` allocates its own scratch unless given the workspace's
+            Algorithm::Auto => match crate::dispatch::kernel() {
+                kernel if kernel.algorithm == "simd_cooley_tukey3" => Transform::SimdCooleyTukey3,
+                kernel => Transform::Kernel(kernel.fft),
+            },
+        };
+
+        Self {
+            algorithm,
+            len,
+            transform,
+        }
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    pub fn fft_len(&self) -> usize {
+        self.len
+    }
+
+    /// Transform `fft_len()` real samples, and write `log10 |X|` of the first `fft_len() / 2` bins
+    /// to `output`, in the same units for every algorithm.
+    pub fn process(&self, workspace: &mut Workspace, input: &[f32], output: &mut [f32]) {
+        assert_eq!(input.len(), self.len);
+        assert_eq!(output.len(), self.len / 2);
+
+        // Take the buffers out so they can be passed to `process_complex` with the workspace
+        let mut buffer = std::mem::take(&mut workspace.input);
+        let mut spectrum = std::mem::take(&mut workspace.output);
+
+        for (x, &r) in buffer.iter_mut().zip(input) {
+            *x = Complex::new(r, 0.);
+        }
+
+        self.process_complex(workspace, &buffer, &mut spectrum);
+
+        for (y, x) in output.iter_mut().zip(&spectrum) {
+            *y = x.norm().log10();
+        }
+
+        workspace.input = buffer;
+        workspace.output = spectrum;
+    }
+
+    /// Same as [`Plan::process`], from the workspace's shared input buffer to its shared output
+    /// buffer, without copying across the JS boundary.
+    pub fn process_shared(&self, workspace: &mut Workspace) {
+        let input = std::mem::take(&mut workspace.shared_input);
+        let mut output = std::mem::take(&mut workspace.shared_output);
+
+        self.process(workspace, &input, &mut output);
+
+        workspace.shared_input = input;
+        workspace.shared_output = output;
+    }
+}
+
+impl Plan {
+    /// Complex forward transform of `input` into `output`, both `fft_len()` long.
+    pub fn process_complex(
+        &self,
+        workspace: &mut Workspace,
+        input: &[Complex<f32>],
+        output: &mut [Complex<f32>],
+    ) {
+        assert_eq!(
+            workspace.algorithm, self.algorithm,
+            "workspace is for another plan"
+        );
+        assert_eq!(workspace.len, self.len, "workspace is for another plan");
+        assert_eq!(input.len(), self.len);
+        assert_eq!(output.len(), self.len);
+
+        match &self.transform {
+            Transform::Lib(fft) => {
+                // rustfft uses the input as scratch space
+                output.copy_from_slice(input);
+                fft.process_with_scratch(output, &mut workspace.scratch);
+            }
+            Transform::Kernel(fft) => fft(input, output),
+            Transform::SimdCooleyTukey3 => simd_cooley_tukey_fft3_complex_with_scratch(
+                input,
+                output,
+                &mut workspace.scratch_x4,
+            ),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Workspace {
+    /// Allocate everything [`Plan::process`] needs for `plan`.
+    pub fn new(plan: &Plan) -> Self {
+        let len = plan.len;
+
+        let scratch = match &plan.transform {
+            Transform::Lib(fft) => vec![Complex::zero(); fft.get_inplace_scratch_len()],
+            _ => Vec::new(),
+        };
+        let scratch_x4 = match plan.transform {
+            Transform::SimdCooleyTukey3 => vec![Complex4::zero(); 2 * len],
+            _ => Vec::new(),
+        };
+
+        Self {
+            algorithm: plan.algorithm,
+            len,
+            shared_input: vec![0.; len],
+            shared_output: vec![0.; len / 2],
+            input: vec![Complex::zero(); len],
+            output: vec![Complex::zero(); len],
+            scratch,
+            scratch_x4,
+        }
+    }
+
+    /// Pointer into wasm memory to the `fft_len()` input samples of [`Plan::process_shared`].
+    pub fn shared_input_ptr(&mut self) -> *mut f32 {
+        self.shared_input.as_mut_ptr()
+    }
+
+    /// Pointer into wasm memory to the `fft_len() / 2` output bins of [`Plan::process_shared`].
+    pub fn shared_output_ptr(&self) -> *const f32 {
+        self.shared_output.as_ptr()
+    }
+
+    /// `Float32Array` view directly over the shared input buffer.
+    ///
+    /// The buffers never move, but a view is only valid until wasm memory grows (any allocation
+    /// in the module may do this). A stale view has a `length` of 0, so check it, or simply fetch a
+    /// new view before each use.
+    pub fn shared_input_view(&mut self) -> js_sys::Float32Array {
+        // SAFETY: the view is handed to JS, which must follow the lifetime contract above.
+        unsafe {
+            js_sys::Float32Array::view_mut_raw(self.shared_input_ptr(), self.shared_input.len())
+        }
+    }
+
+    /// `Float32Array` view directly over the shared output buffer.
+    ///
+    /// Same lifetime contract as [`Workspace::shared_input_view`].
+    pub fn shared_output_view(&self) -> js_sys::Float32Array {
+        // SAFETY: the view is handed to JS, which must follow the lifetime contract above.
+        unsafe { js_sys::Float32Array::view(&self.shared_output) }
+    }
+}
+
+#[test]
+fn test_plans_match_rustfft() {
+    use crate::siggen::{self, NoiseColor};
+
+    let len = 512;
+    let mut re = vec![0.; len];
+    let mut im = vec![0.; len];
+    siggen::noise(NoiseColor::White, 5, &mut re);
+    siggen::noise(NoiseColor::White, 6, &mut im);
+    let input: Vec<_> = re
+        .iter()
+        .zip(&im)
+        .map(|(&re, &im)| Complex::new(re, im))
+        .collect();
+
+    let mut expected = input.clone();
+    FftPlanner::new()
+        .plan_fft_forward(len)
+        .process(&mut expected);
+
+    for algorithm in [
+        Algorithm::Lib,
+        Algorithm::Dft,
+        Algorithm::CooleyTukey,
+        Algorithm::SimdCooleyTukey,
+        Algorithm::SimdCooleyTukey2,
+        Algorithm::SimdCooleyTukey3,
+        Algorithm::Auto,
+    ] {
+        let plan = Plan::new(algorithm, len);
+        let mut workspace = Workspace::new(&plan);
+        let mut output = vec![Complex::zero(); len];
+
+        // Twice, in case anything is left over in the workspace. Bins are around 10, and the DFT
+        // is off by about 1e-3.
+        for _ in 0..2 {
+            plan.process_complex(&mut workspace, &input, &mut output);
+            for (y, x) in output.iter().zip(&expected) {
+                approx::assert_abs_diff_eq!(y.re, x.re, epsilon = 1e-2);
+                approx::assert_abs_diff_eq!(y.im, x.im, epsilon = 1e-2);
+            }
+        }
+
+        let mut spectrum = vec![0.; len / 2];
+        plan.process(&mut workspace, &re, &mut spectrum);
+        let mut wasm_fft = vec![0.; len / 2];
+        crate::WasmFft::new().cooley_tukey(&re, &mut wasm_fft);
+        approx::assert_abs_diff_eq!(spectrum.as_slice(), wasm_fft.as_slice(), epsilon = 1e-3);
+    }
+}
+
+#[test]
+fn test_plan_process_shared() {
+    let len = 256;
+    let mut input = vec![0.; len];
+    crate::siggen::noise(crate::siggen::NoiseColor::Pink, 4, &mut input);
+
+    let plan = Plan::new(Algorithm::SimdCooleyTukey3, len);
+    let mut workspace = Workspace::new(&plan);
+    let mut expected = vec![0.; len / 2];
+    plan.process(&mut workspace, &input, &mut expected);
+
+    let ptr = workspace.shared_input_ptr();
+    // SAFETY: the shared input buffer holds `fft_len()` samples.
+    unsafe { std::slice::from_raw_parts_mut(ptr, len) }.copy_from_slice(&input);
+
+    plan.process_shared(&mut workspace);
+
+    let ptr = workspace.shared_output_ptr();
+    // SAFETY: the shared output buffer holds `fft_len() / 2` bins.
+    let output = unsafe { std::slice::from_raw_parts(ptr, len / 2) };
+    assert_eq!(output, expected.as_slice());
+}
+
+#[test]
+#[should_panic(expected = "workspace is for another plan")]
+fn test_workspace_for_other_plan() {
+    let plan = Plan::new(Algorithm::CooleyTukey, 64);
+    let mut workspace = Workspace::new(&Plan::new(Algorithm::CooleyTukey, 128));
+    let mut output = vec![0.; 32];
+    plan.process(&mut workspace, &[0.; 64], &mut output);
+}
+
+#[test]
+#[should_panic(expected = "is_power_of_2(len)")]
+fn test_plan_not_power_of_2() {
+    // rustfft itself would take any size
+    Plan::new(Algorithm::Lib, 1000);
+}
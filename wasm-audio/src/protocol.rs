@@ -0,0 +1,720 @@
+//! Binary request/response protocol for running a [`StreamProcessor`] in a Web Worker.
+//!
+//! The worker only has to pass each message's bytes to [`Dispatcher::handle`] and post back the
+//! bytes it returns:
+//!
+//! ```js
+//! const dispatcher = Dispatcher.new();
+//! onmessage = (e) => {
+//!   const response = dispatcher.handle(new Uint8Array(e.data));
+//!   postMessage(response.buffer, [response.buffer]);
+//! };
+//! ```
+//!
+//! Every message starts with a 4-byte header, a tag byte and 3 zero bytes, followed by its
+//! fields as little-endian 32-bit values, so sample and bin arrays are 4-byte aligned. Arrays are
+//! a `u32` count followed by the `f32` values. The zero bytes are reserved for later versions of
+//! the protocol, and a message with any of them set is rejected.
+//!
+//! | Tag    | Message          | Fields                                                        |
+//! |--------|------------------|---------------------------------------------------------------|
+//! | `0x01` | `Configure`      | `sample_rate: f32, fft_len: u32, hop: u32, algorithm: u32`    |
+//! | `0x02` | `Push`           | `samples: [f32]`                                              |
+//! | `0x03` | `GetSpectrum`    |                                                               |
+//! | `0x04` | `GetFeatures`    |                                                               |
+//! | `0x05` | `Reset`          |                                                               |
+//! | `0x80` | `Ok`             |                                                               |
+//! | `0x82` | `Pushed`         | `frames: u32, updated: u32`                                   |
+//! | `0x83` | `Spectrum`       | `frame: u32, bins: [f32]`                                     |
+//! | `0x84` | `Features`       | `frame: u32, rms, peak, centroid, flatness, dominant: f32`    |
+//! | `0xff` | `Error`          | `code: u32, value: u32, message: u32 length + UTF-8 bytes`    |
+//!
+//! `algorithm` is the [`Algorithm`] discriminant, and `fft_len` a power of two up to
+//! [`MAX_FFT_LEN`]. Until the first full window after a `Configure` or `Reset`, there is no
+//! spectrum, and `GetSpectrum` and `GetFeatures` get a `NoFrame` error. The message of an error is
+//! its `Display` text, for logging; the code and value are enough to decode it.
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Algorithm, StreamProcessor};
+
+/// Largest `fft_len` a `Configure` may ask for, over 20 seconds at 48 kHz. The analyser takes
+/// tens of bytes per point, so much larger sizes could run out of wasm memory, which aborts the
+/// worker instead of returning an error.
+pub const MAX_FFT_LEN: usize = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    /// Replace the analyser with a new one. The sample rate is only used for the features.
+    Configure {
+        sample_rate: f32,
+        fft_len: u32,
+        hop: u32,
+        algorithm: Algorithm,
+    },
+    /// Feed samples to [`StreamProcessor::process`].
+    Push(Vec<f32>),
+    GetSpectrum,
+    GetFeatures,
+    /// [`StreamProcessor::reset`]
+    Reset,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Response {
+    Ok,
+    /// Total frames analysed since the last reset, and whether this push produced any.
+    Pushed {
+        frames: u32,
+        updated: bool,
+    },
+    /// The latest spectrum, in the units of [`StreamProcessor::spectrum`].
+    Spectrum {
+        frame: u32,
+        bins: Vec<f32>,
+    },
+    Features {
+        frame: u32,
+        features: Features,
+    },
+    Error(ProtocolError),
+}
+
+/// Summary of the latest analysed window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Features {
+    /// RMS level of the samples
+    pub rms: f32,
+    /// Largest absolute sample
+    pub peak: f32,
+    /// Magnitude-weighted mean frequency of the spectrum, in Hz
+    pub centroid: f32,
+    /// Geometric over arithmetic mean of the power spectrum: near 1 for noise, near 0 for tones
+    pub flatness: f32,
+    /// Frequency of the strongest bin, in Hz
+    pub dominant: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolError {
+    /// The message ends before its fields do.
+    Truncated,
+    /// The message continues after its fields.
+    TrailingBytes,
+    /// The 3 reserved bytes of the header are not all zero.
+    ReservedBytes,
+    UnknownTag(u8),
+    UnknownAlgorithm(u32),
+    /// `fft_len` is not a power of two or is above [`MAX_FFT_LEN`], `hop` is 0, or the sample rate
+    /// is not positive.
+    InvalidConfig,
+    /// The request needs an analyser, and there has been no `Configure`.
+    NotConfigured,
+    /// The request needs a spectrum, and no window has been analysed since the last `Configure`
+    /// or `Reset`.
+    NoFrame,
+    UnknownErrorCode(u32),
+}
+
+impl ProtocolError {
+    fn code(&self) -> (u32, u32) {
+        match *self {
+            ProtocolError::Truncated => (1, 0),
+            ProtocolError::TrailingBytes => (2, 0),
+            ProtocolError::UnknownTag(tag) => (3, tag as u32),
+            ProtocolError::UnknownAlgorithm(algorithm) => (4, algorithm),
+            ProtocolError::InvalidConfig => (5, 0),
+            ProtocolError::NotConfigured => (6, 0),
+            ProtocolError::UnknownErrorCode(code) => (7, code),
+            ProtocolError::NoFrame => (8, 0),
+            ProtocolError::ReservedBytes => (9, 0),
+        }
+    }
+
+    fn from_code(code: u32, value: u32) -> Self {
+        match code {
+            1 => ProtocolError::Truncated,
+            2 => ProtocolError::TrailingBytes,
+            3 => ProtocolError::UnknownTag(value as u8),
+            4 => ProtocolError::UnknownAlgorithm(value),
+            5 => ProtocolError::InvalidConfig,
+            6 => ProtocolError::NotConfigured,
+            7 => ProtocolError::UnknownErrorCode(value),
+            8 => ProtocolError::NoFrame,
+            9 => ProtocolError::ReservedBytes,
+            _ => ProtocolError::UnknownErrorCode(code),
+        }
+    }
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Truncated => write!(f, "message is truncated"),
+            ProtocolError::TrailingBytes => write!(f, "message has trailing bytes"),
+            ProtocolError::ReservedBytes => write!(f, "reserved header bytes are not zero"),
+            ProtocolError::UnknownTag(tag) => write!(f, "unknown message tag {tag:#04x}"),
+            ProtocolError::UnknownAlgorithm(algorithm) => {
+                write!(f, "unknown algorithm {algorithm}")
+            }
+            ProtocolError::InvalidConfig => write!(f, "invalid analyser configuration"),
+            ProtocolError::NotConfigured => write!(f, "analyser is not configured"),
+            ProtocolError::NoFrame => write!(f, "no window has been analysed yet"),
+            ProtocolError::UnknownErrorCode(code) => write!(f, "unknown error code {code}"),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+fn algorithm_from_u32(algorithm: u32) -> Result<Algorithm, ProtocolError> {
+    [
+        Algorithm::Lib,
+        Algorithm::Dft,
+        Algorithm::CooleyTukey,
+        Algorithm::SimdCooleyTukey,
+        Algorithm::SimdCooleyTukey2,
+        Algorithm::SimdCooleyTukey3,
+        Algorithm::Auto,
+    ]
+    .into_iter()
+    .find(|&a| a as u32 == algorithm)
+    .ok_or(ProtocolError::UnknownAlgorithm(algorithm))
+}
+
+/// Builds a message, header first.
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn new(tag: u8) -> Self {
+        Writer(vec![tag, 0, 0, 0])
+    }
+
+    fn u32(&mut self, x: u32) -> &mut Self {
+        self.0.extend_from_slice(&x.to_le_bytes());
+        self
+    }
+
+    fn f32(&mut self, x: f32) -> &mut Self {
+        self.0.extend_from_slice(&x.to_le_bytes());
+        self
+    }
+
+    fn f32s(&mut self, xs: &[f32]) -> &mut Self {
+        self.u32(xs.len() as u32);
+        for &x in xs {
+            self.f32(x);
+        }
+        self
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.0.extend_from_slice(bytes);
+        self
+    }
+}
+
+/// Reads the fields of a message, after its header.
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn header(bytes: &[u8]) -> Result<(u8, Reader<'_>), ProtocolError> {
+        match bytes {
+            [tag, 0, 0, 0, rest @ ..] => Ok((*tag, Reader(rest))),
+            [_, _, _, _, ..] => Err(ProtocolError::ReservedBytes),
+            _ => Err(ProtocolError::Truncated),
+        }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&[u8], ProtocolError> {
+        if self.0.len() < len {
+            return Err(ProtocolError::Truncated);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, ProtocolError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, ProtocolError> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    fn f32s(&mut self) -> Result<Vec<f32>, ProtocolError> {
+        let len = self.u32()? as usize;
+        let bytes = self.bytes(len.checked_mul(4).ok_or(ProtocolError::Truncated)?)?;
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|x| f32::from_le_bytes(x.try_into().unwrap()))
+            .collect())
+    }
+
+    fn finish(&self) -> Result<(), ProtocolError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(ProtocolError::TrailingBytes)
+        }
+    }
+}
+
+impl Request {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w;
+        match self {
+            Request::Configure {
+                sample_rate,
+                fft_len,
+                hop,
+                algorithm,
+            } => {
+                w = Writer::new(0x01);
+                w.f32(*sample_rate)
+                    .u32(*fft_len)
+                    .u32(*hop)
+                    .u32(*algorithm as u32);
+            }
+            Request::Push(samples) => {
+                w = Writer::new(0x02);
+                w.f32s(samples);
+            }
+            Request::GetSpectrum => w = Writer::new(0x03),
+            Request::GetFeatures => w = Writer::new(0x04),
+            Request::Reset => w = Writer::new(0x05),
+        }
+        w.0
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        let (tag, mut r) = Reader::header(bytes)?;
+        let request = match tag {
+            0x01 => Request::Configure {
+                sample_rate: r.f32()?,
+                fft_len: r.u32()?,
+                hop: r.u32()?,
+                algorithm: algorithm_from_u32(r.u32()?)?,
+            },
+            0x02 => Request::Push(r.f32s()?),
+            0x03 => Request::GetSpectrum,
+            0x04 => Request::GetFeatures,
+            0x05 => Request::Reset,
+            _ => return Err(ProtocolError::UnknownTag(tag)),
+        };
+        r.finish()?;
+        Ok(request)
+    }
+}
+
+impl Response {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w;
+        match self {
+            Response::Ok => w = Writer::new(0x80),
+            Response::Pushed { frames, updated } => {
+                w = Writer::new(0x82);
+                w.u32(*frames).u32(*updated as u32);
+            }
+            Response::Spectrum { frame, bins } => {
+                w = Writer::new(0x83);
+                w.u32(*frame).f32s(bins);
+            }
+            Response::Features { frame, features } => {
+                w = Writer::new(0x84);
+                w.u32(*frame)
+                    .f32(features.rms)
+                    .f32(features.peak)
+                    .f32(features.centroid)
+                    .f32(features.flatness)
+                    .f32(features.dominant);
+            }
+            Response::Error(error) => {
+                let (code, value) = error.code();
+                let message = error.to_string();
+                w = Writer::new(0xff);
+                w.u32(code)
+                    .u32(value)
+                    .u32(message.len() as u32)
+                    .bytes(message.as_bytes());
+            }
+        }
+        w.0
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        let (tag, mut r) = Reader::header(bytes)?;
+        let response = match tag {
+            0x80 => Response::Ok,
+            0x82 => Response::Pushed {
+                frames: r.u32()?,
+                updated: r.u32()? != 0,
+            },
+            0x83 => Response::Spectrum {
+                frame: r.u32()?,
+                bins: r.f32s()?,
+            },
+            0x84 => Response::Features {
+                frame: r.u32()?,
+                features: Features {
+                    rms: r.f32()?,
+                    peak: r.f32()?,
+                    centroid: r.f32()?,
+                    flatness: r.f32()?,
+                    dominant: r.f32()?,
+                },
+            },
+            0xff => {
+                let (code, value) = (r.u32()?, r.u32()?);
+                let len = r.u32()? as usize;
+                r.bytes(len)?;
+                Response::Error(ProtocolError::from_code(code, value))
+            }
+            _ => return Err(ProtocolError::UnknownTag(tag)),
+        };
+        r.finish()?;
+        Ok(response)
+    }
+}
+
+/// Handles [`Request`]s for one analyser.
+#[wasm_bindgen]
+pub struct Dispatcher {
+    sample_rate: f32,
+    stream: Option<StreamProcessor>,
+    spectrum: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl Dispatcher {
+    pub fn new() -> Self {
+        crate::set_panic_hook();
+
+        Self {
+            sample_rate: 0.,
+            stream: None,
+            spectrum: Vec::new(),
+        }
+    }
+
+    /// Decode a request, run it, and return the encoded response. Malformed requests get an
+    /// `Error` response rather than a panic.
+    pub fn handle(&mut self, request: &[u8]) -> Vec<u8> {
+        let response = match Request::decode(request) {
+            Ok(request) => self.handle_request(request),
+            Err(error) => Response::Error(error),
+        };
+        response.encode()
+    }
+}
+
+impl Dispatcher {
+    pub fn handle_request(&mut self, request: Request) -> Response {
+        match self.try_handle(request) {
+            Ok(response) => response,
+            Err(error) => Response::Error(error),
+        }
+    }
+
+    fn try_handle(&mut self, request: Request) -> Result<Response, ProtocolError> {
+        if let Request::Configure {
+            sample_rate,
+            fft_len,
+            hop,
+            algorithm,
+        } = request
+        {
+            let fft_len = fft_len as usize;
+            // `>` so NaN is rejected too
+            if !(crate::is_power_of_2(fft_len)
+                && fft_len <= MAX_FFT_LEN
+                && hop > 0
+                && sample_rate > 0.)
+            {
+                return Err(ProtocolError::InvalidConfig);
+            }
+
+            self.sample_rate = sample_rate;
+            self.stream = Some(StreamProcessor::with_algorithm(
+                fft_len,
+                hop as usize,
+                algorithm,
+            ));
+            self.spectrum = vec![0.; fft_len / 2];
+            return Ok(Response::Ok);
+        }
+
+        let stream = self.stream.as_mut().ok_or(ProtocolError::NotConfigured)?;
+        if matches!(request, Request::GetSpectrum | Request::GetFeatures) && stream.frames() == 0 {
+            // The spectrum is still all zeros, which would read as a flat magnitude of 1
+            return Err(ProtocolError::NoFrame);
+        }
+
+        Ok(match request {
+            Request::Configure { .. } => unreachable!(),
+            Request::Push(samples) => Response::Pushed {
+                updated: stream.process(&samples),
+                frames: stream.frames(),
+            },
+            Request::GetSpectrum => {
+                stream.spectrum(&mut self.spectrum);
+                Response::Spectrum {
+                    frame: stream.frames(),
+                    bins: self.spectrum.clone(),
+                }
+            }
+            Request::GetFeatures => {
+                stream.spectrum(&mut self.spectrum);
+                Response::Features {
+                    frame: stream.frames(),
+                    features: features(stream, &self.spectrum, self.sample_rate),
+                }
+            }
+            Request::Reset => {
+                stream.reset();
+                Response::Ok
+            }
+        })
+    }
+}
+
+impl Default for Dispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `spectrum` is the latest spectrum of `stream`, as `log10` magnitudes.
+fn features(stream: &StreamProcessor, spectrum: &[f32], sample_rate: f32) -> Features {
+    let bin_hz = sample_rate / stream.fft_len() as f32;
+
+    let mut sum = 0.;
+    let mut weighted = 0.;
+    let mut power = 0.;
+    let mut log_power = 0.;
+    let mut dominant = 0;
+
+    for (k, &y) in spectrum.iter().enumerate() {
+        let magnitude = 10f32.powf(y);
+        sum += magnitude;
+        weighted += magnitude * k as f32;
+        power += magnitude * magnitude;
+        // ln of the power, from log10 of the magnitude
+        log_power += 2. * std::f32::consts::LN_10 * y;
+        if y > spectrum[dominant] {
+            dominant = k;
+        }
+    }
+
+    let n = spectrum.len() as f32;
+    let (centroid, flatness) = if power > 0. {
+        (weighted / sum * bin_hz, (log_power / n).exp() / (power / n))
+    } else {
+        (0., 0.)
+    };
+
+    Features {
+        rms: stream.rms(),
+        peak: stream.peak(),
+        centroid,
+        flatness,
+        dominant: dominant as f32 * bin_hz,
+    }
+}
+
+#[test]
+fn test_request_round_trip() {
+    let requests = [
+        Request::Configure {
+            sample_rate: 48000.,
+            fft_len: 1024,
+            hop: 256,
+            algorithm: Algorithm::SimdCooleyTukey3,
+        },
+        Request::Push(vec![0.25, -1., 0.5]),
+        Request::Push(Vec::new()),
+        Request::GetSpectrum,
+        Request::GetFeatures,
+        Request::Reset,
+    ];
+
+    for request in requests {
+        let bytes = request.encode();
+        assert_eq!(bytes.len() % 4, 0);
+        assert_eq!(Request::decode(&bytes), Ok(request));
+    }
+}
+
+#[test]
+fn test_response_round_trip() {
+    let responses = [
+        Response::Ok,
+        Response::Pushed {
+            frames: 3,
+            updated: true,
+        },
+        Response::Spectrum {
+            frame: 7,
+            bins: vec![-1.5, 0., 2.25],
+        },
+        Response::Features {
+            frame: 2,
+            features: Features {
+                rms: 0.5,
+                peak: 0.9,
+                centroid: 1000.,
+                flatness: 0.1,
+                dominant: 440.,
+            },
+        },
+        Response::Error(ProtocolError::UnknownTag(0x42)),
+        Response::Error(ProtocolError::NotConfigured),
+        Response::Error(ProtocolError::NoFrame),
+        Response::Error(ProtocolError::ReservedBytes),
+    ];
+
+    for response in responses {
+        assert_eq!(Response::decode(&response.encode()), Ok(response));
+    }
+}
+
+#[test]
+fn test_malformed_requests() {
+    let push = Request::Push(vec![1., 2.]).encode();
+
+    assert_eq!(Request::decode(&[]), Err(ProtocolError::Truncated));
+    assert_eq!(
+        Request::decode(&push[..push.len() - 1]),
+        Err(ProtocolError::Truncated)
+    );
+    assert_eq!(
+        Request::decode(&[push.as_slice(), &[0; 4]].concat()),
+        Err(ProtocolError::TrailingBytes)
+    );
+    assert_eq!(
+        Request::decode(&[0x42, 0, 0, 0]),
+        Err(ProtocolError::UnknownTag(0x42))
+    );
+    for i in 1..4 {
+        let mut reset = Request::Reset.encode();
+        reset[i] = 1;
+        assert_eq!(Request::decode(&reset), Err(ProtocolError::ReservedBytes));
+    }
+
+    let mut configure = Request::Configure {
+        sample_rate: 48000.,
+        fft_len: 1024,
+        hop: 256,
+        algorithm: Algorithm::Lib,
+    }
+    .encode();
+    configure[16..].copy_from_slice(&99u32.to_le_bytes());
+    assert_eq!(
+        Request::decode(&configure),
+        Err(ProtocolError::UnknownAlgorithm(99))
+    );
+
+    // A huge count must not overflow or allocate
+    let mut huge = vec![0x02, 0, 0, 0];
+    huge.extend_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(Request::decode(&huge), Err(ProtocolError::Truncated));
+}
+
+#[test]
+fn test_dispatcher() {
+    use crate::siggen;
+
+    let mut dispatcher = Dispatcher::new();
+    let mut handle = |request: Request| Response::decode(&dispatcher.handle(&request.encode()));
+
+    assert_eq!(
+        handle(Request::GetSpectrum),
+        Ok(Response::Error(ProtocolError::NotConfigured))
+    );
+    assert_eq!(
+        handle(Request::Configure {
+            sample_rate: 8000.,
+            fft_len: 1000,
+            hop: 256,
+            algorithm: Algorithm::Lib,
+        }),
+        Ok(Response::Error(ProtocolError::InvalidConfig))
+    );
+    assert_eq!(
+        handle(Request::Configure {
+            sample_rate: 8000.,
+            fft_len: 1 << 30,
+            hop: 256,
+            algorithm: Algorithm::Lib,
+        }),
+        Ok(Response::Error(ProtocolError::InvalidConfig))
+    );
+
+    let sample_rate = 8000.;
+    let fft_len = 512;
+    assert_eq!(
+        handle(Request::Configure {
+            sample_rate,
+            fft_len: fft_len as u32,
+            hop: 256,
+            algorithm: Algorithm::SimdCooleyTukey,
+        }),
+        Ok(Response::Ok)
+    );
+    for request in [Request::GetSpectrum, Request::GetFeatures] {
+        assert_eq!(handle(request), Ok(Response::Error(ProtocolError::NoFrame)));
+    }
+
+    // A tone exactly on bin 64
+    let freq = 64. * sample_rate / fft_len as f32;
+    let mut signal = vec![0.; 1024];
+    siggen::multitone(&[freq], sample_rate, &mut signal);
+
+    let mut frames = 0;
+    for block in signal.chunks(128) {
+        match handle(Request::Push(block.to_vec())) {
+            Ok(Response::Pushed { frames: f, .. }) => frames = f,
+            other => panic!("{other:?}"),
+        }
+    }
+    assert_eq!(frames, 3);
+
+    let mut expected = StreamProcessor::with_algorithm(fft_len, 256, Algorithm::SimdCooleyTukey);
+    for block in signal.chunks(128) {
+        expected.process(block);
+    }
+    let mut bins = vec![0.; fft_len / 2];
+    expected.spectrum(&mut bins);
+    assert_eq!(
+        handle(Request::GetSpectrum),
+        Ok(Response::Spectrum { frame: 3, bins })
+    );
+
+    match handle(Request::GetFeatures) {
+        Ok(Response::Features { frame, features }) => {
+            assert_eq!(frame, 3);
+            approx::assert_abs_diff_eq!(features.dominant, freq);
+            approx::assert_abs_diff_eq!(features.centroid, freq, epsilon = 1.);
+            approx::assert_abs_diff_eq!(features.rms, expected.rms());
+            approx::assert_abs_diff_eq!(features.peak, expected.peak());
+            assert!(features.flatness < 1e-3, "{features:?}");
+        }
+        other => panic!("{other:?}"),
+    }
+
+    assert_eq!(handle(Request::Reset), Ok(Response::Ok));
+    assert_eq!(
+        handle(Request::Push(vec![0.; 128])),
+        Ok(Response::Pushed {
+            frames: 0,
+            updated: false
+        })
+    );
+    assert_eq!(
+        handle(Request::GetSpectrum),
+        Ok(Response::Error(ProtocolError::NoFrame))
+    );
+}
@@ -0,0 +1,393 @@
+//! Test signal generators.
+//!
+//! All generators fill a caller-provided buffer. Oscillators are band-limited with PolyBLEP
+//! (square, sawtooth) and PolyBLAMP (triangle) corrections, so their spectra are close to the
+//! ideal Fourier series up to Nyquist.
+
+use core::f32::consts::PI;
+
+use wasm_bindgen::prelude::*;
+
+const TWO_PI: f32 = 2.0 * PI;
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Triangle,
+    Sawtooth,
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseColor {
+    White,
+    Pink,
+    Brown,
+}
+
+/// Band-limited periodic oscillator. Starts at phase 0 (a rising zero crossing for sine).
+#[derive(Debug, Clone)]
+pub struct Oscillator {
+    waveform: Waveform,
+    phase: f32,
+    dt: f32,
+}
+
+impl Oscillator {
+    pub fn new(waveform: Waveform, freq: f32, sample_rate: f32) -> Self {
+        assert!(freq >= 0. && freq < sample_rate / 2.);
+
+        Self {
+            waveform,
+            phase: 0.,
+            dt: freq / sample_rate,
+        }
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        let t = self.phase;
+        let dt = self.dt;
+
+        let y = match self.waveform {
+            Waveform::Sine => (TWO_PI * t).sin(),
+            Waveform::Square => {
+                let naive = if t < 0.5 { 1. } else { -1. };
+                naive + poly_blep(t, dt) - poly_blep((t + 0.5) % 1., dt)
+            }
+            Waveform::Triangle => {
+                // Peak at phase 0, trough at phase 0.5; slope changes by 8 per cycle at each
+                let naive = 4. * (t - 0.5).abs() - 1.;
+                naive + 8. * dt * (poly_blamp((t + 0.5) % 1., dt) - poly_blamp(t, dt))
+            }
+            Waveform::Sawtooth => 2. * t - 1. - poly_blep(t, dt),
+        };
+
+        self.phase += dt;
+        if self.phase >= 1. {
+            self.phase -= 1.;
+        }
+
+        y
+    }
+
+    pub fn fill(&mut self, output: &mut [f32]) {
+        output.iter_mut().for_each(|y| *y = self.next_sample());
+    }
+}
+
+/// Correction for a step of height 2 at phase 0, spread over one sample either side.
+fn poly_blep(t: f32, dt: f32) -> f32 {
+    if t < dt {
+        let t = t / dt;
+        2. * t - t * t - 1.
+    } else if t > 1. - dt {
+        let t = (t - 1.) / dt;
+        t * t + 2. * t + 1.
+    } else {
+        0.
+    }
+}
+
+/// Correction for a slope increase of 1 per sample at phase 0 (the integral of PolyBLEP).
+fn poly_blamp(t: f32, dt: f32) -> f32 {
+    let d = if t < dt {
+        1. - t / dt
+    } else if t > 1. - dt {
+        1. + (t - 1.) / dt
+    } else {
+        return 0.;
+    };
+
+    d * d * d / 6.
+}
+
+/// Uniform, pink (-3 dB/octave) or brown (-6 dB/octave) noise from a seeded xorshift PRNG.
+#[derive(Debug, Clone)]
+pub struct Noise {
+    color: NoiseColor,
+    state: u32,
+    filter: [f32; 7],
+}
+
+impl Noise {
+    pub fn new(color: NoiseColor, seed: u32) -> Self {
+        Self {
+            color,
+            // xorshift has a fixed point at 0
+            state: seed.max(1),
+            filter: [0.; 7],
+        }
+    }
+
+    fn next_white(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+
+        (self.state >> 8) as f32 / (1 << 23) as f32 - 1.
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        let white = self.next_white();
+        let b = &mut self.filter;
+
+        match self.color {
+            NoiseColor::White => white,
+            NoiseColor::Pink => {
+                // Paul Kellet's refined pink noise filter
+                b[0] = 0.99886 * b[0] + white * 0.0555179;
+                b[1] = 0.99332 * b[1] + white * 0.0750759;
+                b[2] = 0.96900 * b[2] + white * 0.153852;
+                b[3] = 0.86650 * b[3] + white * 0.3104856;
+                b[4] = 0.55000 * b[4] + white * 0.5329522;
+                b[5] = -0.7616 * b[5] - white * 0.0168980;
+                let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+                b[6] = white * 0.115926;
+                pink * 0.11
+            }
+            NoiseColor::Brown => {
+                // Leaky integrator, so the output stays bounded
+                b[0] = (b[0] + 0.02 * white) / 1.02;
+                b[0] * 3.5
+            }
+        }
+    }
+
+    pub fn fill(&mut self, output: &mut [f32]) {
+        output.iter_mut().for_each(|y| *y = self.next_sample());
+    }
+}
+
+pub fn oscillator(waveform: Waveform, freq: f32, sample_rate: f32, output: &mut [f32]) {
+    Oscillator::new(waveform, freq, sample_rate).fill(output);
+}
+
+pub fn noise(color: NoiseColor, seed: u32, output: &mut [f32]) {
+    Noise::new(color, seed).fill(output);
+}
+
+/// Unit impulse at `position`, zero elsewhere.
+pub fn impulse(position: usize, output: &mut [f32]) {
+    output.iter_mut().for_each(|y| *y = 0.);
+    output[position] = 1.;
+}
+
+/// Sine sweep from `f0` to `f1` over the length of `output`, with frequency changing linearly.
+pub fn linear_sweep(f0: f32, f1: f32, sample_rate: f32, output: &mut [f32]) {
+    let duration = output.len() as f64 / sample_rate as f64;
+    let rate = (f1 - f0) as f64 / duration;
+
+    for (i, y) in output.iter_mut().enumerate() {
+        let t = i as f64 / sample_rate as f64;
+        let phase = f0 as f64 * t + 0.5 * rate * t * t;
+        *y = (std::f64::consts::TAU * phase.fract()).sin() as f32;
+    }
+}
+
+/// Sine sweep from `f0` to `f1` over the length of `output`, with frequency changing
+/// exponentially (equal time per octave).
+pub fn log_sweep(f0: f32, f1: f32, sample_rate: f32, output: &mut [f32]) {
+    assert!(f0 > 0. && f1 > 0.);
+
+    let duration = output.len() as f64 / sample_rate as f64;
+    let k = (f1 as f64 / f0 as f64).ln() / duration;
+
+    for (i, y) in output.iter_mut().enumerate() {
+        let t = i as f64 / sample_rate as f64;
+        let phase = if k == 0. {
+            f0 as f64 * t
+        } else {
+            f0 as f64 / k * ((k * t).exp() - 1.)
+        };
+        *y = (std::f64::consts::TAU * phase.fract()).sin() as f32;
+    }
+}
+
+/// Sum of equal-amplitude sines, scaled so the peak cannot exceed 1.
+pub fn multitone(freqs: &[f32], sample_rate: f32, output: &mut [f32]) {
+    let scale = 1. / freqs.len() as f32;
+
+    output.iter_mut().for_each(|y| *y = 0.);
+
+    for &freq in freqs {
+        let mut osc = Oscillator::new(Waveform::Sine, freq, sample_rate);
+        output
+            .iter_mut()
+            .for_each(|y| *y += scale * osc.next_sample());
+    }
+}
+
+/// Row and column frequencies of a DTMF keypad digit (`0-9`, `*`, `#`, `A-D`).
+pub fn dtmf_frequencies(digit: char) -> Option<(f32, f32)> {
+    const ROWS: [f32; 4] = [697., 770., 852., 941.];
+    const COLS: [f32; 4] = [1209., 1336., 1477., 1633.];
+    const KEYS: [[char; 4]; 4] = [
+        ['1', '2', '3', 'A'],
+        ['4', '5', '6', 'B'],
+        ['7', '8', '9', 'C'],
+        ['*', '0', '#', 'D'],
+    ];
+
+    let digit = digit.to_ascii_uppercase();
+
+    KEYS.iter().enumerate().find_map(|(r, row)| {
+        row.iter()
+            .position(|&key| key == digit)
+            .map(|c| (ROWS[r], COLS[c]))
+    })
+}
+
+pub fn dtmf(digit: char, sample_rate: f32, output: &mut [f32]) {
+    let (row, col) = dtmf_frequencies(digit).expect("invalid DTMF digit");
+    multitone(&[row, col], sample_rate, output);
+}
+
+#[cfg(test)]
+fn magnitude_spectrum(signal: &[f32]) -> Vec<f32> {
+    use rustfft::{num_complex::Complex, FftPlanner};
+
+    let mut buffer: Vec<_> = signal.iter().map(|&x| Complex::new(x, 0.)).collect();
+    FftPlanner::new()
+        .plan_fft_forward(buffer.len())
+        .process(&mut buffer);
+
+    let scale = 2. / signal.len() as f32;
+    buffer[..signal.len() / 2]
+        .iter()
+        .map(|x| x.norm() * scale)
+        .collect()
+}
+
+#[test]
+fn test_oscillator_harmonics() {
+    use approx::assert_abs_diff_eq;
+
+    // Fundamental on bin 16 of a 1024-point FFT
+    let n = 1024;
+    let mut signal = vec![0.; n];
+
+    oscillator(Waveform::Sine, 16., n as f32, &mut signal);
+    let spectrum = magnitude_spectrum(&signal);
+    assert_abs_diff_eq!(spectrum[16], 1., epsilon = 1e-3);
+    assert_abs_diff_eq!(spectrum[32], 0., epsilon = 1e-3);
+
+    // Fourier series: square 4/(πk), sawtooth 2/(πk), triangle 8/(πk)², odd k only for the
+    // square and triangle
+    oscillator(Waveform::Square, 16., n as f32, &mut signal);
+    let spectrum = magnitude_spectrum(&signal);
+    for k in [1, 3, 5] {
+        assert_abs_diff_eq!(spectrum[16 * k], 4. / (PI * k as f32), epsilon = 0.01);
+        assert_abs_diff_eq!(spectrum[16 * (k + 1)], 0., epsilon = 0.01);
+    }
+
+    oscillator(Waveform::Sawtooth, 16., n as f32, &mut signal);
+    let spectrum = magnitude_spectrum(&signal);
+    for k in [1, 2, 3, 4] {
+        assert_abs_diff_eq!(spectrum[16 * k], 2. / (PI * k as f32), epsilon = 0.01);
+    }
+
+    oscillator(Waveform::Triangle, 16., n as f32, &mut signal);
+    let spectrum = magnitude_spectrum(&signal);
+    for k in [1, 3, 5] {
+        let expected = 8. / (PI * k as f32).powi(2);
+        assert_abs_diff_eq!(spectrum[16 * k], expected, epsilon = 0.01);
+    }
+}
+
+#[test]
+fn test_oscillator_band_limited() {
+    // Harmonics of a naive sawtooth at a non-integer period alias into every bin. With PolyBLEP
+    // the energy between harmonics is much smaller.
+    let n = 4096;
+    let sample_rate = 48000.;
+    let freq = 2345.;
+    let mut signal = vec![0.; n];
+    oscillator(Waveform::Sawtooth, freq, sample_rate, &mut signal);
+
+    let naive: Vec<f32> = (0..n)
+        .map(|i| 2. * (i as f32 * freq / sample_rate).fract() - 1.)
+        .collect();
+
+    // Bins just below the fundamental, away from any harmonic
+    let bin = (freq / sample_rate * n as f32) as usize;
+    let floor = |s: &[f32]| magnitude_spectrum(s)[bin / 4..bin / 2].iter().sum::<f32>();
+
+    assert!(floor(&signal) < 0.5 * floor(&naive));
+}
+
+#[test]
+fn test_noise_spectra() {
+    // Energy per octave is flat for pink noise, so higher octaves have less energy per bin
+    let n = 1 << 16;
+    let mut signal = vec![0.; n];
+
+    let octave_energy = |s: &[f32], lo: usize| -> f32 {
+        magnitude_spectrum(s)[lo..2 * lo]
+            .iter()
+            .map(|x| x * x)
+            .sum()
+    };
+
+    noise(NoiseColor::White, 1234, &mut signal);
+    assert!(signal.iter().all(|x| (-1.0..1.0).contains(x)));
+    let white_ratio = octave_energy(&signal, 8192) / octave_energy(&signal, 1024);
+    assert!((6.0..10.0).contains(&white_ratio));
+
+    noise(NoiseColor::Pink, 1234, &mut signal);
+    let pink_ratio = octave_energy(&signal, 8192) / octave_energy(&signal, 1024);
+    assert!((0.7..1.4).contains(&pink_ratio));
+
+    noise(NoiseColor::Brown, 1234, &mut signal);
+    let brown_ratio = octave_energy(&signal, 8192) / octave_energy(&signal, 1024);
+    assert!(brown_ratio < 0.25);
+}
+
+#[test]
+fn test_sweeps() {
+    let sample_rate = 8000.;
+    let mut signal = vec![0.; 8000];
+
+    // Zero crossings per 1000-sample window grow linearly from 100 Hz to 1900 Hz
+    let crossings = |s: &[f32]| s.windows(2).filter(|w| w[0] < 0. && w[1] >= 0.).count();
+
+    linear_sweep(100., 1900., sample_rate, &mut signal);
+    let counts: Vec<_> = signal.chunks(1000).map(crossings).collect();
+    for (i, &c) in counts.iter().enumerate() {
+        let expected = (100. + 1800. * (i as f32 + 0.5) / 8.) / 8.;
+        assert!((c as f32 - expected).abs() <= 2., "{counts:?}");
+    }
+
+    // 100 Hz to 1600 Hz is 4 octaves, so each 2000-sample window spans one octave
+    log_sweep(100., 1600., sample_rate, &mut signal);
+    let counts: Vec<_> = signal.chunks(2000).map(crossings).collect();
+    for (i, &c) in counts.iter().enumerate() {
+        // Cycles in an octave starting at f over 0.25 s: f * 0.25 / ln(2)
+        let expected = 100. * 2_f32.powi(i as i32) * 0.25 / 2_f32.ln();
+        assert!((c as f32 - expected).abs() <= 2., "{counts:?}");
+    }
+}
+
+#[test]
+fn test_dtmf() {
+    assert_eq!(dtmf_frequencies('5'), Some((770., 1336.)));
+    assert_eq!(dtmf_frequencies('d'), Some((941., 1633.)));
+    assert_eq!(dtmf_frequencies('x'), None);
+
+    // 1 Hz resolution
+    let sample_rate = 8192.;
+    let mut signal = vec![0.; 8192];
+    dtmf('#', sample_rate, &mut signal);
+
+    let spectrum = magnitude_spectrum(&signal);
+    assert!(spectrum[941] > 0.49 && spectrum[1477] > 0.49);
+    assert!(spectrum[697] < 0.01 && spectrum[1209] < 0.01);
+}
+
+#[test]
+fn test_impulse() {
+    let mut signal = vec![1.; 8];
+    impulse(3, &mut signal);
+    assert_eq!(signal, [0., 0., 0., 1., 0., 0., 0., 0.]);
+}
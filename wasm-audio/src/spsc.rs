@@ -0,0 +1,450 @@
+//! Lock-free single-producer/single-consumer ring buffer of `f32` samples.
+//!
+//! The buffer is a [`SpscHeader`] followed directly by `capacity` samples. It can either be
+//! allocated with [`spsc_ring_buf`], or placed in memory shared between threads (e.g. a wasm
+//! `Memory` backed by a `SharedArrayBuffer`) with [`init_region`], [`Producer::from_raw`] and
+//! [`Consumer::from_raw`].
+
+#[cfg(loom)]
+use loom::{
+    cell::UnsafeCell,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+#[cfg(not(loom))]
+use std::{
+    cell::UnsafeCell,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
+
+/// Indices and counters at the start of a ring buffer region.
+///
+/// `write` and `read` count samples since initialisation and wrap at `u32::MAX`, which is why the
+/// capacity must be a power of 2, and at most half of `u32::MAX`.
+#[repr(C)]
+pub struct SpscHeader {
+    write: AtomicU32,
+    read: AtomicU32,
+    overruns: AtomicU32,
+    underruns: AtomicU32,
+}
+
+impl SpscHeader {
+    fn new() -> Self {
+        Self {
+            write: AtomicU32::new(0),
+            read: AtomicU32::new(0),
+            overruns: AtomicU32::new(0),
+            underruns: AtomicU32::new(0),
+        }
+    }
+}
+
+/// Size in bytes of a region holding `capacity` samples.
+pub fn region_size(capacity: usize) -> usize {
+    std::mem::size_of::<SpscHeader>() + capacity * std::mem::size_of::<f32>()
+}
+
+/// Reset the header of a region so it can be shared by one producer and one consumer.
+///
+/// # Safety
+/// `ptr` must be 4-byte aligned and valid for [`region_size`]`(capacity)` bytes, and no producer
+/// or consumer may be using the region during this call.
+#[cfg(not(loom))]
+pub unsafe fn init_region(ptr: *mut u8, capacity: usize) {
+    assert!(crate::is_power_of_2(capacity));
+    assert!(capacity <= u32::MAX as usize / 2);
+    (ptr as *mut SpscHeader).write(SpscHeader::new());
+}
+
+/// Allocate a ring buffer holding `capacity` samples and split it into its two ends.
+pub fn spsc_ring_buf(capacity: usize) -> (Producer, Consumer) {
+    assert!(crate::is_power_of_2(capacity));
+    assert!(capacity <= u32::MAX as usize / 2);
+
+    let region = Arc::new(OwnedRegion {
+        header: SpscHeader::new(),
+        data: (0..capacity).map(|_| UnsafeCell::new(0.)).collect(),
+    });
+    let raw = RawRing {
+        header: &region.header,
+        data: region.data.as_ptr() as *mut Sample,
+        capacity: capacity as u32,
+    };
+
+    (
+        Producer {
+            raw,
+            _owner: Some(region.clone()),
+        },
+        Consumer {
+            raw,
+            _owner: Some(region),
+        },
+    )
+}
+
+/// Storage of one sample: plain `f32` in shared regions, but loom's `UnsafeCell` under loom, so
+/// the model checker sees every access to the samples and can report races on them.
+#[cfg(not(loom))]
+type Sample = f32;
+#[cfg(loom)]
+type Sample = UnsafeCell<f32>;
+
+struct OwnedRegion {
+    header: SpscHeader,
+    data: Box<[UnsafeCell<f32>]>,
+}
+
+// SAFETY: the data cells are only accessed through one `Producer` and one `Consumer`, which
+// synchronise through the header atomics.
+unsafe impl Send for OwnedRegion {}
+unsafe impl Sync for OwnedRegion {}
+
+#[derive(Clone, Copy)]
+struct RawRing {
+    header: *const SpscHeader,
+    data: *mut Sample,
+    capacity: u32,
+}
+
+impl RawRing {
+    /// # Safety
+    /// See [`init_region`].
+    #[cfg(not(loom))]
+    unsafe fn from_raw(ptr: *mut u8, capacity: usize) -> Self {
+        assert!(crate::is_power_of_2(capacity));
+        assert!(capacity <= u32::MAX as usize / 2);
+        assert_eq!(ptr as usize % std::mem::align_of::<SpscHeader>(), 0);
+
+        Self {
+            header: ptr as *const SpscHeader,
+            data: ptr.add(std::mem::size_of::<SpscHeader>()) as *mut f32,
+            capacity: capacity as u32,
+        }
+    }
+
+    fn header(&self) -> &SpscHeader {
+        // SAFETY: the header outlives both ends (owned by `_owner`, or guaranteed by the caller
+        // of `from_raw`).
+        unsafe { &*self.header }
+    }
+
+    fn mask(&self) -> u32 {
+        self.capacity - 1
+    }
+
+    /// # Safety
+    /// `idx` must be in bounds and in the free part of the ring.
+    unsafe fn write(&self, idx: u32, x: f32) {
+        #[cfg(not(loom))]
+        self.data.add(idx as usize).write(x);
+        #[cfg(loom)]
+        (*self.data.add(idx as usize)).with_mut(|sample| sample.write(x));
+    }
+
+    /// # Safety
+    /// `idx` must be in bounds and in the filled part of the ring.
+    unsafe fn read(&self, idx: u32) -> f32 {
+        #[cfg(not(loom))]
+        return self.data.add(idx as usize).read();
+        #[cfg(loom)]
+        return (*self.data.add(idx as usize)).with(|sample| sample.read());
+    }
+}
+
+/// Writing end of the ring buffer. Only one may exist per region.
+pub struct Producer {
+    raw: RawRing,
+    _owner: Option<Arc<OwnedRegion>>,
+}
+
+// SAFETY: the producer only writes the free part of the ring, published with release ordering.
+unsafe impl Send for Producer {}
+
+impl Producer {
+    /// # Safety
+    /// The region must have been set up with [`init_region`], must outlive the producer, and no
+    /// other producer may use it.
+    #[cfg(not(loom))]
+    pub unsafe fn from_raw(ptr: *mut u8, capacity: usize) -> Self {
+        Self {
+            raw: RawRing::from_raw(ptr, capacity),
+            _owner: None,
+        }
+    }
+
+    /// Number of samples that can be pushed without overrunning.
+    pub fn free(&self) -> usize {
+        let header = self.raw.header();
+        let write = header.write.load(Ordering::Relaxed);
+        let read = header.read.load(Ordering::Acquire);
+
+        (self.raw.capacity - write.wrapping_sub(read)) as usize
+    }
+
+    /// Push as many of `samples` as fit, returning how many were written.
+    ///
+    /// If some samples do not fit, they are dropped and counted in [`Producer::overruns`].
+    pub fn push(&mut self, samples: &[f32]) -> usize {
+        let header = self.raw.header();
+        let write = header.write.load(Ordering::Relaxed);
+        let count = self.free().min(samples.len());
+
+        for (i, &x) in samples[..count].iter().enumerate() {
+            let idx = write.wrapping_add(i as u32) & self.raw.mask();
+            // SAFETY: `idx` is in bounds and in the free part of the ring, which the consumer
+            // does not read until `write` is published below.
+            unsafe { self.raw.write(idx, x) };
+        }
+
+        header
+            .write
+            .store(write.wrapping_add(count as u32), Ordering::Release);
+
+        if count < samples.len() {
+            header
+                .overruns
+                .fetch_add((samples.len() - count) as u32, Ordering::Relaxed);
+        }
+
+        count
+    }
+
+    /// Total number of samples dropped because the ring was full.
+    pub fn overruns(&self) -> u32 {
+        self.raw.header().overruns.load(Ordering::Relaxed)
+    }
+
+    /// Total number of [`Consumer::pop`] calls that could not be fully satisfied.
+    pub fn underruns(&self) -> u32 {
+        self.raw.header().underruns.load(Ordering::Relaxed)
+    }
+}
+
+/// Reading end of the ring buffer. Only one may exist per region.
+pub struct Consumer {
+    raw: RawRing,
+    _owner: Option<Arc<OwnedRegion>>,
+}
+
+// SAFETY: the consumer only reads the filled part of the ring, acquired before reading.
+unsafe impl Send for Consumer {}
+
+impl Consumer {
+    /// # Safety
+    /// The region must have been set up with [`init_region`], must outlive the consumer, and no
+    /// other consumer may use it.
+    #[cfg(not(loom))]
+    pub unsafe fn from_raw(ptr: *mut u8, capacity: usize) -> Self {
+        Self {
+            raw: RawRing::from_raw(ptr, capacity),
+            _owner: None,
+        }
+    }
+
+    /// Number of samples ready to be popped.
+    pub fn available(&self) -> usize {
+        let header = self.raw.header();
+        let write = header.write.load(Ordering::Acquire);
+        let read = header.read.load(Ordering::Relaxed);
+
+        write.wrapping_sub(read) as usize
+    }
+
+    /// Pop up to `output.len()` samples, returning how many were read.
+    ///
+    /// If fewer samples are available than requested, the call is counted in
+    /// [`Consumer::underruns`].
+    pub fn pop(&mut self, output: &mut [f32]) -> usize {
+        let header = self.raw.header();
+        let read = header.read.load(Ordering::Relaxed);
+        let count = self.available().min(output.len());
+
+        for (i, y) in output[..count].iter_mut().enumerate() {
+            let idx = read.wrapping_add(i as u32) & self.raw.mask();
+            // SAFETY: `idx` is in bounds and in the filled part of the ring, which the producer
+            // does not overwrite until `read` is published below.
+            *y = unsafe { self.raw.read(idx) };
+        }
+
+        header
+            .read
+            .store(read.wrapping_add(count as u32), Ordering::Release);
+
+        if count < output.len() {
+            header.underruns.fetch_add(1, Ordering::Relaxed);
+        }
+
+        count
+    }
+
+    /// Total number of samples dropped because the ring was full.
+    pub fn overruns(&self) -> u32 {
+        self.raw.header().overruns.load(Ordering::Relaxed)
+    }
+
+    /// Total number of [`Consumer::pop`] calls that could not be fully satisfied.
+    pub fn underruns(&self) -> u32 {
+        self.raw.header().underruns.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_spsc_counters() {
+    let (mut tx, mut rx) = spsc_ring_buf(4);
+    let mut out = [0.; 4];
+
+    assert_eq!(tx.push(&[1., 2., 3.]), 3);
+    assert_eq!(tx.push(&[4., 5., 6.]), 1);
+    assert_eq!(tx.overruns(), 2);
+    assert_eq!(tx.free(), 0);
+
+    assert_eq!(rx.pop(&mut out[..2]), 2);
+    assert_eq!(out[..2], [1., 2.]);
+    assert_eq!(rx.pop(&mut out), 2);
+    assert_eq!(out[..2], [3., 4.]);
+    assert_eq!(rx.underruns(), 1);
+    assert_eq!(tx.underruns(), 1);
+    assert_eq!(rx.available(), 0);
+}
+
+#[cfg(not(loom))]
+#[test]
+#[should_panic(expected = "is_power_of_2(capacity)")]
+fn test_spsc_capacity_not_power_of_2() {
+    spsc_ring_buf(3);
+}
+
+#[cfg(not(loom))]
+#[test]
+#[should_panic(expected = "is_power_of_2(capacity)")]
+fn test_spsc_raw_capacity_not_power_of_2() {
+    let mut region = vec![0_u32; region_size(16) / 4];
+
+    // SAFETY: `region` is aligned and large enough; the capacity check fails before any access.
+    unsafe { Producer::from_raw(region.as_mut_ptr() as *mut u8, 12) };
+}
+
+#[cfg(not(loom))]
+#[test]
+#[should_panic(expected = "capacity <= u32::MAX as usize / 2")]
+fn test_spsc_capacity_too_large() {
+    spsc_ring_buf(1 << 31);
+}
+
+#[cfg(all(test, not(loom)))]
+proptest::proptest! {
+    /// Any sequence of pushes (`true`) and pops of up to 11 samples behaves like a bounded queue.
+    #[test]
+    fn test_spsc_matches_model(
+        ops in proptest::collection::vec((proptest::bool::ANY, 0..12usize), 0..200),
+    ) {
+        use std::collections::VecDeque;
+
+        let (mut tx, mut rx) = spsc_ring_buf(16);
+        let mut model = VecDeque::new();
+        let mut next = 0.;
+
+        for (push, len) in ops {
+            if push {
+                let input: Vec<f32> = (0..len).map(|i| next + i as f32).collect();
+                let written = tx.push(&input);
+                proptest::prop_assert_eq!(written, len.min(16 - model.len()));
+
+                model.extend(&input[..written]);
+                next += written as f32;
+            } else {
+                let mut output = vec![0.; len];
+                let read = rx.pop(&mut output);
+                proptest::prop_assert_eq!(read, len.min(model.len()));
+
+                let expected: Vec<f32> = model.drain(..read).collect();
+                proptest::prop_assert_eq!(&output[..read], &expected[..]);
+            }
+        }
+    }
+}
+
+#[cfg(not(loom))]
+#[test]
+fn test_spsc_raw_region_threads() {
+    const CAPACITY: usize = 64;
+    const TOTAL: usize = 20_000;
+
+    // u32-backed to get the header alignment
+    let mut region = vec![0_u32; region_size(CAPACITY) / 4];
+    let ptr = region.as_mut_ptr() as *mut u8;
+
+    // SAFETY: `region` is aligned, large enough, and outlives both threads.
+    let (mut tx, mut rx) = unsafe {
+        init_region(ptr, CAPACITY);
+        (
+            Producer::from_raw(ptr, CAPACITY),
+            Consumer::from_raw(ptr, CAPACITY),
+        )
+    };
+
+    std::thread::scope(|s| {
+        s.spawn(move || {
+            let mut sent = 0;
+            while sent < TOTAL {
+                let block: Vec<f32> = (sent..(sent + 7).min(TOTAL)).map(|x| x as f32).collect();
+                sent += tx.push(&block);
+            }
+        });
+
+        s.spawn(move || {
+            let mut received = 0;
+            let mut block = [0.; 5];
+            while received < TOTAL {
+                let n = rx.pop(&mut block);
+                for &x in &block[..n] {
+                    assert_eq!(x, received as f32);
+                    received += 1;
+                }
+            }
+        });
+    });
+}
+
+#[cfg(loom)]
+#[test]
+fn test_spsc_loom() {
+    let mut builder = loom::model::Builder::new();
+    builder.preemption_bound = Some(3);
+
+    builder.check(|| {
+        let (mut tx, mut rx) = spsc_ring_buf(2);
+
+        let producer = loom::thread::spawn(move || {
+            let mut sent = 0;
+            while sent < 3 {
+                let n = tx.push(&[sent as f32 + 1.]);
+                if n == 0 {
+                    loom::thread::yield_now();
+                }
+                sent += n;
+            }
+        });
+
+        let mut received = 0;
+        let mut out = [0.];
+        while received < 3 {
+            if rx.pop(&mut out) == 1 {
+                received += 1;
+                assert_eq!(out[0], received as f32);
+            } else {
+                loom::thread::yield_now();
+            }
+        }
+
+        producer.join().unwrap();
+    });
+}
@@ -0,0 +1,335 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Algorithm, Plan, Workspace};
+
+/// Number of samples per channel in each Web Audio render quantum.
+pub const RENDER_QUANTUM: usize = 128;
+
+/// The latest `len` samples of a stream, for analysers that look at them every `hop` samples.
+///
+/// Frames are unwrapped from the ring into a buffer of their own, oldest sample first, so
+/// nothing allocates after [`HopRing::new`].
+pub(crate) struct HopRing {
+    samples: Vec<f32>,
+    frame: Vec<f32>,
+    hop: usize,
+
+    write_pos: usize,
+    filled: usize,
+    since_analysis: usize,
+    frames: u32,
+}
+
+impl HopRing {
+    pub(crate) fn new(len: usize, hop: usize) -> Self {
+        assert!(hop > 0);
+
+        Self {
+            samples: vec![0.; len],
+            frame: vec![0.; len],
+            hop,
+            write_pos: 0,
+            filled: 0,
+            since_analysis: 0,
+            frames: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub(crate) fn hop(&self) -> usize {
+        self.hop
+    }
+
+    /// Frames analysed since construction or the last [`HopRing::reset`].
+    pub(crate) fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Push a block of samples, passing the latest `len()` of them to `analyse` every `hop`
+    /// samples once that many have arrived. Returns `true` if `analyse` was called.
+    pub(crate) fn push(&mut self, block: &[f32], mut analyse: impl FnMut(&[f32])) -> bool {
+        let len = self.samples.len();
+        let mut analysed = false;
+
+        for &x in block {
+            self.samples[self.write_pos] = x;
+            self.write_pos = (self.write_pos + 1) % len;
+            self.filled = (self.filled + 1).min(len);
+            self.since_analysis += 1;
+
+            if self.filled == len && self.since_analysis >= self.hop {
+                // `write_pos` points at the oldest sample once the ring is full
+                let (newer, older) = self.samples.split_at(self.write_pos);
+                for (y, &x) in self.frame.iter_mut().zip(older.iter().chain(newer)) {
+                    *y = x;
+                }
+
+                analyse(&self.frame);
+                self.frames += 1;
+                self.since_analysis = 0;
+                analysed = true;
+            }
+        }
+
+        analysed
+    }
+
+    /// Forget all buffered samples, and start counting frames again.
+    pub(crate) fn reset(&mut self) {
+        self.samples.iter_mut().for_each(|x| *x = 0.);
+        self.write_pos = 0;
+        self.filled = 0;
+        self.since_analysis = 0;
+        self.frames = 0;
+    }
+}
+
+/// Streaming analyser meant to be driven from an `AudioWorkletProcessor`.
+///
+/// Samples are pushed in blocks (normally [`RENDER_QUANTUM`] long) into an internal ring buffer.
+/// Every `hop` samples, once at least `fft_len` samples have arrived, the most recent `fft_len`
+/// samples are transformed. All memory is allocated in [`StreamProcessor::new`], so
+/// [`StreamProcessor::process`] never allocates.
+///
+/// From JS, wasm-bindgen copies slice arguments through the wasm heap, allocating on every call. An
+/// `AudioWorkletProcessor` should instead write each render quantum into
+/// [`StreamProcessor::input_view`], call [`StreamProcessor::process_shared`] and read
+/// [`StreamProcessor::spectrum_view`].
+#[wasm_bindgen]
+pub struct StreamProcessor {
+    plan: Plan,
+    workspace: Workspace,
+    ring: HopRing,
+
+    input: Vec<f32>,
+    spectrum: Vec<f32>,
+    rms: f32,
+    peak: f32,
+}
+
+#[wasm_bindgen]
+impl StreamProcessor {
+    pub fn new(fft_len: usize, hop: usize) -> Self {
+        Self::with_algorithm(fft_len, hop, Algorithm::Lib)
+    }
+
+    /// Same as [`StreamProcessor::new`], transforming with `algorithm`.
+    pub fn with_algorithm(fft_len: usize, hop: usize, algorithm: Algorithm) -> Self {
+        let plan = Plan::new(algorithm, fft_len);
+        let workspace = Workspace::new(&plan);
+
+        Self {
+            plan,
+            workspace,
+            ring: HopRing::new(fft_len, hop),
+            input: vec![0.; RENDER_QUANTUM],
+            spectrum: vec![0.; fft_len / 2],
+            rms: 0.,
+            peak: 0.,
+        }
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.plan.algorithm()
+    }
+
+    pub fn fft_len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn hop(&self) -> usize {
+        self.ring.hop()
+    }
+
+    /// Total number of spectra computed since construction or the last [`StreamProcessor::reset`].
+    pub fn frames(&self) -> u32 {
+        self.ring.frames()
+    }
+
+    /// Push a block of samples, analysing as many times as the hop size requires.
+    ///
+    /// Returns `true` if the spectrum was updated during this call.
+    pub fn process(&mut self, block: &[f32]) -> bool {
+        self.ring.push(block, |window| {
+            self.plan
+                .process(&mut self.workspace, window, &mut self.spectrum);
+
+            // log10 of the 1 / sqrt(n) normalization
+            let normalize = -0.5 * (window.len() as f32).log10();
+            for y in &mut self.spectrum {
+                *y += normalize;
+            }
+
+            let energy: f32 = window.iter().map(|x| x * x).sum();
+            self.rms = (energy / window.len() as f32).sqrt();
+            self.peak = window.iter().fold(0., |peak, x| x.abs().max(peak));
+        })
+    }
+
+    /// Pointer into wasm memory to the [`RENDER_QUANTUM`] samples read by
+    /// [`StreamProcessor::process_shared`].
+    pub fn input_ptr(&mut self) -> *mut f32 {
+        self.input.as_mut_ptr()
+    }
+
+    /// Pointer into wasm memory to the `fft_len() / 2` bins of the latest spectrum.
+    pub fn spectrum_ptr(&self) -> *const f32 {
+        self.spectrum.as_ptr()
+    }
+
+    /// `Float32Array` view directly over the shared input block.
+    ///
+    /// The buffers never move, but the view is only valid until wasm memory grows (any allocation
+    /// in the module may do this). A stale view has a `length` of 0, so check it, or simply fetch a
+    /// new view before each use.
+    pub fn input_view(&mut self) -> js_sys::Float32Array {
+        // SAFETY: the view is handed to JS, which must follow the lifetime contract above.
+        unsafe { js_sys::Float32Array::view_mut_raw(self.input_ptr(), self.input.len()) }
+    }
+
+    /// `Float32Array` view directly over the latest spectrum.
+    ///
+    /// Same lifetime contract as [`StreamProcessor::input_view`].
+    pub fn spectrum_view(&self) -> js_sys::Float32Array {
+        // SAFETY: the view is handed to JS, which must follow the lifetime contract above.
+        unsafe { js_sys::Float32Array::view(&self.spectrum) }
+    }
+
+    /// Same as [`StreamProcessor::process`], on the block in the shared input buffer, without
+    /// copying across the JS boundary.
+    pub fn process_shared(&mut self) -> bool {
+        let input = std::mem::take(&mut self.input);
+        let updated = self.process(&input);
+        self.input = input;
+
+        updated
+    }
+
+    /// Copy the latest spectrum (`fft_len / 2` bins, same scale as `WasmFft::lib_fft`).
+    pub fn spectrum(&self, output: &mut [f32]) {
+        output.copy_from_slice(&self.spectrum);
+    }
+
+    /// RMS level of the window the latest spectrum was computed from.
+    pub fn rms(&self) -> f32 {
+        self.rms
+    }
+
+    /// Largest absolute sample of the window the latest spectrum was computed from.
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    /// Forget all buffered samples. The latest spectrum is kept.
+    pub fn reset(&mut self) {
+        self.ring.reset();
+    }
+}
+
+#[test]
+fn test_stream_processor_triggers() {
+    let mut stream = StreamProcessor::new(512, 256);
+    let block = [0.5; RENDER_QUANTUM];
+
+    // 512 samples must arrive before the first analysis
+    assert!(!stream.process(&block));
+    assert!(!stream.process(&block));
+    assert!(!stream.process(&block));
+    assert!(stream.process(&block));
+    assert_eq!(stream.frames(), 1);
+
+    // ...then every 256 samples
+    assert!(!stream.process(&block));
+    assert!(stream.process(&block));
+    assert_eq!(stream.frames(), 2);
+
+    stream.reset();
+    assert!(!stream.process(&block));
+    assert_eq!(stream.frames(), 0);
+}
+
+#[test]
+fn test_stream_processor_spectrum() {
+    let fft_len = 256;
+    let signal: Vec<f32> = (0..1024).map(|i| (i as f32 * 0.3).sin()).collect();
+
+    let mut stream = StreamProcessor::new(fft_len, fft_len);
+    let mut actual = vec![0.; fft_len / 2];
+    let mut expected = vec![0.; fft_len / 2];
+    let mut fft = crate::WasmFft::new();
+
+    for (i, block) in signal.chunks_exact(RENDER_QUANTUM).enumerate() {
+        let end = (i + 1) * RENDER_QUANTUM;
+        assert_eq!(stream.process(block), end.is_multiple_of(fft_len));
+
+        // Hop equals `fft_len`, so the latest window ends at the last multiple of `fft_len`
+        let window_end = end / fft_len * fft_len;
+        stream.spectrum(&mut actual);
+        if window_end > 0 {
+            fft.lib_fft(&signal[window_end - fft_len..window_end], &mut expected);
+            approx::assert_abs_diff_eq!(actual.as_slice(), expected.as_slice(), epsilon = 1e-5);
+        }
+    }
+}
+
+#[test]
+fn test_stream_processor_shared() {
+    let mut signal = vec![0.; 8 * RENDER_QUANTUM];
+    crate::siggen::multitone(&[440., 3000.], 16000., &mut signal);
+
+    let mut stream = StreamProcessor::new(512, 256);
+    let mut shared = StreamProcessor::new(512, 256);
+
+    for block in signal.chunks_exact(RENDER_QUANTUM) {
+        let ptr = shared.input_ptr();
+        // SAFETY: the shared input buffer holds `RENDER_QUANTUM` samples.
+        unsafe { std::slice::from_raw_parts_mut(ptr, RENDER_QUANTUM) }.copy_from_slice(block);
+        assert_eq!(shared.process_shared(), stream.process(block));
+    }
+
+    let mut expected = vec![0.; 256];
+    stream.spectrum(&mut expected);
+    let ptr = shared.spectrum_ptr();
+    // SAFETY: the spectrum holds `fft_len() / 2` bins.
+    let actual = unsafe { std::slice::from_raw_parts(ptr, expected.len()) };
+    assert_eq!(actual, expected.as_slice());
+    assert_eq!(shared.frames(), 3);
+}
+
+#[test]
+fn test_stream_processor_algorithms() {
+    let fft_len = 256;
+    let mut signal = vec![0.; 1024];
+    crate::siggen::oscillator(crate::siggen::Waveform::Square, 440., 8000., &mut signal);
+
+    let mut lib = StreamProcessor::new(fft_len, 128);
+    let mut simd = StreamProcessor::with_algorithm(fft_len, 128, Algorithm::SimdCooleyTukey3);
+    assert_eq!(simd.algorithm(), Algorithm::SimdCooleyTukey3);
+
+    for block in signal.chunks_exact(RENDER_QUANTUM) {
+        assert_eq!(lib.process(block), simd.process(block));
+    }
+
+    let mut expected = vec![0.; fft_len / 2];
+    let mut actual = vec![0.; fft_len / 2];
+    lib.spectrum(&mut expected);
+    simd.spectrum(&mut actual);
+    approx::assert_abs_diff_eq!(actual.as_slice(), expected.as_slice(), epsilon = 1e-4);
+
+    // Levels of the last `fft_len` samples
+    let window = &signal[signal.len() - fft_len..];
+    let rms = (window.iter().map(|x| x * x).sum::<f32>() / fft_len as f32).sqrt();
+    let peak = window.iter().fold(0f32, |peak, x| peak.max(x.abs()));
+    approx::assert_abs_diff_eq!(simd.rms(), rms, epsilon = 1e-6);
+    assert_eq!(simd.peak(), peak);
+}
+
+#[test]
+#[should_panic(expected = "is_power_of_2(len)")]
+fn test_stream_processor_not_power_of_2() {
+    // Checked by the plan
+    StreamProcessor::new(1000, 250);
+}
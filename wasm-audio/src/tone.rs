@@ -0,0 +1,214 @@
+use rustfft::num_complex::Complex;
+use wasm_bindgen::prelude::*;
+
+use crate::{goertzel::GoertzelBank, sliding_dft::SlidingDft};
+
+/// Goertzel filters for a few frequencies, over consecutive `block_len`-sample blocks.
+///
+/// Cheaper than [`crate::WasmFft`] when only a handful of bins are needed, as in DTMF detection.
+/// Frequencies need not fall on DFT bins. The bank doesn't allocate after construction, but from
+/// JS wasm-bindgen copies each slice argument through the wasm heap; [`WasmGoertzel::push`] takes
+/// a single sample and avoids that.
+#[wasm_bindgen]
+pub struct WasmGoertzel {
+    bank: GoertzelBank<f32>,
+    block_len: usize,
+}
+
+#[wasm_bindgen]
+impl WasmGoertzel {
+    pub fn new(freqs: &[f32], sample_rate: f32, block_len: usize) -> Self {
+        crate::set_panic_hook();
+
+        Self {
+            bank: GoertzelBank::from_frequencies(freqs, sample_rate, block_len),
+            block_len,
+        }
+    }
+
+    pub fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    /// Push one sample. Returns `true` when it ends a block and the results were updated.
+    pub fn push(&mut self, x: f32) -> bool {
+        self.bank.push(x)
+    }
+
+    /// Push a block of samples of any length. Returns `true` if any block ended.
+    pub fn process(&mut self, block: &[f32]) -> bool {
+        self.bank.process(block)
+    }
+
+    /// `|X|` at each frequency for the last complete block, on the scale of the DFT.
+    pub fn magnitudes(&self, output: &mut [f32]) {
+        magnitudes(self.bank.results(), output);
+    }
+
+    /// Phase at each frequency for the last complete block, relative to its first sample.
+    pub fn phases(&self, output: &mut [f32]) {
+        phases(self.bank.results(), output);
+    }
+
+    /// Drop the current block and the results.
+    pub fn reset(&mut self) {
+        self.bank.reset();
+    }
+}
+
+/// Sliding DFT of a few frequencies over the latest `window_len` samples, updated every sample.
+/// Every sample costs the same, in proportion to the number of frequencies, so it suits a
+/// real-time thread.
+///
+/// Rust callers get no allocation after construction. JS callers do on every call with a slice,
+/// for wasm-bindgen's copy of it, but not through [`WasmSlidingDft::push`].
+#[wasm_bindgen]
+pub struct WasmSlidingDft {
+    sdft: SlidingDft<f32>,
+    bins: Vec<Complex<f32>>,
+}
+
+#[wasm_bindgen]
+impl WasmSlidingDft {
+    pub fn new(freqs: &[f32], sample_rate: f32, window_len: usize) -> Self {
+        crate::set_panic_hook();
+
+        Self {
+            sdft: SlidingDft::from_frequencies(freqs, sample_rate, window_len),
+            bins: vec![Complex::new(0., 0.); freqs.len()],
+        }
+    }
+
+    pub fn window_len(&self) -> usize {
+        self.sdft.window_len()
+    }
+
+    pub fn push(&mut self, x: f32) {
+        self.sdft.push(x);
+    }
+
+    pub fn process(&mut self, block: &[f32]) {
+        self.sdft.process(block);
+    }
+
+    /// `|X|` at each frequency for the current window, on the scale of the DFT.
+    pub fn magnitudes(&mut self, output: &mut [f32]) {
+        self.sdft.bins(&mut self.bins);
+        magnitudes(&self.bins, output);
+    }
+
+    /// Phase at each frequency for the current window, relative to its oldest sample.
+    pub fn phases(&mut self, output: &mut [f32]) {
+        self.sdft.bins(&mut self.bins);
+        phases(&self.bins, output);
+    }
+
+    /// Clear the window.
+    pub fn reset(&mut self) {
+        self.sdft.reset();
+    }
+}
+
+fn magnitudes(bins: &[Complex<f32>], output: &mut [f32]) {
+    assert_eq!(bins.len(), output.len());
+
+    for (y, x) in output.iter_mut().zip(bins) {
+        *y = x.norm();
+    }
+}
+
+fn phases(bins: &[Complex<f32>], output: &mut [f32]) {
+    assert_eq!(bins.len(), output.len());
+
+    for (y, x) in output.iter_mut().zip(bins) {
+        *y = x.arg();
+    }
+}
+
+/// The DTMF digit with the strongest row and column in `magnitudes`, if both stand out.
+#[cfg(test)]
+fn detect_dtmf(magnitudes: &[f32]) -> Option<char> {
+    const KEYS: [[char; 4]; 4] = [
+        ['1', '2', '3', 'A'],
+        ['4', '5', '6', 'B'],
+        ['7', '8', '9', 'C'],
+        ['*', '0', '#', 'D'],
+    ];
+
+    let strongest = |group: &[f32]| {
+        let (i, &max) = group
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .unwrap();
+        let others = group
+            .iter()
+            .filter(|&&x| x != max)
+            .fold(0f32, |a, &b| a.max(b));
+        (max > 5. * others).then_some(i)
+    };
+
+    Some(KEYS[strongest(&magnitudes[..4])?][strongest(&magnitudes[4..])?])
+}
+
+#[cfg(test)]
+const DTMF_FREQUENCIES: [f32; 8] = [697., 770., 852., 941., 1209., 1336., 1477., 1633.];
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_goertzel_dtmf() {
+    // The usual 205-sample blocks at 8 kHz, whose bins fall close to every DTMF frequency
+    let sample_rate = 8000.;
+    let mut goertzel = WasmGoertzel::new(&DTMF_FREQUENCIES, sample_rate, 205);
+    let mut magnitudes = [0.; 8];
+    let mut tone = vec![0.; 410];
+
+    for digit in "0123456789*#ABCD".chars() {
+        crate::siggen::dtmf(digit, sample_rate, &mut tone);
+
+        goertzel.reset();
+        assert!(goertzel.process(&tone[..300]));
+        goertzel.magnitudes(&mut magnitudes);
+        assert_eq!(detect_dtmf(&magnitudes), Some(digit));
+
+        // The second block completes on its last sample
+        assert!(!goertzel.process(&tone[300..409]));
+        assert!(goertzel.push(tone[409]));
+    }
+
+    goertzel.reset();
+    goertzel.magnitudes(&mut magnitudes);
+    assert_eq!(magnitudes, [0.; 8]);
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_sliding_dft_dtmf() {
+    let sample_rate = 8000.;
+    let window_len = 205;
+    let mut sdft = WasmSlidingDft::new(&DTMF_FREQUENCIES, sample_rate, window_len);
+    let mut magnitudes = [0.; 8];
+
+    // Back-to-back digits, with no gap: the window follows every change within `window_len`
+    let mut tone = vec![0.; 1000];
+    for digit in "159D".chars() {
+        crate::siggen::dtmf(digit, sample_rate, &mut tone);
+
+        for (i, block) in tone.chunks(crate::RENDER_QUANTUM).enumerate() {
+            sdft.process(block);
+            if (i + 1) * crate::RENDER_QUANTUM >= window_len {
+                sdft.magnitudes(&mut magnitudes);
+                assert_eq!(detect_dtmf(&magnitudes), Some(digit));
+            }
+        }
+    }
+
+    // Per sample, matching the block path
+    let mut other = WasmSlidingDft::new(&DTMF_FREQUENCIES, sample_rate, window_len);
+    let mut expected = [0.; 8];
+    tone.iter().for_each(|&x| other.push(x));
+    other.magnitudes(&mut expected);
+    approx::assert_abs_diff_eq!(magnitudes.as_slice(), expected.as_slice(), epsilon = 1e-3);
+}
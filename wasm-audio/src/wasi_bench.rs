@@ -0,0 +1,107 @@
+//! Plain C ABI exports for the `wasm-bench` runner, which times the kernels in wasmtime.
+//!
+//! Only built for WASI targets, where there is no JS glue to call the `wasm_bindgen` API. Each
+//! kernel is run through the same [`WasmFft`] method the web page calls, so the runner times
+//! the same work as the overlay.
+
+use std::cell::RefCell;
+
+use crate::{
+    siggen::{self, NoiseColor},
+    WasmFft,
+};
+
+/// Kernel names, as labelled in the web overlay.
+#[cfg(not(feature = "relaxed-simd"))]
+const KERNELS: &[&str] = &[
+    "dft", "lib", "naive", "simd1", "simd2", "simd3", "auto", "q15",
+];
+#[cfg(feature = "relaxed-simd")]
+const KERNELS: &[&str] = &[
+    "dft", "lib", "naive", "simd1", "simd2", "simd3", "auto", "q15", "relx2", "relx3",
+];
+
+struct State {
+    fft: WasmFft,
+    input: Vec<f32>,
+    /// `input` as 16-bit PCM, for the fixed-point kernel
+    pcm: Vec<i16>,
+    output: Vec<f32>,
+}
+
+thread_local! {
+    static STATE: RefCell<Option<State>> = const { RefCell::new(None) };
+}
+
+#[no_mangle]
+pub extern "C" fn bench_kernel_count() -> u32 {
+    KERNELS.len() as u32
+}
+
+#[no_mangle]
+pub extern "C" fn bench_kernel_name_ptr(kernel: u32) -> *const u8 {
+    KERNELS[kernel as usize].as_ptr()
+}
+
+#[no_mangle]
+pub extern "C" fn bench_kernel_name_len(kernel: u32) -> u32 {
+    KERNELS[kernel as usize].len() as u32
+}
+
+/// Allocate buffers for `size`-sample transforms of white noise.
+#[no_mangle]
+pub extern "C" fn bench_prepare(size: u32) {
+    let size = size as usize;
+    let mut input = vec![0.; size];
+    siggen::noise(NoiseColor::White, 1, &mut input);
+    let pcm = input.iter().map(|&x| (x * 32767.) as i16).collect();
+
+    STATE.with(|state| {
+        *state.borrow_mut() = Some(State {
+            fft: WasmFft::with_capcity(size),
+            input,
+            pcm,
+            output: vec![0.; size / 2],
+        })
+    });
+}
+
+/// Run `kernel` `calls` times on the buffers from [`bench_prepare`].
+#[no_mangle]
+pub extern "C" fn bench_run(kernel: u32, calls: u32) {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let State {
+            fft,
+            input,
+            pcm,
+            output,
+        } = state.as_mut().expect("bench_prepare not called");
+
+        if KERNELS[kernel as usize] == "q15" {
+            for _ in 0..calls {
+                fft.fft_q15(pcm, output);
+            }
+            return;
+        }
+
+        let run = match KERNELS[kernel as usize] {
+            "dft" => WasmFft::dft,
+            "lib" => WasmFft::lib_fft,
+            "naive" => WasmFft::cooley_tukey,
+            "simd1" => WasmFft::simd_cooley_tukey,
+            "simd2" => WasmFft::simd_cooley_tukey2,
+            "simd3" => WasmFft::simd_cooley_tukey3,
+            "auto" => WasmFft::fft,
+            #[cfg(feature = "relaxed-simd")]
+            "relx2" => WasmFft::simd_cooley_tukey2_relaxed,
+            #[cfg(feature = "relaxed-simd")]
+            "relx3" => WasmFft::simd_cooley_tukey3_relaxed,
+            _ => unreachable!(),
+        };
+
+        for _ in 0..calls {
+            run(fft, input, output);
+        }
+    });
+}
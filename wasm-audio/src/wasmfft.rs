@@ -1,8 +1,21 @@
 use rustfft::{num_complex::Complex, num_traits::Zero, FftPlanner};
 use wasm_bindgen::prelude::*;
 
-use crate::{cooley_tukey::cooley_tukey_fft, dft::dft, simd_cooley_tukey3::Complex4};
+use crate::{
+    accuracy::FftFn,
+    cooley_tukey::cooley_tukey_fft,
+    dft::dft,
+    siggen::{self, NoiseColor, Waveform},
+    simd::Native64,
+};
 
+use crate::simd_cooley_tukey3::Complex4;
+
+#[cfg(all(target_arch = "wasm32", feature = "relaxed-simd"))]
+use crate::simd::Relaxed;
+
+/// Every transform in the crate, on buffers sized to each call. See [`crate::Plan`] for calls
+/// that never allocate.
 #[wasm_bindgen]
 pub struct WasmFft {
     planner: FftPlanner<f32>,
@@ -10,8 +23,30 @@ pub struct WasmFft {
     output_buffer: Vec<Complex<f32>>,
     scratch_buffer: Vec<Complex<f32>>,
 
+    shared_input: Vec<f32>,
+    shared_output: Vec<f32>,
+
     input_buffer_x4: Vec<Complex4>,
     output_buffer_x4: Vec<Complex4>,
+
+    // For the `_f64` methods, allocated on first use
+    planner_f64: FftPlanner<f64>,
+    input_buffer_f64: Vec<Complex<f64>>,
+    output_buffer_f64: Vec<Complex<f64>>,
+    scratch_buffer_f64: Vec<Complex<f64>>,
+    input_buffer_x4_f64: Vec<Complex4<Native64>>,
+    output_buffer_x4_f64: Vec<Complex4<Native64>>,
+
+    // For the fixed-point methods, allocated on first use
+    input_buffer_q15: Vec<Complex<i16>>,
+    output_buffer_q15: Vec<Complex<i16>>,
+    input_buffer_q31: Vec<Complex<i32>>,
+    output_buffer_q31: Vec<Complex<i32>>,
+
+    #[cfg(all(target_arch = "wasm32", feature = "relaxed-simd"))]
+    input_buffer_x4_relaxed: Vec<Complex4<Relaxed>>,
+    #[cfg(all(target_arch = "wasm32", feature = "relaxed-simd"))]
+    output_buffer_x4_relaxed: Vec<Complex4<Relaxed>>,
 }
 
 #[wasm_bindgen]
@@ -31,16 +66,29 @@ impl WasmFft {
         let fft = planner.plan_fft_forward(capacity);
         let scratch_buffer = vec![Complex::zero(); fft.get_outofplace_scratch_len()];
 
-        let input_buffer_x4 = vec![Complex4::zero(); capacity];
-        let output_buffer_x4 = vec![Complex4::zero(); capacity];
-
         Self {
             planner,
             input_buffer,
             output_buffer,
             scratch_buffer,
-            input_buffer_x4,
-            output_buffer_x4,
+            shared_input: vec![0.; capacity],
+            shared_output: vec![0.; capacity / 2],
+            input_buffer_x4: vec![Complex4::zero(); capacity],
+            output_buffer_x4: vec![Complex4::zero(); capacity],
+            planner_f64: FftPlanner::new(),
+            input_buffer_f64: Vec::new(),
+            output_buffer_f64: Vec::new(),
+            scratch_buffer_f64: Vec::new(),
+            input_buffer_x4_f64: Vec::new(),
+            output_buffer_x4_f64: Vec::new(),
+            input_buffer_q15: Vec::new(),
+            output_buffer_q15: Vec::new(),
+            input_buffer_q31: Vec::new(),
+            output_buffer_q31: Vec::new(),
+            #[cfg(all(target_arch = "wasm32", feature = "relaxed-simd"))]
+            input_buffer_x4_relaxed: vec![Complex4::zero(); capacity],
+            #[cfg(all(target_arch = "wasm32", feature = "relaxed-simd"))]
+            output_buffer_x4_relaxed: vec![Complex4::zero(); capacity],
         }
     }
 
@@ -75,6 +123,148 @@ impl WasmFft {
         });
     }
 
+    /// Resize the shared buffers to `len` input samples and `len / 2` output bins.
+    ///
+    /// This may move the buffers, so any pointers or views taken before must be fetched again.
+    pub fn resize_shared(&mut self, len: usize) {
+        assert!(crate::is_power_of_2(len));
+
+        self.shared_input.resize(len, 0.);
+        self.shared_output.resize(len / 2, 0.);
+    }
+
+    pub fn shared_input_len(&self) -> usize {
+        self.shared_input.len()
+    }
+
+    pub fn shared_output_len(&self) -> usize {
+        self.shared_output.len()
+    }
+
+    /// Pointer into wasm memory to the `shared_input_len()` input samples.
+    pub fn shared_input_ptr(&mut self) -> *mut f32 {
+        self.shared_input.as_mut_ptr()
+    }
+
+    /// Pointer into wasm memory to the `shared_output_len()` output bins.
+    pub fn shared_output_ptr(&self) -> *const f32 {
+        self.shared_output.as_ptr()
+    }
+
+    /// `Float32Array` view directly over the shared input buffer.
+    ///
+    /// The view is only valid until the next call to [`WasmFft::resize_shared`], or until wasm
+    /// memory grows (any allocation in the module may do this). A stale view has a `length` of 0,
+    /// so check it, or simply fetch a new view before each use.
+    pub fn shared_input_view(&mut self) -> js_sys::Float32Array {
+        // SAFETY: the view is handed to JS, which must follow the lifetime contract above.
+        unsafe {
+            js_sys::Float32Array::view_mut_raw(self.shared_input_ptr(), self.shared_input.len())
+        }
+    }
+
+    /// `Float32Array` view directly over the shared output buffer.
+    ///
+    /// Same lifetime contract as [`WasmFft::shared_input_view`].
+    pub fn shared_output_view(&self) -> js_sys::Float32Array {
+        // SAFETY: the view is handed to JS, which must follow the lifetime contract above.
+        unsafe { js_sys::Float32Array::view(&self.shared_output) }
+    }
+
+    /// Same as [`WasmFft::lib_fft`], reading from and writing to the shared buffers without
+    /// copying across the JS boundary.
+    pub fn lib_fft_shared(&mut self) {
+        let input = std::mem::take(&mut self.shared_input);
+        let mut output = std::mem::take(&mut self.shared_output);
+
+        self.lib_fft(&input, &mut output);
+
+        self.shared_input = input;
+        self.shared_output = output;
+    }
+
+    /// Number of frames [`WasmFft::process_batch`] produces for `input_len` samples.
+    pub fn batch_frame_count(input_len: usize, frame_len: usize, hop: usize) -> usize {
+        assert!(hop > 0);
+
+        if input_len < frame_len {
+            0
+        } else {
+            (input_len - frame_len) / hop + 1
+        }
+    }
+
+    /// Transform every `frame_len` window of `input`, advancing by `hop` samples, in one call.
+    ///
+    /// `output` is a row-major matrix with one row of `frame_len / 2` bins per frame, in the same
+    /// scale as [`WasmFft::lib_fft`]. With the `parallel` feature, frames are spread across the
+    /// thread pool.
+    pub fn process_batch(
+        &mut self,
+        input: &[f32],
+        frame_len: usize,
+        hop: usize,
+        output: &mut [f32],
+    ) {
+        assert!(crate::is_power_of_2(frame_len));
+
+        let bins = frame_len / 2;
+        let frames = Self::batch_frame_count(input.len(), frame_len, hop);
+        assert_eq!(output.len(), frames * bins);
+
+        let fft = self.planner.plan_fft_forward(frame_len);
+        let scratch_len = fft.get_inplace_scratch_len();
+        let normalize = 1.0 / (frame_len as f32).sqrt();
+
+        let transform_frame = |frame: usize,
+                               row: &mut [f32],
+                               buffer: &mut [Complex<f32>],
+                               scratch: &mut [Complex<f32>]| {
+            let start = frame * hop;
+
+            for (x, &r) in buffer.iter_mut().zip(&input[start..start + frame_len]) {
+                *x = Complex::new(r, 0.);
+            }
+
+            fft.process_with_scratch(buffer, scratch);
+
+            for (y, x) in row.iter_mut().zip(buffer.iter()) {
+                *y = (x.norm() * normalize).log10();
+            }
+        };
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            output.par_chunks_exact_mut(bins).enumerate().for_each_init(
+                || {
+                    (
+                        vec![Complex::zero(); frame_len],
+                        vec![Complex::zero(); scratch_len],
+                    )
+                },
+                |(buffer, scratch), (frame, row)| transform_frame(frame, row, buffer, scratch),
+            );
+        }
+
+        #[cfg(not(feature = "parallel"))]
+        {
+            // Not `input_buffer`, whose imaginary parts the other methods expect to stay zero
+            self.output_buffer.resize(frame_len, Complex::zero());
+            self.scratch_buffer.resize(scratch_len, Complex::zero());
+
+            for (frame, row) in output.chunks_exact_mut(bins).enumerate() {
+                transform_frame(
+                    frame,
+                    row,
+                    &mut self.output_buffer,
+                    &mut self.scratch_buffer,
+                );
+            }
+        }
+    }
+
     pub fn dft(&mut self, input: &[f32], output: &mut [f32]) {
         assert_eq!(input.len(), output.len() * 2);
         assert!(crate::is_power_of_2(input.len()));
@@ -93,6 +283,32 @@ impl WasmFft {
         });
     }
 
+    /// Same as [`WasmFft::cooley_tukey`], on the fastest of the crate's kernels for this machine and
+    /// build, as picked by [`crate::dispatch::kernel`].
+    pub fn fft(&mut self, input: &[f32], output: &mut [f32]) {
+        assert_eq!(input.len(), output.len() * 2);
+        assert!(crate::is_power_of_2(input.len()));
+
+        self.input_buffer.resize(input.len(), Complex::zero());
+        self.output_buffer.resize(input.len(), Complex::zero());
+
+        for (i, &r) in input.iter().enumerate() {
+            self.input_buffer[i].re = r;
+        }
+
+        crate::dispatch::fft(&self.input_buffer, &mut self.output_buffer);
+
+        (0..output.len()).for_each(|i| {
+            output[i] = (self.output_buffer[i].norm()).log10();
+        });
+    }
+
+    /// Name and SIMD backend of the kernel [`WasmFft::fft`] runs, e.g.
+    /// `simd_cooley_tukey (simd128)`.
+    pub fn fft_kernel(&self) -> String {
+        crate::dispatch::kernel().to_string()
+    }
+
     pub fn cooley_tukey(&mut self, input: &[f32], output: &mut [f32]) {
         assert_eq!(input.len(), output.len() * 2);
         assert!(crate::is_power_of_2(input.len()));
@@ -111,7 +327,6 @@ impl WasmFft {
         });
     }
 
-    #[cfg(target_arch = "wasm32")]
     pub fn simd_cooley_tukey(&mut self, input: &[f32], output: &mut [f32]) {
         assert_eq!(input.len(), output.len() * 2);
         assert!(crate::is_power_of_2(input.len()));
@@ -133,7 +348,6 @@ impl WasmFft {
         });
     }
 
-    #[cfg(target_arch = "wasm32")]
     pub fn simd_cooley_tukey2(&mut self, input: &[f32], output: &mut [f32]) {
         assert_eq!(input.len(), output.len() * 2);
         assert!(crate::is_power_of_2(input.len()));
@@ -155,7 +369,11 @@ impl WasmFft {
         });
     }
 
-    #[cfg(target_arch = "wasm32")]
+    // Also converts to and from `Complex4`, so enable SIMD for the whole method
+    #[cfg_attr(
+        all(target_arch = "wasm32", feature = "simd128"),
+        target_feature(enable = "simd128")
+    )]
     pub fn simd_cooley_tukey3(&mut self, input: &[f32], output: &mut [f32]) {
         assert_eq!(input.len(), output.len() * 2);
         assert!(crate::is_power_of_2(input.len()));
@@ -177,12 +395,269 @@ impl WasmFft {
         });
     }
 
-    #[cfg(target_arch = "wasm32")]
-    pub fn test(&self) -> bool {
-        crate::simd_cooley_tukey2::test_simd_complex_mul();
-        crate::simd_cooley_tukey3::test_mul_parallel();
+    /// Fill `output` with a band-limited oscillator starting at phase 0.
+    pub fn generate_waveform(waveform: Waveform, freq: f32, sample_rate: f32, output: &mut [f32]) {
+        siggen::oscillator(waveform, freq, sample_rate, output);
+    }
+
+    pub fn generate_noise(color: NoiseColor, seed: u32, output: &mut [f32]) {
+        siggen::noise(color, seed, output);
+    }
+
+    pub fn generate_impulse(position: usize, output: &mut [f32]) {
+        siggen::impulse(position, output);
+    }
+
+    /// Fill `output` with a sine sweep from `f0` to `f1`, either linear or logarithmic.
+    pub fn generate_sweep(
+        f0: f32,
+        f1: f32,
+        sample_rate: f32,
+        logarithmic: bool,
+        output: &mut [f32],
+    ) {
+        if logarithmic {
+            siggen::log_sweep(f0, f1, sample_rate, output);
+        } else {
+            siggen::linear_sweep(f0, f1, sample_rate, output);
+        }
+    }
+
+    pub fn generate_multitone(freqs: &[f32], sample_rate: f32, output: &mut [f32]) {
+        siggen::multitone(freqs, sample_rate, output);
+    }
+
+    pub fn generate_dtmf(digit: char, sample_rate: f32, output: &mut [f32]) {
+        siggen::dtmf(digit, sample_rate, output);
+    }
+}
+
+/// `f64` versions of the transforms, taking `Float64Array`s.
+#[wasm_bindgen]
+impl WasmFft {
+    /// Same as [`WasmFft::lib_fft`], in `f64`.
+    pub fn lib_fft_f64(&mut self, input: &[f64], output: &mut [f64]) {
+        assert_eq!(input.len(), output.len() * 2);
+        assert!(crate::is_power_of_2(input.len()));
+
+        self.input_buffer_f64.resize(input.len(), Complex::zero());
+        self.output_buffer_f64.resize(input.len(), Complex::zero());
+
+        for (x, &r) in self.input_buffer_f64.iter_mut().zip(input) {
+            *x = Complex::new(r, 0.);
+        }
+
+        let fft = self.planner_f64.plan_fft_forward(input.len());
+
+        self.scratch_buffer_f64
+            .resize(fft.get_outofplace_scratch_len(), Complex::zero());
+
+        fft.process_outofplace_with_scratch(
+            &mut self.input_buffer_f64,
+            &mut self.output_buffer_f64,
+            &mut self.scratch_buffer_f64,
+        );
+
+        let normalize = 1.0 / (input.len() as f64).sqrt();
+        for (y, x) in output.iter_mut().zip(&self.output_buffer_f64) {
+            *y = (x.norm() * normalize).log10();
+        }
+    }
+
+    pub fn dft_f64(&mut self, input: &[f64], output: &mut [f64]) {
+        self.transform_f64(input, output, dft);
+    }
+
+    pub fn cooley_tukey_f64(&mut self, input: &[f64], output: &mut [f64]) {
+        self.transform_f64(input, output, cooley_tukey_fft);
+    }
+
+    pub fn simd_cooley_tukey_f64(&mut self, input: &[f64], output: &mut [f64]) {
+        self.transform_f64(
+            input,
+            output,
+            crate::simd_cooley_tukey::simd_cooley_tukey_fft,
+        );
+    }
+
+    pub fn simd_cooley_tukey2_f64(&mut self, input: &[f64], output: &mut [f64]) {
+        self.transform_f64(
+            input,
+            output,
+            crate::simd_cooley_tukey2::simd_cooley_tukey_fft2,
+        );
+    }
+
+    // Also converts to and from `Complex4`, so enable SIMD for the whole method
+    #[cfg_attr(
+        all(target_arch = "wasm32", feature = "simd128"),
+        target_feature(enable = "simd128")
+    )]
+    pub fn simd_cooley_tukey3_f64(&mut self, input: &[f64], output: &mut [f64]) {
+        assert_eq!(input.len(), output.len() * 2);
+        assert!(crate::is_power_of_2(input.len()));
+
+        self.input_buffer_x4_f64
+            .resize(input.len(), Complex4::zero());
+        self.output_buffer_x4_f64
+            .resize(input.len(), Complex4::zero());
+
+        for (x, &r) in self.input_buffer_x4_f64.iter_mut().zip(input) {
+            *x = Complex4::real(r);
+        }
+
+        crate::simd_cooley_tukey3::simd_cooley_tukey_fft3(
+            &self.input_buffer_x4_f64,
+            &mut self.output_buffer_x4_f64,
+        );
+
+        for (y, x) in output.iter_mut().zip(&self.output_buffer_x4_f64) {
+            *y = x.norm().log10();
+        }
+    }
+}
+
+/// Fixed-point transforms of integer PCM, taking `Int16Array`s and `Int32Array`s.
+#[wasm_bindgen]
+impl WasmFft {
+    /// [`crate::fixed::fft_q15`] of 16-bit samples. `output` is in the same units as
+    /// [`WasmFft::cooley_tukey`] on the samples scaled to `[-1, 1)`.
+    pub fn fft_q15(&mut self, input: &[i16], output: &mut [f32]) {
+        assert_eq!(input.len(), output.len() * 2);
+        assert!(crate::is_power_of_2(input.len()));
+
+        self.input_buffer_q15.resize(input.len(), Complex::zero());
+        self.output_buffer_q15.resize(input.len(), Complex::zero());
+
+        for (x, &r) in self.input_buffer_q15.iter_mut().zip(input) {
+            *x = Complex::new(r, 0);
+        }
+
+        let exponent = crate::fixed::fft_q15(&self.input_buffer_q15, &mut self.output_buffer_q15);
+
+        let scale = 2f32.powi(exponent as i32 - 15);
+        for (y, x) in output.iter_mut().zip(&self.output_buffer_q15) {
+            *y = (Complex::new(x.re as f32, x.im as f32).norm() * scale).log10();
+        }
+    }
+
+    /// Same as [`WasmFft::fft_q15`], for 32-bit samples.
+    pub fn fft_q31(&mut self, input: &[i32], output: &mut [f32]) {
+        assert_eq!(input.len(), output.len() * 2);
+        assert!(crate::is_power_of_2(input.len()));
+
+        self.input_buffer_q31.resize(input.len(), Complex::zero());
+        self.output_buffer_q31.resize(input.len(), Complex::zero());
+
+        for (x, &r) in self.input_buffer_q31.iter_mut().zip(input) {
+            *x = Complex::new(r, 0);
+        }
+
+        let exponent = crate::fixed::fft_q31(&self.input_buffer_q31, &mut self.output_buffer_q31);
+
+        let scale = 2f64.powi(exponent as i32 - 31);
+        for (y, x) in output.iter_mut().zip(&self.output_buffer_q31) {
+            *y = (Complex::new(x.re as f64, x.im as f64).norm() * scale).log10() as f32;
+        }
+    }
+}
+
+/// Transforms across the thread pool, only in builds with the `parallel` feature.
+#[cfg(feature = "parallel")]
+#[wasm_bindgen]
+impl WasmFft {
+    /// Same as [`WasmFft::cooley_tukey`], on [`crate::parallel::parallel_fft`]. Only faster than
+    /// [`WasmFft::fft`] above [`crate::parallel::CHUNK`] points.
+    pub fn parallel_fft(&mut self, input: &[f32], output: &mut [f32]) {
+        assert_eq!(input.len(), output.len() * 2);
+        assert!(crate::is_power_of_2(input.len()));
+
+        self.input_buffer.resize(input.len(), Complex::zero());
+        self.output_buffer.resize(input.len(), Complex::zero());
+
+        for (x, &r) in self.input_buffer.iter_mut().zip(input) {
+            *x = Complex::new(r, 0.);
+        }
+
+        crate::parallel::parallel_fft(&self.input_buffer, &mut self.output_buffer);
+
+        for (y, x) in output.iter_mut().zip(&self.output_buffer) {
+            *y = x.norm().log10();
+        }
+    }
+}
+
+impl WasmFft {
+    /// Transform `input` as real samples with `fft`, and write `log10 |X|` of the first half of
+    /// the spectrum to `output`, like the `f32` methods.
+    fn transform_f64(&mut self, input: &[f64], output: &mut [f64], fft: FftFn<f64>) {
+        assert_eq!(input.len(), output.len() * 2);
+        assert!(crate::is_power_of_2(input.len()));
+
+        self.input_buffer_f64.resize(input.len(), Complex::zero());
+        self.output_buffer_f64.resize(input.len(), Complex::zero());
+
+        for (x, &r) in self.input_buffer_f64.iter_mut().zip(input) {
+            *x = Complex::new(r, 0.);
+        }
+
+        fft(&self.input_buffer_f64, &mut self.output_buffer_f64);
+
+        for (y, x) in output.iter_mut().zip(&self.output_buffer_f64) {
+            *y = x.norm().log10();
+        }
+    }
+}
+
+/// Kernels on relaxed-simd multiply-adds, only in builds with the `relaxed-simd` feature.
+#[cfg(all(target_arch = "wasm32", feature = "relaxed-simd"))]
+#[wasm_bindgen]
+impl WasmFft {
+    /// Same as [`WasmFft::simd_cooley_tukey2`], on [`Relaxed`].
+    pub fn simd_cooley_tukey2_relaxed(&mut self, input: &[f32], output: &mut [f32]) {
+        assert_eq!(input.len(), output.len() * 2);
+        assert!(crate::is_power_of_2(input.len()));
+
+        self.input_buffer.resize(input.len(), Complex::zero());
+        self.output_buffer.resize(input.len(), Complex::zero());
+
+        for (i, &r) in input.iter().enumerate() {
+            self.input_buffer[i].re = r;
+        }
+
+        crate::simd_cooley_tukey2::simd_cooley_tukey_fft2_relaxed(
+            &self.input_buffer,
+            &mut self.output_buffer,
+        );
+
+        (0..output.len()).for_each(|i| {
+            output[i] = (self.output_buffer[i].norm()).log10();
+        });
+    }
+
+    /// Same as [`WasmFft::simd_cooley_tukey3`], on [`Relaxed`].
+    #[target_feature(enable = "simd128,relaxed-simd")]
+    pub fn simd_cooley_tukey3_relaxed(&mut self, input: &[f32], output: &mut [f32]) {
+        assert_eq!(input.len(), output.len() * 2);
+        assert!(crate::is_power_of_2(input.len()));
+
+        self.input_buffer_x4_relaxed
+            .resize(input.len(), Complex4::zero());
+        self.output_buffer_x4_relaxed
+            .resize(input.len(), Complex4::zero());
+
+        for (i, &r) in input.iter().enumerate() {
+            self.input_buffer_x4_relaxed[i] = Complex4::real(r);
+        }
+
+        crate::simd_cooley_tukey3::simd_cooley_tukey_fft3_with(
+            &self.input_buffer_x4_relaxed,
+            &mut self.output_buffer_x4_relaxed,
+        );
 
-        true
+        (0..output.len()).for_each(|i| {
+            output[i] = (self.output_buffer_x4_relaxed[i].norm()).log10();
+        });
     }
 }
 
@@ -191,3 +666,126 @@ impl Default for WasmFft {
         Self::new()
     }
 }
+
+#[test]
+fn test_lib_fft_shared() {
+    let mut input = vec![0.; 256];
+    crate::siggen::oscillator(Waveform::Sawtooth, 440., 8000., &mut input);
+
+    let mut fft = WasmFft::new();
+    let mut expected = vec![0.; input.len() / 2];
+    fft.lib_fft(&input, &mut expected);
+
+    fft.resize_shared(input.len());
+    assert_eq!(fft.shared_input_len(), 256);
+    assert_eq!(fft.shared_output_len(), 128);
+
+    let ptr = fft.shared_input_ptr();
+    // SAFETY: the shared input buffer holds `shared_input_len()` samples.
+    unsafe { std::slice::from_raw_parts_mut(ptr, input.len()) }.copy_from_slice(&input);
+
+    fft.lib_fft_shared();
+
+    let ptr = fft.shared_output_ptr();
+    // SAFETY: the shared output buffer holds `shared_output_len()` bins.
+    let output = unsafe { std::slice::from_raw_parts(ptr, expected.len()) };
+    assert_eq!(output, expected.as_slice());
+}
+
+#[test]
+fn test_process_batch() {
+    let frame_len = 64;
+    let hop = 16;
+    let mut input = vec![0.; 300];
+    crate::siggen::multitone(&[1234., 5678.], 16000., &mut input);
+
+    let frames = WasmFft::batch_frame_count(input.len(), frame_len, hop);
+    assert_eq!(frames, 15);
+
+    let mut fft = WasmFft::new();
+    let mut batch = vec![0.; frames * frame_len / 2];
+    fft.process_batch(&input, frame_len, hop, &mut batch);
+
+    let mut single = vec![0.; frame_len / 2];
+    for (frame, row) in batch.chunks_exact(frame_len / 2).enumerate() {
+        let start = frame * hop;
+        fft.lib_fft(&input[start..start + frame_len], &mut single);
+        approx::assert_abs_diff_eq!(row, single.as_slice(), epsilon = 1e-5);
+    }
+}
+
+#[test]
+fn test_f64_methods() {
+    let mut input = vec![0.; 512];
+    crate::siggen::multitone(&[1000., 3000.], 16000., &mut input);
+
+    let mut fft = WasmFft::new();
+    let mut expected = vec![0.; input.len() / 2];
+    fft.cooley_tukey(&input, &mut expected);
+
+    let input: Vec<f64> = input.iter().map(|&x| x as f64).collect();
+    let mut output = vec![0.; input.len() / 2];
+
+    let normalize = (input.len() as f64).sqrt().log10();
+    fft.lib_fft_f64(&input, &mut output);
+    output.iter_mut().for_each(|y| *y += normalize);
+
+    type Method = fn(&mut WasmFft, &[f64], &mut [f64]);
+    let methods: [Method; 5] = [
+        WasmFft::dft_f64,
+        WasmFft::cooley_tukey_f64,
+        WasmFft::simd_cooley_tukey_f64,
+        WasmFft::simd_cooley_tukey2_f64,
+        WasmFft::simd_cooley_tukey3_f64,
+    ];
+    let mut outputs = vec![output];
+    for method in methods {
+        let mut output = vec![0.; input.len() / 2];
+        method(&mut fft, &input, &mut output);
+        outputs.push(output);
+    }
+
+    // Bins with energy match the f32 transform, in the same units
+    for output in &outputs {
+        for (&y, &e) in output.iter().zip(&expected) {
+            if e > -1. {
+                approx::assert_abs_diff_eq!(y, e as f64, epsilon = 1e-3);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_fixed_point_methods() {
+    let mut input = vec![0.; 512];
+    crate::siggen::multitone(&[1000., 3000.], 16000., &mut input);
+
+    let mut fft = WasmFft::new();
+    let mut expected = vec![0.; input.len() / 2];
+    fft.cooley_tukey(&input, &mut expected);
+
+    let pcm16: Vec<i16> = input.iter().map(|&x| (x * 32767.).round() as i16).collect();
+    let mut output16 = vec![0.; input.len() / 2];
+    fft.fft_q15(&pcm16, &mut output16);
+
+    let pcm32: Vec<i32> = input
+        .iter()
+        .map(|&x| (x as f64 * i32::MAX as f64).round() as i32)
+        .collect();
+    let mut output32 = vec![0.; input.len() / 2];
+    fft.fft_q31(&pcm32, &mut output32);
+
+    for output in [output16, output32] {
+        for (&y, &e) in output.iter().zip(&expected) {
+            if e > -1. {
+                approx::assert_abs_diff_eq!(y, e, epsilon = 1e-3);
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "is_power_of_2(len)")]
+fn test_resize_shared_not_power_of_2() {
+    WasmFft::new().resize_shared(1000);
+}
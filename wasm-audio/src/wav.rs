@@ -0,0 +1,518 @@
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+const WAVE_FORMAT_PCM: u16 = 0x0001;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xfffe;
+
+/// Tail of the `KSDATAFORMAT_SUBTYPE_*` GUIDs; the first 2 bytes are the format tag.
+const SUBFORMAT_GUID_TAIL: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xaa, 0x00, 0x38, 0x9b, 0x71,
+];
+
+/// Sample encoding used when writing a WAV file.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    Pcm8,
+    Pcm16,
+    Pcm24,
+    Pcm32,
+    Float32,
+    Float64,
+}
+
+impl SampleFormat {
+    fn bits(self) -> u16 {
+        match self {
+            SampleFormat::Pcm8 => 8,
+            SampleFormat::Pcm16 => 16,
+            SampleFormat::Pcm24 => 24,
+            SampleFormat::Pcm32 | SampleFormat::Float32 => 32,
+            SampleFormat::Float64 => 64,
+        }
+    }
+
+    fn tag(self) -> u16 {
+        match self {
+            SampleFormat::Float32 | SampleFormat::Float64 => WAVE_FORMAT_IEEE_FLOAT,
+            _ => WAVE_FORMAT_PCM,
+        }
+    }
+
+    fn from_tag(tag: u16, bits: u16) -> Result<Self, WavError> {
+        match (tag, bits) {
+            (WAVE_FORMAT_PCM, 8) => Ok(SampleFormat::Pcm8),
+            (WAVE_FORMAT_PCM, 16) => Ok(SampleFormat::Pcm16),
+            (WAVE_FORMAT_PCM, 24) => Ok(SampleFormat::Pcm24),
+            (WAVE_FORMAT_PCM, 32) => Ok(SampleFormat::Pcm32),
+            (WAVE_FORMAT_IEEE_FLOAT, 32) => Ok(SampleFormat::Float32),
+            (WAVE_FORMAT_IEEE_FLOAT, 64) => Ok(SampleFormat::Float64),
+            (WAVE_FORMAT_PCM | WAVE_FORMAT_IEEE_FLOAT, _) => {
+                Err(WavError::UnsupportedBitDepth(bits))
+            }
+            _ => Err(WavError::UnsupportedFormat(tag)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WavError {
+    NotRiff,
+    NotWave,
+    Truncated,
+    MissingFmt,
+    MissingData,
+    NoChannels,
+    UnsupportedFormat(u16),
+    UnsupportedBitDepth(u16),
+    /// The channel count, byte rate or data length doesn't fit its header field.
+    TooLarge,
+}
+
+impl fmt::Display for WavError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WavError::NotRiff => write!(f, "not a RIFF file"),
+            WavError::NotWave => write!(f, "RIFF file is not WAVE"),
+            WavError::Truncated => write!(f, "file is truncated"),
+            WavError::MissingFmt => write!(f, "missing 'fmt ' chunk"),
+            WavError::MissingData => write!(f, "missing 'data' chunk"),
+            WavError::NoChannels => write!(f, "file has no channels"),
+            WavError::UnsupportedFormat(tag) => write!(f, "unsupported format tag {tag:#06x}"),
+            WavError::UnsupportedBitDepth(bits) => write!(f, "unsupported bit depth {bits}"),
+            WavError::TooLarge => write!(f, "audio is too large for a WAV file"),
+        }
+    }
+}
+
+impl std::error::Error for WavError {}
+
+/// Planar audio, one `Vec<f32>` per channel, with samples in `[-1, 1]`.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wav {
+    sample_rate: u32,
+    channels: Vec<Vec<f32>>,
+}
+
+impl Wav {
+    pub fn from_channels(sample_rate: u32, channels: Vec<Vec<f32>>) -> Self {
+        assert!(!channels.is_empty());
+        assert!(channels.iter().all(|c| c.len() == channels[0].len()));
+
+        Self {
+            sample_rate,
+            channels,
+        }
+    }
+
+    pub fn channels(&self) -> &[Vec<f32>] {
+        &self.channels
+    }
+
+    pub fn into_channels(self) -> Vec<Vec<f32>> {
+        self.channels
+    }
+
+    /// Decode a RIFF/WAVE file (PCM 8/16/24/32-bit, IEEE float, or `WAVE_FORMAT_EXTENSIBLE`).
+    pub fn decode(bytes: &[u8]) -> Result<Self, WavError> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" {
+            return Err(WavError::NotRiff);
+        }
+        if &bytes[8..12] != b"WAVE" {
+            return Err(WavError::NotWave);
+        }
+
+        let mut fmt = None;
+        let mut data = None;
+        let mut rest = &bytes[12..];
+
+        while rest.len() >= 8 {
+            let id = &rest[0..4];
+            let len = read_u32(&rest[4..8]) as usize;
+            let body = 8usize
+                .checked_add(len)
+                .and_then(|end| rest.get(8..end))
+                .ok_or(WavError::Truncated)?;
+
+            match id {
+                b"fmt " => fmt = Some(Fmt::parse(body)?),
+                b"data" => data = Some(body),
+                _ => {}
+            }
+
+            // Chunks are padded to an even length
+            rest = rest.get(8 + len + (len & 1)..).unwrap_or(&[]);
+        }
+
+        let fmt = fmt.ok_or(WavError::MissingFmt)?;
+        let data = data.ok_or(WavError::MissingData)?;
+
+        let num_channels = fmt.channels as usize;
+        let sample_size = fmt.format.bits() as usize / 8;
+        let block_align = num_channels * sample_size;
+        let frames = data.len() / block_align;
+
+        let mut channels = vec![Vec::with_capacity(frames); num_channels];
+
+        for frame in data.chunks_exact(block_align) {
+            for (channel, sample) in channels.iter_mut().zip(frame.chunks_exact(sample_size)) {
+                channel.push(decode_sample(sample, fmt.format));
+            }
+        }
+
+        Ok(Self {
+            sample_rate: fmt.sample_rate,
+            channels,
+        })
+    }
+
+    /// Encode as a RIFF/WAVE file, which holds at most 4 GiB and 65535 channels.
+    ///
+    /// `WAVE_FORMAT_EXTENSIBLE` is used for more than 2 channels or more than 16 bits, as
+    /// recommended by the format specification.
+    pub fn encode(&self, format: SampleFormat) -> Result<Vec<u8>, WavError> {
+        let header = Header::new(
+            self.channels.len(),
+            self.num_frames(),
+            self.sample_rate,
+            format,
+        )?;
+        let bits = format.bits();
+        let extensible = header.num_channels > 2 || bits > 16;
+        let tag = if extensible {
+            WAVE_FORMAT_EXTENSIBLE
+        } else {
+            format.tag()
+        };
+
+        let mut out =
+            Vec::with_capacity(28 + header.fmt_len as usize + header.data_len as usize + 1);
+
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&header.riff_len.to_le_bytes());
+        out.extend_from_slice(b"WAVE");
+
+        out.extend_from_slice(b"fmt ");
+        out.extend_from_slice(&header.fmt_len.to_le_bytes());
+        out.extend_from_slice(&tag.to_le_bytes());
+        out.extend_from_slice(&header.num_channels.to_le_bytes());
+        out.extend_from_slice(&self.sample_rate.to_le_bytes());
+        out.extend_from_slice(&header.byte_rate.to_le_bytes());
+        out.extend_from_slice(&header.block_align.to_le_bytes());
+        out.extend_from_slice(&bits.to_le_bytes());
+        if extensible {
+            out.extend_from_slice(&22_u16.to_le_bytes());
+            out.extend_from_slice(&bits.to_le_bytes());
+            // No speaker positions assigned
+            out.extend_from_slice(&0_u32.to_le_bytes());
+            out.extend_from_slice(&format.tag().to_le_bytes());
+            out.extend_from_slice(&SUBFORMAT_GUID_TAIL);
+        }
+
+        out.extend_from_slice(b"data");
+        out.extend_from_slice(&header.data_len.to_le_bytes());
+        for i in 0..self.num_frames() {
+            for channel in &self.channels {
+                encode_sample(channel[i], format, &mut out);
+            }
+        }
+        if header.data_len & 1 == 1 {
+            out.push(0);
+        }
+
+        Ok(out)
+    }
+}
+
+#[wasm_bindgen]
+impl Wav {
+    #[wasm_bindgen(js_name = decode)]
+    pub fn decode_js(bytes: &[u8]) -> Result<Wav, JsError> {
+        Self::decode(bytes).map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            channels: Vec::new(),
+        }
+    }
+
+    /// Append a channel. All channels must have the same length.
+    pub fn push_channel(&mut self, samples: Vec<f32>) {
+        if let Some(first) = self.channels.first() {
+            assert_eq!(first.len(), samples.len());
+        }
+        self.channels.push(samples);
+    }
+
+    /// Encode as a RIFF/WAVE file, throwing if it's too large; see [`Wav::encode`].
+    #[wasm_bindgen(js_name = encode)]
+    pub fn encode_js(&self, format: SampleFormat) -> Result<Vec<u8>, JsError> {
+        self.encode(format)
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.channels.first().map_or(0, Vec::len)
+    }
+
+    pub fn channel(&self, index: usize) -> Vec<f32> {
+        self.channels[index].clone()
+    }
+}
+
+/// The sizes and rates written to the header, checked against their fields' widths.
+#[derive(Debug, PartialEq, Eq)]
+struct Header {
+    num_channels: u16,
+    block_align: u16,
+    byte_rate: u32,
+    fmt_len: u32,
+    data_len: u32,
+    /// Everything after the RIFF chunk header, including the data chunk's padding byte
+    riff_len: u32,
+}
+
+impl Header {
+    fn new(
+        channels: usize,
+        frames: usize,
+        sample_rate: u32,
+        format: SampleFormat,
+    ) -> Result<Self, WavError> {
+        if channels == 0 {
+            return Err(WavError::NoChannels);
+        }
+
+        let num_channels = u16::try_from(channels).map_err(|_| WavError::TooLarge)?;
+        let bits = format.bits();
+        let block_align = num_channels
+            .checked_mul(bits / 8)
+            .ok_or(WavError::TooLarge)?;
+        let byte_rate = sample_rate
+            .checked_mul(block_align as u32)
+            .ok_or(WavError::TooLarge)?;
+        let fmt_len = if num_channels > 2 || bits > 16 {
+            40
+        } else {
+            16
+        };
+        let data_len = frames
+            .checked_mul(block_align as usize)
+            .and_then(|len| u32::try_from(len).ok())
+            .ok_or(WavError::TooLarge)?;
+        let riff_len = (20 + fmt_len + (data_len & 1))
+            .checked_add(data_len)
+            .ok_or(WavError::TooLarge)?;
+
+        Ok(Self {
+            num_channels,
+            block_align,
+            byte_rate,
+            fmt_len,
+            data_len,
+            riff_len,
+        })
+    }
+}
+
+struct Fmt {
+    format: SampleFormat,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Fmt {
+    fn parse(body: &[u8]) -> Result<Self, WavError> {
+        if body.len() < 16 {
+            return Err(WavError::Truncated);
+        }
+
+        let mut tag = read_u16(&body[0..2]);
+        let channels = read_u16(&body[2..4]);
+        let sample_rate = read_u32(&body[4..8]);
+        let bits = read_u16(&body[14..16]);
+
+        if tag == WAVE_FORMAT_EXTENSIBLE {
+            let subformat = body.get(24..40).ok_or(WavError::Truncated)?;
+            if subformat[2..] != SUBFORMAT_GUID_TAIL {
+                return Err(WavError::UnsupportedFormat(tag));
+            }
+            tag = read_u16(&subformat[0..2]);
+        }
+
+        if channels == 0 {
+            return Err(WavError::NoChannels);
+        }
+
+        Ok(Self {
+            format: SampleFormat::from_tag(tag, bits)?,
+            channels,
+            sample_rate,
+        })
+    }
+}
+
+fn read_u16(b: &[u8]) -> u16 {
+    u16::from_le_bytes([b[0], b[1]])
+}
+
+fn read_u32(b: &[u8]) -> u32 {
+    u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+}
+
+fn decode_sample(b: &[u8], format: SampleFormat) -> f32 {
+    match format {
+        SampleFormat::Pcm8 => (b[0] as f32 - 128.) / 128.,
+        SampleFormat::Pcm16 => i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.,
+        // Shift into the top of an i32 to sign-extend
+        SampleFormat::Pcm24 => i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2147483648.,
+        SampleFormat::Pcm32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.,
+        SampleFormat::Float32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        SampleFormat::Float64 => {
+            f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32
+        }
+    }
+}
+
+fn encode_sample(x: f32, format: SampleFormat, out: &mut Vec<u8>) {
+    fn quantize(x: f32, bits: u16) -> i64 {
+        let full_scale = (1_i64 << (bits - 1)) as f64;
+        ((x as f64 * full_scale).round() as i64).clamp(-full_scale as i64, full_scale as i64 - 1)
+    }
+
+    match format {
+        SampleFormat::Pcm8 => out.push((quantize(x, 8) + 128) as u8),
+        SampleFormat::Pcm16 => out.extend_from_slice(&(quantize(x, 16) as i16).to_le_bytes()),
+        SampleFormat::Pcm24 => out.extend_from_slice(&(quantize(x, 24) as i32).to_le_bytes()[..3]),
+        SampleFormat::Pcm32 => out.extend_from_slice(&(quantize(x, 32) as i32).to_le_bytes()),
+        SampleFormat::Float32 => out.extend_from_slice(&x.to_le_bytes()),
+        SampleFormat::Float64 => out.extend_from_slice(&(x as f64).to_le_bytes()),
+    }
+}
+
+#[test]
+fn test_wav_decode_pcm16_stereo() {
+    #[rustfmt::skip]
+    let bytes = [
+        b'R', b'I', b'F', b'F', 44, 0, 0, 0, b'W', b'A', b'V', b'E',
+        b'f', b'm', b't', b' ', 16, 0, 0, 0,
+        1, 0, 2, 0, 0x44, 0xac, 0, 0, 0x10, 0xb1, 2, 0, 4, 0, 16, 0,
+        // Unknown chunks with odd length are skipped with their padding
+        b'L', b'I', b'S', b'T', 1, 0, 0, 0, 0xff, 0,
+        b'd', b'a', b't', b'a', 8, 0, 0, 0,
+        0x00, 0x40, 0x00, 0xc0,
+        0xff, 0x7f, 0x00, 0x80,
+    ];
+
+    let wav = Wav::decode(&bytes).unwrap();
+    assert_eq!(wav.sample_rate(), 44100);
+    assert_eq!(
+        wav.channels(),
+        &[vec![0.5, 32767. / 32768.], vec![-0.5, -1.]]
+    );
+}
+
+#[test]
+fn test_wav_round_trip() {
+    let left: Vec<f32> = (0..100).map(|i| (i as f32 * 0.1).sin() * 0.9).collect();
+    let channels = vec![
+        left.clone(),
+        left.iter().map(|x| -x).collect(),
+        vec![0.25; 100],
+    ];
+    let wav = Wav::from_channels(48000, channels);
+
+    for (format, epsilon) in [
+        (SampleFormat::Pcm8, 1. / 128.),
+        (SampleFormat::Pcm16, 1. / 32768.),
+        (SampleFormat::Pcm24, 1e-6),
+        (SampleFormat::Pcm32, 1e-6),
+        (SampleFormat::Float32, 0.),
+        (SampleFormat::Float64, 0.),
+    ] {
+        let decoded = Wav::decode(&wav.encode(format).unwrap()).unwrap();
+        assert_eq!(decoded.sample_rate(), 48000);
+        assert_eq!(decoded.num_channels(), 3);
+
+        for (a, b) in decoded.channels().iter().zip(wav.channels()) {
+            approx::assert_abs_diff_eq!(a.as_slice(), b.as_slice(), epsilon = epsilon);
+        }
+    }
+}
+
+#[test]
+fn test_wav_errors() {
+    assert_eq!(Wav::decode(b"RIFX"), Err(WavError::NotRiff));
+    assert_eq!(Wav::decode(b"RIFF\0\0\0\0AVI "), Err(WavError::NotWave));
+    assert_eq!(Wav::decode(b"RIFF\0\0\0\0WAVE"), Err(WavError::MissingFmt));
+
+    let mut bytes = Wav::from_channels(8000, vec![vec![0.; 4]])
+        .encode(SampleFormat::Pcm16)
+        .unwrap();
+    bytes[20] = 0x55;
+    assert_eq!(Wav::decode(&bytes), Err(WavError::UnsupportedFormat(0x55)));
+
+    bytes[20] = 1;
+    bytes.truncate(bytes.len() - 1);
+    assert_eq!(Wav::decode(&bytes), Err(WavError::Truncated));
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_wav_huge_chunk() {
+    // A chunk length of `u32::MAX` would overflow the end offset on wasm32
+    let mut bytes = b"RIFF\0\0\0\0WAVEdata".to_vec();
+    bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+    bytes.extend_from_slice(&[0; 16]);
+    assert_eq!(Wav::decode(&bytes), Err(WavError::Truncated));
+}
+
+#[test]
+fn test_wav_header_limits() {
+    use SampleFormat::*;
+
+    let header = |channels, frames, sample_rate, format| {
+        Header::new(channels, frames, sample_rate, format).map(|h| h.riff_len)
+    };
+
+    assert_eq!(Wav::new(8000).encode(Pcm16), Err(WavError::NoChannels));
+    assert_eq!(header(65535, 0, 8000, Pcm8), Ok(60));
+    assert_eq!(header(65536, 0, 8000, Pcm8), Err(WavError::TooLarge));
+    // A block of 65535 * 2 bytes
+    assert_eq!(header(65535, 0, 8000, Pcm16), Err(WavError::TooLarge));
+    assert_eq!(header(1, 0, u32::MAX / 8, Float64), Ok(60));
+    assert_eq!(
+        header(1, 0, u32::MAX / 8 + 1, Float64),
+        Err(WavError::TooLarge)
+    );
+
+    // The RIFF chunk, 36 bytes of headers and 16-bit samples, reaches `u32::MAX - 1`
+    let frames = (u32::MAX as usize - 36) / 2;
+    assert_eq!(header(1, frames, 8000, Pcm16), Ok(u32::MAX - 1));
+    assert_eq!(header(1, frames + 1, 8000, Pcm16), Err(WavError::TooLarge));
+    // An odd data length takes a padding byte, so `u32::MAX - 36` bytes of data don't fit
+    assert_eq!(
+        header(1, u32::MAX as usize - 38, 8000, Pcm8),
+        Ok(u32::MAX - 1)
+    );
+    assert_eq!(
+        header(1, u32::MAX as usize - 36, 8000, Pcm8),
+        Err(WavError::TooLarge)
+    );
+    assert_eq!(header(1, usize::MAX, 8000, Pcm16), Err(WavError::TooLarge));
+}
@@ -0,0 +1,123 @@
+use rustfft::{num_complex::Complex, num_traits::Zero};
+use wasm_bindgen::prelude::*;
+
+use crate::chirp_z::ChirpZ;
+
+/// Zoom FFT: `points` evenly spaced frequencies from `f1` to `f2` Hz, of `input_len`-sample
+/// windows of any length.
+///
+/// For a close look at a narrow band, such as mains hum, at a finer spacing than an FFT of the
+/// same window would give. The spacing doesn't add resolution beyond `sample_rate / input_len`,
+/// but locates peaks within it. The transform itself doesn't allocate after construction; what
+/// does is wasm-bindgen, copying the window and the spectrum between JS and the wasm heap.
+#[wasm_bindgen]
+pub struct WasmZoomFft {
+    czt: ChirpZ<f32>,
+    f1: f32,
+    step: f32,
+    input: Vec<Complex<f32>>,
+    output: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+}
+
+#[wasm_bindgen]
+impl WasmZoomFft {
+    pub fn new(input_len: usize, points: usize, f1: f32, f2: f32, sample_rate: f32) -> Self {
+        crate::set_panic_hook();
+
+        let czt = ChirpZ::zoom(input_len, points, f1 as f64, f2 as f64, sample_rate as f64);
+        let scratch = vec![Complex::zero(); czt.scratch_len()];
+
+        Self {
+            czt,
+            f1,
+            step: if points > 1 {
+                (f2 - f1) / (points - 1) as f32
+            } else {
+                0.
+            },
+            input: vec![Complex::zero(); input_len],
+            output: vec![Complex::zero(); points],
+            scratch,
+        }
+    }
+
+    pub fn input_len(&self) -> usize {
+        self.czt.input_len()
+    }
+
+    pub fn points(&self) -> usize {
+        self.czt.points()
+    }
+
+    /// Frequency of each point, in Hz.
+    pub fn frequencies(&self, output: &mut [f32]) {
+        assert_eq!(output.len(), self.points());
+
+        for (k, y) in output.iter_mut().enumerate() {
+            *y = self.f1 + k as f32 * self.step;
+        }
+    }
+
+    /// Transform `input_len()` real samples, and write `log10 |X|` at each point, on the same
+    /// scale as `WasmFft::lib_fft`.
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        assert_eq!(input.len(), self.input.len());
+        assert_eq!(output.len(), self.output.len());
+
+        for (y, &x) in self.input.iter_mut().zip(input) {
+            *y = Complex::new(x, 0.);
+        }
+
+        self.czt
+            .process_with_scratch(&self.input, &mut self.output, &mut self.scratch);
+
+        let normalize = 1. / (input.len() as f32).sqrt();
+        for (y, x) in output.iter_mut().zip(&self.output) {
+            *y = (x.norm() * normalize).log10();
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_zoom_fft_matches_lib_fft() {
+    // At 1024 Hz, 1024 samples put the FFT bins on whole Hz
+    let mut signal = vec![0.; 1024];
+    crate::siggen::noise(crate::siggen::NoiseColor::Pink, 3, &mut signal);
+
+    let mut expected = vec![0.; 512];
+    crate::WasmFft::new().lib_fft(&signal, &mut expected);
+
+    let mut zoom = WasmZoomFft::new(1024, 31, 40., 70., 1024.);
+    let mut actual = vec![0.; 31];
+    zoom.process(&signal, &mut actual);
+
+    approx::assert_abs_diff_eq!(actual.as_slice(), &expected[40..=70], epsilon = 1e-3);
+}
+
+#[cfg(test)]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_zoom_fft_hum() {
+    // Hum and a slightly sharp harmonic, 1 Hz resolution but located to the 0.1 Hz spacing
+    let sample_rate = 8000.;
+    let mut signal = vec![0.; 8000];
+    crate::siggen::multitone(&[50., 60.3], sample_rate, &mut signal);
+
+    let mut zoom = WasmZoomFft::new(signal.len(), 201, 45., 65., sample_rate);
+    let mut magnitudes = vec![0.; 201];
+    let mut frequencies = vec![0.; 201];
+    zoom.process(&signal, &mut magnitudes);
+    zoom.frequencies(&mut frequencies);
+
+    // The two strongest local maxima
+    let mut peaks: Vec<usize> = (1..200)
+        .filter(|&k| magnitudes[k] > magnitudes[k - 1] && magnitudes[k] > magnitudes[k + 1])
+        .collect();
+    peaks.sort_by(|&a, &b| magnitudes[b].total_cmp(&magnitudes[a]));
+
+    approx::assert_abs_diff_eq!(frequencies[peaks[0].min(peaks[1])], 50., epsilon = 1e-3);
+    approx::assert_abs_diff_eq!(frequencies[peaks[0].max(peaks[1])], 60.3, epsilon = 1e-3);
+}
@@ -0,0 +1,114 @@
+//! Every forward transform in the crate against rustfft, on random complex input at every
+//! power-of-two size, in `f32` and `f64`.
+//!
+//! Runs natively with `cargo test`, and in Node with the wasm-bindgen test runner (see the
+//! README), so the SIMD kernels are checked on each backend they are built for.
+
+use rustfft::{num_complex::Complex, FftPlanner};
+use wasm_audio::{
+    accuracy,
+    siggen::{self, NoiseColor},
+    simd::Float,
+};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const MAX_SIZE: usize = 1 << 14;
+
+/// The O(n²) DFT is only checked up to this size.
+const MAX_DFT_SIZE: usize = 1 << 10;
+
+/// Inputs per size, each with a different noise seed.
+const INPUTS: u32 = 3;
+
+fn random_input(size: usize, seed: u32) -> Vec<Complex<f32>> {
+    let mut re = vec![0.; size];
+    let mut im = vec![0.; size];
+    siggen::noise(NoiseColor::White, seed, &mut re);
+    siggen::noise(NoiseColor::White, seed.wrapping_mul(0x9e37_79b9), &mut im);
+
+    re.into_iter()
+        .zip(im)
+        .map(|(re, im)| Complex::new(re, im))
+        .collect()
+}
+
+/// `|actual - expected| / |expected|` over the whole spectrum.
+fn relative_error<T: Float>(actual: &[Complex<T>], expected: &[Complex<T>]) -> T {
+    let error = actual
+        .iter()
+        .zip(expected)
+        .fold(T::zero(), |sum, (&a, &e)| sum + (a - e).norm_sqr());
+    let energy = expected.iter().fold(T::zero(), |sum, e| sum + e.norm_sqr());
+
+    (error / energy).sqrt()
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_kernels_match_rustfft() {
+    let mut planner = FftPlanner::new();
+
+    for size in (1..).map(|s| 1 << s).take_while(|&n| n <= MAX_SIZE) {
+        let fft = planner.plan_fft_forward(size);
+
+        for seed in 1..=INPUTS {
+            let input = random_input(size, seed);
+            let mut expected = input.clone();
+            fft.process(&mut expected);
+
+            for (name, kernel) in accuracy::algorithms() {
+                if name == "dft" && size > MAX_DFT_SIZE {
+                    continue;
+                }
+
+                let mut output = vec![Complex::new(0., 0.); size];
+                kernel(&input, &mut output);
+
+                // Rounding error grows with log2(n) for the FFTs and with n for the DFT, which
+                // reaches about 1e-4 at its largest size
+                let error = relative_error(&output, &expected);
+                assert!(
+                    error < 2e-4,
+                    "{name} size {size} seed {seed}: relative error {error:e}"
+                );
+            }
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_kernels_match_rustfft_f64() {
+    let mut planner = FftPlanner::new();
+
+    for size in (1..).map(|s| 1 << s).take_while(|&n| n <= MAX_SIZE) {
+        let fft = planner.plan_fft_forward(size);
+
+        for seed in 1..=INPUTS {
+            let input: Vec<Complex<f64>> = random_input(size, seed)
+                .iter()
+                .map(|x| Complex::new(x.re.into(), x.im.into()))
+                .collect();
+            let mut expected = input.clone();
+            fft.process(&mut expected);
+
+            for (name, kernel) in accuracy::algorithms_for::<f64>() {
+                if name == "dft" && size > MAX_DFT_SIZE {
+                    continue;
+                }
+
+                let mut output = vec![Complex::new(0., 0.); size];
+                kernel(&input, &mut output);
+
+                // Same growth as in f32, with the DFT reaching about 2e-13
+                let error = relative_error(&output, &expected);
+                assert!(
+                    error < 1e-12,
+                    "{name} size {size} seed {seed}: relative error {error:e}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Property-based checks of the DFT identities for every forward transform in the crate.
+//!
+//! Signal lengths are generated as a power of two, so on failure proptest shrinks the size as
+//! well as the sample values and reports the smallest transform that breaks.
+
+use std::f32::consts::PI;
+
+use proptest::prelude::*;
+use rustfft::num_complex::Complex;
+use wasm_audio::accuracy::{self, FftFn};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test;
+
+/// Largest generated size is `2^MAX_LOG2`.
+const MAX_LOG2: u32 = 10;
+
+/// The O(n²) DFT is skipped above this size, which keeps the suite fast in debug builds.
+const MAX_DFT_SIZE: usize = 256;
+
+/// Relative L2 error allowed on each identity. The DFT is the least accurate kernel, at about
+/// 3e-5 for 256 points.
+const TOLERANCE: f32 = 5e-4;
+
+fn config() -> ProptestConfig {
+    ProptestConfig {
+        cases: 64,
+        // There is no file system to save regressions to
+        #[cfg(target_arch = "wasm32")]
+        failure_persistence: None,
+        ..ProptestConfig::default()
+    }
+}
+
+fn sample() -> impl Strategy<Value = Complex<f32>> {
+    (-1_f32..1., -1_f32..1.).prop_map(|(re, im)| Complex::new(re, im))
+}
+
+fn signal() -> impl Strategy<Value = Vec<Complex<f32>>> {
+    (1..=MAX_LOG2).prop_flat_map(|log2| prop::collection::vec(sample(), 1 << log2))
+}
+
+fn real_signal() -> impl Strategy<Value = Vec<Complex<f32>>> {
+    (1..=MAX_LOG2).prop_flat_map(|log2| {
+        prop::collection::vec((-1_f32..1.).prop_map(|re| Complex::new(re, 0.)), 1 << log2)
+    })
+}
+
+/// Non-zero scale factor with any phase.
+fn scalar() -> impl Strategy<Value = Complex<f32>> {
+    (0.5_f32..2., -PI..PI).prop_map(|(r, theta)| Complex::from_polar(r, theta))
+}
+
+/// Every algorithm able to transform `n` points in reasonable time.
+fn algorithms(n: usize) -> impl Iterator<Item = (&'static str, FftFn)> {
+    accuracy::algorithms()
+        .into_iter()
+        .filter(move |&(name, _)| name != "dft" || n <= MAX_DFT_SIZE)
+}
+
+fn forward(fft: FftFn, input: &[Complex<f32>]) -> Vec<Complex<f32>> {
+    let mut output = vec![Complex::new(0., 0.); input.len()];
+    fft(input, &mut output);
+    output
+}
+
+/// `ifft(X) = conj(fft(conj(X))) / n`
+fn inverse(fft: FftFn, input: &[Complex<f32>]) -> Vec<Complex<f32>> {
+    let n = input.len() as f32;
+    let conj: Vec<_> = input.iter().map(|x| x.conj()).collect();
+    forward(fft, &conj).iter().map(|x| x.conj() / n).collect()
+}
+
+/// `|actual - expected| / |expected|`
+fn relative_error(actual: &[Complex<f32>], expected: &[Complex<f32>]) -> f32 {
+    let error: f32 = actual
+        .iter()
+        .zip(expected)
+        .map(|(a, e)| (a - e).norm_sqr())
+        .sum();
+    let energy: f32 = expected.iter().map(|e| e.norm_sqr()).sum();
+
+    (error / energy).sqrt()
+}
+
+fn energy(x: &[Complex<f32>]) -> f32 {
+    x.iter().map(|x| x.norm_sqr()).sum()
+}
+
+proptest! {
+    #![proptest_config(config())]
+
+    /// `F(a x + b y) = a F(x) + b F(y)`
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn linearity(
+        (x, y) in signal().prop_flat_map(|x| {
+            let n = x.len();
+            (Just(x), prop::collection::vec(sample(), n))
+        }),
+        a in scalar(),
+        b in scalar(),
+    ) {
+        let combined: Vec<_> = x.iter().zip(&y).map(|(x, y)| a * x + b * y).collect();
+
+        for (name, fft) in algorithms(x.len()) {
+            let expected: Vec<_> = forward(fft, &x)
+                .iter()
+                .zip(forward(fft, &y))
+                .map(|(fx, fy)| a * fx + b * fy)
+                .collect();
+            let error = relative_error(&forward(fft, &combined), &expected);
+            prop_assert!(error < TOLERANCE, "{name}: relative error {error:e}");
+        }
+    }
+
+    /// `n Σ|x|² = Σ|X|²`
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn parseval(x in signal()) {
+        let expected = x.len() as f32 * energy(&x);
+
+        for (name, fft) in algorithms(x.len()) {
+            let actual = energy(&forward(fft, &x));
+            let error = (actual - expected).abs() / expected;
+            prop_assert!(error < TOLERANCE, "{name}: relative error {error:e}");
+        }
+    }
+
+    /// `x[j - s]` transforms to `X[k] e^(-2πi k s / n)`
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn time_shift((x, shift) in signal().prop_flat_map(|x| {
+        let n = x.len();
+        (Just(x), 0..n)
+    })) {
+        let n = x.len();
+        let mut shifted = x.clone();
+        shifted.rotate_right(shift);
+
+        for (name, fft) in algorithms(n) {
+            let expected: Vec<_> = forward(fft, &x)
+                .iter()
+                .enumerate()
+                .map(|(k, fx)| {
+                    let phase = -2. * PI * ((k * shift) % n) as f32 / n as f32;
+                    fx * Complex::from_polar(1., phase)
+                })
+                .collect();
+            let error = relative_error(&forward(fft, &shifted), &expected);
+            prop_assert!(error < TOLERANCE, "{name}: relative error {error:e}");
+        }
+    }
+
+    /// `x[j] e^(2πi m j / n)` transforms to `X[k - m]`
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn modulation((x, m) in signal().prop_flat_map(|x| {
+        let n = x.len();
+        (Just(x), 0..n)
+    })) {
+        let n = x.len();
+        let modulated: Vec<_> = x
+            .iter()
+            .enumerate()
+            .map(|(j, x)| x * Complex::from_polar(1., 2. * PI * ((m * j) % n) as f32 / n as f32))
+            .collect();
+
+        for (name, fft) in algorithms(n) {
+            let mut expected = forward(fft, &x);
+            expected.rotate_right(m);
+            let error = relative_error(&forward(fft, &modulated), &expected);
+            prop_assert!(error < TOLERANCE, "{name}: relative error {error:e}");
+        }
+    }
+
+    /// Real input has `X[n - k] = conj(X[k])`
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn conjugate_symmetry(x in real_signal()) {
+        let n = x.len();
+
+        for (name, fft) in algorithms(n) {
+            let spectrum = forward(fft, &x);
+            let mirrored: Vec<_> = (0..n).map(|k| spectrum[(n - k) % n].conj()).collect();
+            let error = relative_error(&spectrum, &mirrored);
+            prop_assert!(error < TOLERANCE, "{name}: relative error {error:e}");
+        }
+    }
+
+    /// `inverse(forward(x)) = x`
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn round_trip(x in signal()) {
+        for (name, fft) in algorithms(x.len()) {
+            let error = relative_error(&inverse(fft, &forward(fft, &x)), &x);
+            prop_assert!(error < TOLERANCE, "{name}: relative error {error:e}");
+        }
+    }
+}
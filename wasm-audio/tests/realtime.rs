@@ -0,0 +1,245 @@
+//! The real-time paths must not allocate once set up: [`Plan::process`], the single-bin trackers,
+//! the zoom FFT, the constant-Q analyser, the octave band analysers, the weighting filters and
+//! [`StreamProcessor::process`] are run under a global allocator that counts every allocation made
+//! on the current thread.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use rustfft::num_complex::Complex;
+use wasm_audio::{
+    siggen::{self, NoiseColor},
+    Algorithm, FrequencyWeighting, Plan, StreamProcessor, WasmCqt, WasmGoertzel, WasmOctaveBands,
+    WasmOctaveFilterBank, WasmSlidingDft, WasmWeightingCurve, WasmWeightingFilter, WasmZoomFft,
+    Workspace, RENDER_QUANTUM,
+};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test;
+
+struct CountingAllocator;
+
+thread_local! {
+    // Per thread, so tests running in parallel don't count each other's allocations
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count() {
+    // `try_with` as this can be called while the thread-local is being destroyed
+    let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Number of allocations `f` makes on this thread.
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+const ALGORITHMS: [Algorithm; 7] = [
+    Algorithm::Lib,
+    Algorithm::Dft,
+    Algorithm::CooleyTukey,
+    Algorithm::SimdCooleyTukey,
+    Algorithm::SimdCooleyTukey2,
+    Algorithm::SimdCooleyTukey3,
+    Algorithm::Auto,
+];
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_allocator_counts() {
+    assert_eq!(allocations(|| drop(vec![0u8; 16])), 1);
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_plan_process_does_not_allocate() {
+    for len in [16, 256, 4096] {
+        let mut input = vec![0.; len];
+        siggen::noise(NoiseColor::White, 1, &mut input);
+        let complex_input: Vec<_> = input.iter().map(|&x| Complex::new(x, -x)).collect();
+        let mut output = vec![0.; len / 2];
+        let mut complex_output = vec![Complex::new(0., 0.); len];
+
+        for algorithm in ALGORITHMS {
+            if algorithm == Algorithm::Dft && len > 256 {
+                continue;
+            }
+
+            let plan = Plan::new(algorithm, len);
+            let mut workspace = Workspace::new(&plan);
+
+            let n = allocations(|| {
+                for _ in 0..3 {
+                    plan.process(&mut workspace, &input, &mut output);
+                    plan.process_complex(&mut workspace, &complex_input, &mut complex_output);
+                    plan.process_shared(&mut workspace);
+                }
+            });
+            assert_eq!(n, 0, "{algorithm:?} at {len} points");
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_stream_processor_does_not_allocate() {
+    let mut signal = vec![0.; 16 * RENDER_QUANTUM];
+    siggen::noise(NoiseColor::Pink, 2, &mut signal);
+
+    let mut stream = StreamProcessor::new(1024, 256);
+    let mut spectrum = vec![0.; 512];
+
+    let n = allocations(|| {
+        for block in signal.chunks_exact(RENDER_QUANTUM) {
+            stream.process(block);
+        }
+        stream.spectrum(&mut spectrum);
+    });
+    assert_eq!(n, 0);
+    assert_eq!(stream.frames(), 5);
+
+    // The shared input block, as an `AudioWorkletProcessor` would use it from JS
+    let n = allocations(|| {
+        for block in signal.chunks_exact(RENDER_QUANTUM) {
+            let ptr = stream.input_ptr();
+            // SAFETY: the shared input buffer holds `RENDER_QUANTUM` samples.
+            unsafe { std::slice::from_raw_parts_mut(ptr, RENDER_QUANTUM) }.copy_from_slice(block);
+            stream.process_shared();
+        }
+    });
+    assert_eq!(n, 0);
+    assert_eq!(stream.frames(), 13);
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_single_bin_trackers_do_not_allocate() {
+    let mut signal = vec![0.; 16 * RENDER_QUANTUM];
+    siggen::dtmf('7', 8000., &mut signal);
+
+    let freqs = [852., 1209.];
+    let mut goertzel = WasmGoertzel::new(&freqs, 8000., 205);
+    let mut sdft = WasmSlidingDft::new(&freqs, 8000., 205);
+    let mut magnitudes = [0.; 2];
+
+    let n = allocations(|| {
+        for block in signal.chunks_exact(RENDER_QUANTUM) {
+            goertzel.process(block);
+            goertzel.magnitudes(&mut magnitudes);
+            sdft.process(block);
+            sdft.magnitudes(&mut magnitudes);
+        }
+        signal.iter().for_each(|&x| {
+            goertzel.push(x);
+            sdft.push(x);
+        });
+    });
+    assert_eq!(n, 0);
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_zoom_fft_does_not_allocate() {
+    let mut signal = vec![0.; 1000];
+    siggen::noise(NoiseColor::White, 3, &mut signal);
+
+    let mut zoom = WasmZoomFft::new(signal.len(), 201, 45., 65., 8000.);
+    let mut output = vec![0.; 201];
+
+    let n = allocations(|| {
+        for _ in 0..3 {
+            zoom.process(&signal, &mut output);
+        }
+    });
+    assert_eq!(n, 0);
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_cqt_does_not_allocate() {
+    let mut signal = vec![0.; 32 * RENDER_QUANTUM];
+    siggen::noise(NoiseColor::Pink, 4, &mut signal);
+
+    let mut cqt = WasmCqt::new(8000., 130.81, 48, 24, 512);
+    let mut spectrum = vec![0.; 48];
+    let mut chroma = [0.; 12];
+
+    let n = allocations(|| {
+        for block in signal.chunks_exact(RENDER_QUANTUM) {
+            cqt.process(block);
+        }
+        cqt.spectrum(&mut spectrum);
+        cqt.chroma(&mut chroma);
+    });
+    assert_eq!(n, 0);
+    assert!(cqt.frames() > 0);
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_octave_bands_do_not_allocate() {
+    let mut signal = vec![0.; 16 * RENDER_QUANTUM];
+    siggen::noise(NoiseColor::Pink, 6, &mut signal);
+
+    let mut analyser = WasmOctaveBands::new(48000., 3, 25., 16000., 2048);
+    let mut bank = WasmOctaveFilterBank::new(48000., 3, 25., 16000., FrequencyWeighting::A);
+    let mut levels = vec![0.; analyser.bands()];
+
+    let n = allocations(|| {
+        analyser.set_weighting(FrequencyWeighting::A);
+        analyser.process(&signal[..2048], &mut levels);
+        for block in signal.chunks_exact(RENDER_QUANTUM) {
+            bank.process(block);
+        }
+        bank.levels(&mut levels);
+        bank.reset();
+    });
+    assert_eq!(n, 0);
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_weighting_does_not_allocate() {
+    let mut signal = vec![0.; 4 * RENDER_QUANTUM];
+    siggen::noise(NoiseColor::White, 7, &mut signal);
+
+    let mut filter = WasmWeightingFilter::new(FrequencyWeighting::Itu468, 48000.);
+    let curve = WasmWeightingCurve::new(FrequencyWeighting::A, 48000., 1024);
+    let mut spectrum = vec![0.; 512];
+
+    let n = allocations(|| {
+        for block in signal.chunks_exact_mut(RENDER_QUANTUM) {
+            filter.process(block);
+        }
+        curve.apply(&mut spectrum);
+    });
+    assert_eq!(n, 0);
+}
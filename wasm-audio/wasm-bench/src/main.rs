@@ -0,0 +1,146 @@
+//! Headless benchmark of the wasm kernels, running the crate in an embedded wasmtime.
+//!
+//! Build the crate for WASI with SIMD enabled first, from `wasm-audio/`:
+//!
+//! ```text
+//! RUSTFLAGS="-C target-feature=+simd128" cargo build --release --target wasm32-wasip1 -p wasm-audio
+//! cargo run --release -p wasm-bench -- [--size N] [--calls N] [PATH.wasm]
+//! ```
+//!
+//! Prints the average time per call of each kernel in the same format as the web overlay.
+
+use std::{path::PathBuf, time::Instant};
+
+use anyhow::{bail, Context, Result};
+use wasmtime::{Caller, Engine, Extern, Instance, Linker, Module, Store};
+
+const WASI_ERRNO_FAULT: u32 = 21;
+
+struct Options {
+    wasm: PathBuf,
+    /// Matches the `AnalyserNode.fftSize` of the web page
+    size: u32,
+    /// Matches the ring buffer size the overlay averages over
+    calls: u32,
+}
+
+impl Options {
+    fn parse() -> Result<Self> {
+        let mut options = Self {
+            wasm: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("../target/wasm32-wasip1/release/wasm_audio.wasm"),
+            size: 1024,
+            calls: 100,
+        };
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--size" => options.size = args.next().context("--size needs a value")?.parse()?,
+                "--calls" => {
+                    options.calls = args.next().context("--calls needs a value")?.parse()?
+                }
+                _ if arg.starts_with("--") => bail!("unknown option {arg}"),
+                _ => options.wasm = arg.into(),
+            }
+        }
+
+        if !options.size.is_power_of_two() || options.size < 2 {
+            bail!("--size must be a power of 2");
+        }
+
+        Ok(options)
+    }
+}
+
+fn kernel_name(store: &mut Store<()>, instance: &Instance, kernel: u32) -> Result<String> {
+    let ptr = instance
+        .get_typed_func::<u32, u32>(&mut *store, "bench_kernel_name_ptr")?
+        .call(&mut *store, kernel)?;
+    let len = instance
+        .get_typed_func::<u32, u32>(&mut *store, "bench_kernel_name_len")?
+        .call(&mut *store, kernel)?;
+
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .context("module has no memory export")?;
+    let bytes = memory
+        .data(&*store)
+        .get(ptr as usize..(ptr + len) as usize)
+        .context("kernel name out of bounds")?;
+
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn main() -> Result<()> {
+    let options = Options::parse()?;
+
+    let engine = Engine::default();
+    let module = Module::from_file(&engine, &options.wasm).with_context(|| {
+        format!(
+            "could not load {} (was the crate built for wasm32-wasip1?)",
+            options.wasm.display()
+        )
+    })?;
+
+    // rustfft's planner seeds a `HashMap`, which asks WASI for random bytes. Any bytes will do.
+    let mut linker = Linker::new(&engine);
+    linker.func_wrap(
+        "wasi_snapshot_preview1",
+        "random_get",
+        |mut caller: Caller<'_, ()>, ptr: u32, len: u32| -> u32 {
+            let Some(Extern::Memory(memory)) = caller.get_export("memory") else {
+                return WASI_ERRNO_FAULT;
+            };
+            match memory
+                .data_mut(&mut caller)
+                .get_mut(ptr as usize..(ptr + len) as usize)
+            {
+                Some(buf) => {
+                    buf.iter_mut()
+                        .enumerate()
+                        .for_each(|(i, b)| *b = (i as u8).wrapping_mul(37));
+                    0
+                }
+                None => WASI_ERRNO_FAULT,
+            }
+        },
+    )?;
+
+    // Otherwise the kernels never call into WASI or the wasm-bindgen JS glue, so those imports
+    // only need to exist. Anything that does reach them (e.g. a panic message) traps.
+    linker.define_unknown_imports_as_traps(&module)?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = linker.instantiate(&mut store, &module)?;
+
+    if let Ok(init) = instance.get_typed_func::<(), ()>(&mut store, "_initialize") {
+        init.call(&mut store, ())?;
+    }
+
+    let kernel_count = instance
+        .get_typed_func::<(), u32>(&mut store, "bench_kernel_count")
+        .context("module has no benchmark exports (was it built for WASI?)")?
+        .call(&mut store, ())?;
+    let prepare = instance.get_typed_func::<u32, ()>(&mut store, "bench_prepare")?;
+    let run = instance.get_typed_func::<(u32, u32), ()>(&mut store, "bench_run")?;
+
+    println!("size: {}, calls: {}", options.size, options.calls);
+
+    for kernel in 0..kernel_count {
+        let name = kernel_name(&mut store, &instance, kernel)?;
+
+        prepare.call(&mut store, options.size)?;
+
+        // Warm up
+        run.call(&mut store, (kernel, (options.calls / 10).max(1)))?;
+
+        let start = Instant::now();
+        run.call(&mut store, (kernel, options.calls))?;
+        let avg_ms = start.elapsed().as_secs_f64() * 1000. / options.calls as f64;
+
+        println!("{name:<5}: {avg_ms:.3} ms");
+    }
+
+    Ok(())
+}